@@ -0,0 +1,932 @@
+// Command-line flag parsing.
+//
+// Flags are parsed by hand from `std::env::args()` so the tool keeps its
+// small dependency footprint. Each flag maps onto a field of `Options`;
+// anything unrecognized is reported back to `main` as an error string.
+use crate::config::{
+    CompletionImputation, CsvQuote, DelayDays, DuplicateHeaders, EfficiencyFormula,
+    IncompleteStatus, JvSplit, Options, PlaceholderGroups, Placeholders, PlausibilityRanges,
+    ReportSort, RoundingMode, ValueRange, YoyBaseline, FUNDING_YEARS,
+};
+use crate::loader;
+use crate::reports;
+use crate::util;
+use std::collections::BTreeMap;
+
+/// Usage text printed when an unknown or malformed flag is given.
+pub const USAGE: &str = "\
+Usage: rust_report [OPTIONS]
+       rust_report serve [--port N] [OPTIONS]
+
+Commands:
+  serve                 Load the dataset once and serve /report1, /report2, /report3 and
+                        /summary as JSON over HTTP on 127.0.0.1 (--port, default 8080)
+
+Options:
+  --version             Print the program version and exit
+  --print-config        Print the version and effective options (flags over the --config file
+                        over defaults) as JSON and exit
+  --config PATH         Read options from a JSON file in the --print-config format; flags
+                        override its values and any option it leaves out keeps its default
+  --input PATH          Load PATH instead of dpwh_flood_control_projects.csv
+                        (a .parquet file is read as Parquet)
+  --clamp-reliability   Clamp negative ReliabilityIndex values to 0 (same as --reliability-floor 0)
+  --reliability-floor X Clamp ReliabilityIndex values below X to X (default: unbounded)
+  --type-horizon TYPE=DAYS
+                        Report 2 reliability horizon for one TypeOfWork instead of the
+                        default 90 days (repeatable)
+  --reliability-cap X   Clamp ReliabilityIndex values above X to X (default 100)
+  --efficiency-formula F
+                        Report 1 raw efficiency: `default` (MedianSavings / AvgDelay)
+                        or `plus-one` (MedianSavings / (AvgDelay + 1))
+  --raw-efficiency      Add the unnormalized efficiency (RawEfficiency) to Report 1, taken
+                        before any --weighted-efficiency or --completeness-weight multiplier
+  --weighted-efficiency Weight Report 1 raw efficiency by each group's share of the national
+                        budget before the 0-100 scaling
+  --completeness-weight Scale Report 1 raw efficiency by the share of a group's projects with
+                        no imputed coordinates or completion date
+  --yoy-baseline B      Report 3 YoY baseline: `per-type` (default) or `global`
+  --smooth-savings      Add a 3-year moving average (SmoothedAvgSavings) to Report 3
+  --dense-report3       List every year x type pair in Report 3, with 0-project rows for gaps
+  --count-drop-pct P    Add a Report 3 CountCollapse column flagging types whose project count
+                        fell by more than P% from the previous year
+  --jv-split S          Report 2 joint ventures (A / B): `single` entity (default), `full`
+                        credit to each firm, or `even` split of cost and savings
+  --sort-report1 KEY[:asc|desc]
+                        Sort Report 1 by a numeric column, e.g. total_budget:asc
+                        (default direction desc; same as --sort report1:TotalBudget:...)
+  --sort SPEC           Re-sort a report before output, e.g. report1:AvgDelay:asc
+                        (repeatable; the first --sort for a report is the primary key)
+  --placeholder-groups P
+                        Groups formed by a missing-value placeholder (e.g. the Unknown
+                        region): `keep` (default), `exclude` or `suffix` (`Unknown (imputed)`)
+  --placeholder FIELD=VALUE
+                        Placeholder for an empty region, main_island, province,
+                        type_of_work or contractor (defaults: Unknown, Unspecified for
+                        type_of_work, Unknown Contractor)
+  --expect reportN=ROWS[,...]
+                        Report an error after generating if a report's row count differs,
+                        e.g. report1=17,report2=15 (repeatable); exits with status 1 when
+                        the menu choices come from a pipe or file rather than a terminal
+  --currency-rounding R Rounding of peso amounts: `half-even` (default, as before) or `half-up`
+                        (commercial, e.g. 2.675 -> 2.68)
+  --strict-bounds       Abort report generation if a percentage column leaves [0, 100]
+  --geojson             Also write projects with coordinates to projects.geojson
+  --geojson-exclude-imputed
+                        Leave out points placed at the provincial capital or a province average
+  --as-of DATE          Count summary.json's days_since_latest_completion (and date the
+                        --csv-metadata line) from DATE (YYYY-MM-DD) instead of today
+  --by-region           Also write each region's summary and projects to by_region/<region>.json
+  --export-clean        Also write every cleaned record to clean_records.csv (stable row order)
+  --flag-exact-budget   Count projects whose ContractCost equals the approved budget exactly
+                        (in summary.json) and flag them in clean_records.csv
+  --json-reports        Also write each report as a JSON array of objects (.json)
+  --fixed-width         Also write each report as a fixed-width .txt table
+  --prometheus          Also write summary and load metrics in Prometheus text format to metrics.prom
+  --sqlite PATH         Also write the clean records and reports to a SQLite database
+  --compact-summary     Also print total savings in compact form (e.g. ₱1.23B)
+  --throughput          After loading, print how many rows were parsed per second
+  --stats               Print the ReliabilityIndex distribution over all qualifying contractors
+  --explain             Print the formula and parameters behind each report
+  --csv-metadata        Start each CSV with a `# generated ...` line listing rows and parameters
+  --csv-delimiter C     Field delimiter for CSV outputs: one character, or `tab` (default ,)
+  --append-csv          Append rows to existing CSV outputs (header must match) instead of overwriting
+  --csv-quote Q         Quote `necessary` fields only (default) or `always`
+  --output-prefix NAME  Prefix every output file name with NAME_
+  --timestamp-outputs   Prefix output file names with the run's local date and time
+  --preview-rows N      Show N rows in each console preview
+  --preview-sample      Preview rows spread evenly from first to last instead of the first N
+  --preview-significant Preview only significant rows: EfficiencyScore (Report 1) or YoYChange
+                        (Report 3) over one standard deviation from the mean, and
+                        high-risk contractors (Report 2)
+  --preview-width N     Truncate text cells in previews to N characters (with …)
+  --preview-align-numbers
+                        Right-align numeric columns in previews
+  --previews-md         Also write all previews and the summary to previews.md
+  --markdown-report     Also write report.md: summary bullets and the top rows
+                        of each report (--preview-rows sets how many)
+  --page-size N         Rows per page when paging through a report from the menu (default 10)
+  --extremes N          Regions listed on each side of the top/bottom view (default 3)
+  --merge-candidates N  Write clusters of contractor names within edit distance N
+  --cost-outliers K     Write records with ContractCost outside [Q1 - K*IQR, Q3 + K*IQR]
+                        (K = 1.5 is the usual choice) to report_cost_outliers.csv
+  --cost-outliers-by-type
+                        Compute the --cost-outliers fences per TypeOfWork
+  --worsening-contractors
+                        Write contractors whose average delay rose each year 2021-2023
+                        to report_worsening_contractors.csv
+  --single-region-contractors
+                        Write contractors whose projects are all in one region (or at most
+                        --max-contractor-regions N regions) to single_region_contractors.csv
+  --what-if NAME=FACTOR Recompute the summary and Report 2 with one contractor's contract costs
+                        multiplied by FACTOR (e.g. \"ABC CORP=1.1\") and print the change
+  --compare PATH        Compare Report 1 region ranks against a previous-period dataset
+  --changes-since DIR   Compare each report with the report CSVs of a previous run in DIR and
+                        write only new, changed and removed rows to <report>_changes.csv
+  --per-year            Also compute Reports 1 and 2 for each funding year alone, written to
+                        report1_<year>.csv and report2_<year>.csv
+  --side-by-side PATH   Also build Report 1 for PATH and write both datasets' rows, tagged by
+                        file name, to report1_by_source.csv
+  --deflators LIST      Comma-separated YEAR=INDEX price indices (covering 2021-2023);
+                        money columns are restated in base-year pesos
+  --deflator-base YEAR  Base year for --deflators (default: earliest year listed)
+  --completion-fallback F
+                        Missing ActualCompletionDate: `start-date` (default, delay 0),
+                        `type-median` (start + median duration of the type) or `drop`
+  --incomplete-status P Rows whose Status column is not Completed (e.g. Not Yet Started): `keep`
+                        (default), `exclude-delay` (left out of delay averages) or `drop`
+  --duplicate-headers P Two columns with the same header: `error` (default, refuse to load),
+                        `first` or `last` (read the leftmost or rightmost of them)
+  --delay-days U        Count delays in `calendar` days (default) or `working` days (Mon-Fri)
+  --max-imputed-pct X   Reject the load if more than X% of retained rows have imputed coordinates
+                        (serve exits with status 1; the menu stays open)
+  --fix-swapped-budget F
+                        Swap ApprovedBudgetForContract and ContractCost back on rows whose cost
+                        is more than F times the budget (e.g. 10), when both stay within --range
+  --max-duration-days N Count projects that took more than N days from start to completion
+  --exclude-long-duration
+                        Leave those projects out of delay averages (needs --max-duration-days)
+  --range FIELD=MIN:MAX Drop rows whose approved_budget, contract_cost or completion_delay_days
+                        is outside [MIN, MAX]; leave MIN or MAX empty for no bound
+                        (e.g. completion_delay_days=:10000; repeatable)
+  --log-rejected N      Write up to N distinct numeric cell values that failed to parse, with
+                        counts, to rejected_formats.csv
+  --column-map PATH     JSON object mapping DPWH column names to this file's headers,
+                        e.g. {\"ApprovedBudgetForContract\": \"budget\"}
+  --skip-units-row      Discard the first data row of a CSV (a row of units under the header)
+  --comment-char C      Skip CSV input lines starting with C (e.g. #) as comments
+  --infer-island        Fill a missing MainIsland from the region (e.g. Region VII -> Visayas)
+  --strip-units LIST    Comma-separated trailing units to strip from numeric cells (e.g. PHP,days)
+  --strip-currency LIST Comma-separated currency prefixes to strip from budget and cost cells
+                        (e.g. ₱,PHP)";
+
+/// Parse the command-line arguments (without the program name) into
+/// `Options`, starting from the `--config` file if one is given and from
+/// the defaults otherwise. Flags always win over the file; repeatable
+/// flags (e.g. `--sort`) add to the file's entries.
+pub fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args: Vec<String> = args.into_iter().collect();
+    let mut opts = match args.iter().position(|a| a == "--config") {
+        Some(i) => {
+            let path = args
+                .get(i + 1)
+                .ok_or_else(|| "Missing value for --config".to_string())?;
+            Options::from_json_file(path)
+                .map_err(|e| format!("Invalid --config file {}: {}", path, e))?
+        }
+        None => Options::default(),
+    };
+    let mut args = args.into_iter();
+    let mut serve = false;
+    let mut port: Option<u16> = None;
+    let mut max_regions_set = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "serve" => serve = true,
+            "--port" => port = Some(parse_number(&next_value(&mut args, &arg)?, &arg)?),
+            "--version" => opts.print_version = true,
+            "--print-config" => opts.print_config = true,
+            // Already read above, before any other flag.
+            "--config" => {
+                next_value(&mut args, &arg)?;
+            }
+            "--input" => opts.load.input_path = Some(next_value(&mut args, &arg)?),
+            "--clamp-reliability" => opts.report.reliability_floor = Some(0.0),
+            "--reliability-floor" => {
+                opts.report.reliability_floor =
+                    Some(parse_number(&next_value(&mut args, &arg)?, &arg)?)
+            }
+            "--type-horizon" => parse_type_horizon(
+                &next_value(&mut args, &arg)?,
+                &mut opts.report.type_horizons,
+            )?,
+            "--reliability-cap" => {
+                opts.report.reliability_cap = parse_number(&next_value(&mut args, &arg)?, &arg)?
+            }
+            "--stats" => opts.output.stats = true,
+            "--explain" => opts.output.explain = true,
+            "--compact-summary" => opts.output.compact_summary = true,
+            "--throughput" => opts.output.throughput = true,
+            "--delay-days" => {
+                opts.load.delay_days = match next_value(&mut args, &arg)?.as_str() {
+                    "calendar" => DelayDays::Calendar,
+                    "working" => DelayDays::Working,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected calendar or working)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--completion-fallback" => {
+                opts.load.completion_imputation = match next_value(&mut args, &arg)?.as_str() {
+                    "start-date" => CompletionImputation::StartDate,
+                    "type-median" => CompletionImputation::TypeMedianDuration,
+                    "drop" => CompletionImputation::Drop,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected start-date, type-median or drop)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--incomplete-status" => {
+                opts.load.incomplete_status = match next_value(&mut args, &arg)?.as_str() {
+                    "keep" => IncompleteStatus::Keep,
+                    "exclude-delay" => IncompleteStatus::ExcludeDelay,
+                    "drop" => IncompleteStatus::Drop,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected keep, exclude-delay or drop)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--duplicate-headers" => {
+                opts.load.duplicate_headers = match next_value(&mut args, &arg)?.as_str() {
+                    "error" => DuplicateHeaders::Error,
+                    "first" => DuplicateHeaders::FirstWins,
+                    "last" => DuplicateHeaders::LastWins,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected error, first or last)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--max-imputed-pct" => {
+                let pct: f64 = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if !(0.0..=100.0).contains(&pct) {
+                    return Err(format!(
+                        "Invalid value for {}: {} (expected 0-100)",
+                        arg, pct
+                    ));
+                }
+                opts.load.max_imputed_pct = Some(pct);
+            }
+            "--fix-swapped-budget" => {
+                let factor: f64 = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if factor <= 1.0 {
+                    return Err(format!(
+                        "Invalid value for {}: {} (expected a factor above 1, e.g. 10)",
+                        arg, factor
+                    ));
+                }
+                opts.load.swap_factor = Some(factor);
+            }
+            "--max-duration-days" => {
+                let days: f64 = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if days <= 0.0 {
+                    return Err(format!(
+                        "Invalid value for {}: {} (expected a positive number of days)",
+                        arg, days
+                    ));
+                }
+                opts.load.max_duration_days = Some(days);
+            }
+            "--exclude-long-duration" => opts.load.exclude_long_duration = true,
+            "--range" => parse_range(&next_value(&mut args, &arg)?, &mut opts.load.plausibility)?,
+            "--log-rejected" => {
+                opts.load.rejected_formats_limit =
+                    Some(parse_number(&next_value(&mut args, &arg)?, &arg)?)
+            }
+            "--column-map" => {
+                opts.load.column_map = parse_column_map(&next_value(&mut args, &arg)?)?
+            }
+            "--skip-units-row" => opts.load.skip_units_row = true,
+            "--comment-char" => {
+                let value = next_value(&mut args, &arg)?;
+                opts.load.comment_char = match value.as_str() {
+                    v if v.len() == 1 && !matches!(v, "," | "\"" | "\n" | "\r") => {
+                        Some(v.as_bytes()[0])
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected a single ASCII character \
+                             other than , or \")",
+                            arg, value
+                        ))
+                    }
+                };
+            }
+            "--strip-currency" => {
+                opts.load.currency_prefixes = next_value(&mut args, &arg)?
+                    .split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect();
+            }
+            "--infer-island" => opts.load.infer_main_island = true,
+            "--strip-units" => {
+                opts.load.unit_suffixes = next_value(&mut args, &arg)?
+                    .split(',')
+                    .map(|u| u.trim().to_string())
+                    .filter(|u| !u.is_empty())
+                    .collect();
+            }
+            "--deflators" => {
+                opts.load.deflators = parse_deflators(&next_value(&mut args, &arg)?, &arg)?
+            }
+            "--deflator-base" => {
+                opts.load.deflator_base_year =
+                    Some(parse_number(&next_value(&mut args, &arg)?, &arg)?)
+            }
+            "--append-csv" => opts.output.append_csv = true,
+            "--csv-metadata" => opts.output.csv_metadata = true,
+            "--csv-delimiter" => {
+                let value = next_value(&mut args, &arg)?;
+                opts.output.csv_format.delimiter = match value.as_str() {
+                    "tab" | "\\t" => b'\t',
+                    v if v.len() == 1 && v != "\"" && v != "\n" && v != "\r" => v.as_bytes()[0],
+                    _ => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected a single ASCII character or tab)",
+                            arg, value
+                        ))
+                    }
+                };
+            }
+            "--csv-quote" => {
+                opts.output.csv_format.quote = match next_value(&mut args, &arg)?.as_str() {
+                    "necessary" => CsvQuote::Necessary,
+                    "always" => CsvQuote::Always,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected necessary or always)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--output-prefix" => opts.output.prefix = next_value(&mut args, &arg)?,
+            "--timestamp-outputs" => opts.output.timestamp_outputs = true,
+            "--preview-rows" => {
+                opts.output.preview_rows = Some(parse_number(&next_value(&mut args, &arg)?, &arg)?)
+            }
+            "--preview-sample" => opts.output.preview_sampled = true,
+            "--preview-significant" => opts.output.preview_significant = true,
+            "--preview-width" => {
+                opts.output.preview_layout.max_text_width =
+                    Some(parse_number(&next_value(&mut args, &arg)?, &arg)?)
+            }
+            "--preview-align-numbers" => opts.output.preview_layout.right_align_numbers = true,
+            "--previews-md" => opts.output.previews_markdown = true,
+            "--markdown-report" => opts.output.markdown_report = true,
+            "--extremes" => {
+                opts.output.extremes_count = parse_number(&next_value(&mut args, &arg)?, &arg)?
+            }
+            "--page-size" => {
+                let size: usize = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if size == 0 {
+                    return Err(format!(
+                        "Invalid value for {}: 0 (expected at least 1 row per page)",
+                        arg
+                    ));
+                }
+                opts.output.page_size = size;
+            }
+            "--merge-candidates" => {
+                opts.output.merge_candidates_distance =
+                    Some(parse_number(&next_value(&mut args, &arg)?, &arg)?)
+            }
+            "--cost-outliers" => {
+                let k: f64 = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if !(k >= 0.0 && k.is_finite()) {
+                    return Err(format!(
+                        "Invalid value for {}: {} (expected a non-negative number)",
+                        arg, k
+                    ));
+                }
+                opts.output.cost_outlier_fence = Some(k);
+            }
+            "--worsening-contractors" => opts.output.worsening_contractors = true,
+            "--single-region-contractors" => opts.output.single_region_contractors = true,
+            "--max-contractor-regions" => {
+                let max: usize = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if max == 0 {
+                    return Err(format!(
+                        "Invalid value for {}: 0 (expected at least 1 region)",
+                        arg
+                    ));
+                }
+                opts.output.max_contractor_regions = max;
+                max_regions_set = true;
+            }
+            "--cost-outliers-by-type" => opts.output.cost_outliers_by_type = true,
+            "--what-if" => {
+                opts.output.what_if = Some(parse_what_if(&next_value(&mut args, &arg)?)?)
+            }
+            "--compare" => opts.output.compare_path = Some(next_value(&mut args, &arg)?),
+            "--changes-since" => opts.output.changes_since = Some(next_value(&mut args, &arg)?),
+            "--per-year" => opts.output.per_year = true,
+            "--side-by-side" => opts.output.side_by_side_path = Some(next_value(&mut args, &arg)?),
+            "--efficiency-formula" => {
+                opts.report.efficiency_formula = match next_value(&mut args, &arg)?.as_str() {
+                    "default" => EfficiencyFormula::ZeroWhenNoDelay,
+                    "plus-one" => EfficiencyFormula::PlusOneDay,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected default or plus-one)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--yoy-baseline" => {
+                opts.report.yoy_baseline = match next_value(&mut args, &arg)?.as_str() {
+                    "per-type" => YoyBaseline::PerType,
+                    "global" => YoyBaseline::GlobalPerYear,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected per-type or global)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--placeholder-groups" => {
+                opts.report.placeholder_groups = match next_value(&mut args, &arg)?.as_str() {
+                    "keep" => PlaceholderGroups::Keep,
+                    "exclude" => PlaceholderGroups::Exclude,
+                    "suffix" => PlaceholderGroups::Suffix,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected keep, exclude or suffix)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--placeholder" => {
+                parse_placeholder(&next_value(&mut args, &arg)?, &mut opts.load.placeholders)?
+            }
+            "--smooth-savings" => opts.report.smooth_savings = true,
+            "--raw-efficiency" => opts.report.raw_efficiency = true,
+            "--weighted-efficiency" => opts.report.weighted_efficiency = true,
+            "--completeness-weight" => opts.report.completeness_weighted = true,
+            "--dense-report3" => opts.report.dense_report3 = true,
+            "--count-drop-pct" => {
+                let pct: f64 = parse_number(&next_value(&mut args, &arg)?, &arg)?;
+                if !(0.0..=100.0).contains(&pct) {
+                    return Err(format!(
+                        "Invalid value for {}: {} (expected 0-100)",
+                        arg, pct
+                    ));
+                }
+                opts.report.count_drop_pct = Some(pct);
+            }
+            "--jv-split" => {
+                opts.report.jv_split = match next_value(&mut args, &arg)?.as_str() {
+                    "single" => JvSplit::Single,
+                    "full" => JvSplit::FullCredit,
+                    "even" => JvSplit::SplitEvenly,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected single, full or even)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--sort-report1" => opts
+                .output
+                .sorts
+                .push(parse_sort_report1(&next_value(&mut args, &arg)?)?),
+            "--sort" => opts
+                .output
+                .sorts
+                .push(parse_sort(&next_value(&mut args, &arg)?)?),
+            "--expect" => {
+                for spec in next_value(&mut args, &arg)?.split(',') {
+                    let (report, rows) = parse_expect(spec)?;
+                    opts.output.expected_rows.insert(report, rows);
+                }
+            }
+            "--currency-rounding" => {
+                opts.report.currency_rounding = match next_value(&mut args, &arg)?.as_str() {
+                    "half-even" => RoundingMode::HalfEven,
+                    "half-up" => RoundingMode::HalfUp,
+                    other => {
+                        return Err(format!(
+                            "Invalid value for {}: {} (expected half-even or half-up)",
+                            arg, other
+                        ))
+                    }
+                };
+            }
+            "--strict-bounds" => opts.report.strict_bounds = true,
+            "--json-reports" => opts.output.json_reports = true,
+            "--export-clean" => opts.output.export_clean = true,
+            "--flag-exact-budget" => opts.output.flag_exact_budget = true,
+            "--prometheus" => opts.output.prometheus = true,
+            "--geojson" => opts.output.geojson = true,
+            "--geojson-exclude-imputed" => opts.output.geojson_exclude_imputed = true,
+            "--by-region" => opts.output.by_region = true,
+            "--as-of" => {
+                let value = next_value(&mut args, &arg)?;
+                opts.output.as_of = Some(util::parse_date_safe(Some(&value)).ok_or_else(|| {
+                    format!("Invalid value for {}: {} (expected YYYY-MM-DD)", arg, value)
+                })?);
+            }
+            "--fixed-width" => opts.output.fixed_width = true,
+            "--sqlite" => opts.output.sqlite_path = Some(next_value(&mut args, &arg)?),
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+    if let Some(floor) = opts.report.reliability_floor {
+        if floor > opts.report.reliability_cap {
+            return Err(format!(
+                "--reliability-floor {} is above --reliability-cap {}",
+                floor, opts.report.reliability_cap
+            ));
+        }
+    }
+    if !opts.load.deflators.is_empty() {
+        let base = opts.load.deflator_base().unwrap_or(2021);
+        for year in FUNDING_YEARS.chain(std::iter::once(base)) {
+            if !opts.load.deflators.contains_key(&year) {
+                return Err(format!("--deflators has no index for {}", year));
+            }
+        }
+    } else if opts.load.deflator_base_year.is_some() {
+        return Err("--deflator-base requires --deflators".to_string());
+    }
+    if serve {
+        opts.serve_port = Some(port.unwrap_or(8080));
+    } else if port.is_some() {
+        return Err("--port requires serve".to_string());
+    }
+    if opts.output.cost_outliers_by_type && opts.output.cost_outlier_fence.is_none() {
+        return Err("--cost-outliers-by-type requires --cost-outliers".to_string());
+    }
+    if max_regions_set && !opts.output.single_region_contractors {
+        return Err("--max-contractor-regions requires --single-region-contractors".to_string());
+    }
+    if opts.load.exclude_long_duration && opts.load.max_duration_days.is_none() {
+        return Err("--exclude-long-duration requires --max-duration-days".to_string());
+    }
+    Ok(opts)
+}
+
+/// Parse one `--type-horizon TYPE=DAYS` entry into `horizons`. The type is
+/// split at the last `=` and must match TypeOfWork exactly.
+fn parse_type_horizon(value: &str, horizons: &mut BTreeMap<String, f64>) -> Result<(), String> {
+    let invalid = || {
+        format!(
+            "Invalid value for --type-horizon: {} (expected TYPE=DAYS with a positive DAYS)",
+            value
+        )
+    };
+    let (type_of_work, days) = value.rsplit_once('=').ok_or_else(invalid)?;
+    let days: f64 = days.trim().parse().map_err(|_| invalid())?;
+    if type_of_work.trim().is_empty() || !(days > 0.0 && days.is_finite()) {
+        return Err(invalid());
+    }
+    horizons.insert(type_of_work.trim().to_string(), days);
+    Ok(())
+}
+
+/// Parse a `--placeholder FIELD=VALUE` spec into `placeholders`.
+fn parse_placeholder(value: &str, placeholders: &mut Placeholders) -> Result<(), String> {
+    let invalid = || {
+        format!(
+            "Invalid value for --placeholder: {} (expected FIELD=VALUE with FIELD one of \
+             region, main_island, province, type_of_work, contractor)",
+            value
+        )
+    };
+    let (field, text) = value.split_once('=').ok_or_else(invalid)?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    let slot = match field.trim() {
+        "region" => &mut placeholders.region,
+        "main_island" => &mut placeholders.main_island,
+        "province" => &mut placeholders.province,
+        "type_of_work" => &mut placeholders.type_of_work,
+        "contractor" => &mut placeholders.contractor,
+        _ => return Err(invalid()),
+    };
+    *slot = text.to_string();
+    Ok(())
+}
+
+/// Parse `--what-if CONTRACTOR=FACTOR`. The name is split at the last `=`
+/// so names containing one still work.
+fn parse_what_if(value: &str) -> Result<(String, f64), String> {
+    let invalid = || {
+        format!(
+            "Invalid value for --what-if: {} (expected CONTRACTOR=FACTOR with a non-negative \
+             FACTOR, e.g. \"ABC CORP=1.1\")",
+            value
+        )
+    };
+    let (name, factor) = value.rsplit_once('=').ok_or_else(invalid)?;
+    let factor: f64 = factor.trim().parse().map_err(|_| invalid())?;
+    if name.trim().is_empty() || !(factor >= 0.0 && factor.is_finite()) {
+        return Err(invalid());
+    }
+    Ok((name.trim().to_string(), factor))
+}
+
+/// Parse a `--range FIELD=MIN:MAX` value into the matching field of
+/// `ranges`. An empty MIN or MAX leaves that side open.
+fn parse_range(value: &str, ranges: &mut PlausibilityRanges) -> Result<(), String> {
+    let invalid = || {
+        format!(
+            "Invalid value for --range: {} (expected FIELD=MIN:MAX with FIELD one of \
+             approved_budget, contract_cost, completion_delay_days)",
+            value
+        )
+    };
+    let (field, bounds) = value.split_once('=').ok_or_else(invalid)?;
+    let (min, max) = bounds.split_once(':').ok_or_else(invalid)?;
+    let bound = |s: &str| -> Result<Option<f64>, String> {
+        match s.trim() {
+            "" => Ok(None),
+            s => s.parse::<f64>().map(Some).map_err(|_| invalid()),
+        }
+    };
+    let range = ValueRange {
+        min: bound(min)?,
+        max: bound(max)?,
+    };
+    if let (Some(min), Some(max)) = (range.min, range.max) {
+        if min > max {
+            return Err(format!(
+                "Invalid value for --range: {} (MIN is above MAX)",
+                value
+            ));
+        }
+    }
+    let slot = match field.trim() {
+        "approved_budget" => &mut ranges.approved_budget,
+        "contract_cost" => &mut ranges.contract_cost,
+        "completion_delay_days" => &mut ranges.completion_delay_days,
+        _ => return Err(invalid()),
+    };
+    *slot = range;
+    Ok(())
+}
+
+/// Parse a `--sort-report1` spec like `total_budget:asc` into the
+/// equivalent Report 1 `ReportSort`. Only numeric columns are accepted.
+fn parse_sort_report1(value: &str) -> Result<ReportSort, String> {
+    let (key, direction) = match value.split_once(':') {
+        Some((key, direction)) => (key.trim(), direction.trim()),
+        None => (value.trim(), "desc"),
+    };
+    let column = reports::REPORT1_SORT_KEYS
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, header)| header.to_string())
+        .ok_or_else(|| {
+            let keys: Vec<&str> = reports::REPORT1_SORT_KEYS.iter().map(|(k, _)| *k).collect();
+            format!(
+                "Invalid column for --sort-report1: {} (expected one of: {})",
+                key,
+                keys.join(", ")
+            )
+        })?;
+    let descending = match direction {
+        "asc" => false,
+        "desc" => true,
+        other => {
+            return Err(format!(
+                "Invalid direction for --sort-report1: {} (expected asc or desc)",
+                other
+            ))
+        }
+    };
+    Ok(ReportSort {
+        report: 1,
+        column,
+        descending,
+    })
+}
+
+/// Parse one `--expect` item like `report1=17` into (report, rows).
+fn parse_expect(spec: &str) -> Result<(usize, usize), String> {
+    let invalid = || {
+        format!(
+            "Invalid value for --expect: {} (expected reportN=ROWS with N 1, 2 or 3)",
+            spec
+        )
+    };
+    let (report, rows) = spec.split_once('=').ok_or_else(invalid)?;
+    let report = report
+        .trim()
+        .strip_prefix("report")
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| (1..=3).contains(n))
+        .ok_or_else(invalid)?;
+    let rows = rows.trim().parse::<usize>().map_err(|_| invalid())?;
+    Ok((report, rows))
+}
+
+/// Parse and validate a `--sort` spec like `report1:AvgDelay:asc`.
+fn parse_sort(value: &str) -> Result<ReportSort, String> {
+    let parts: Vec<&str> = value.split(':').map(str::trim).collect();
+    let [report, column, direction] = parts[..] else {
+        return Err(format!(
+            "Invalid value for --sort: {} (expected report:column:asc|desc)",
+            value
+        ));
+    };
+    let report_num = report
+        .strip_prefix("report")
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| (1..=3).contains(n))
+        .ok_or_else(|| {
+            format!(
+                "Invalid report in --sort: {} (expected report1, report2 or report3)",
+                report
+            )
+        })?;
+    let columns = reports::report_columns(report_num).unwrap_or_default();
+    if !columns.iter().any(|c| c == column) {
+        return Err(format!(
+            "Unknown column in --sort: {} has no {} column (expected one of: {})",
+            report,
+            column,
+            columns.join(", ")
+        ));
+    }
+    let descending = match direction.to_ascii_lowercase().as_str() {
+        "asc" => false,
+        "desc" => true,
+        _ => {
+            return Err(format!(
+                "Invalid direction in --sort: {} (expected asc or desc)",
+                direction
+            ))
+        }
+    };
+    Ok(ReportSort {
+        report: report_num,
+        column: column.to_string(),
+        descending,
+    })
+}
+
+/// Parse a deflator table like `2021=100,2022=105.8,2023=112.3`.
+fn parse_deflators(value: &str, flag: &str) -> Result<BTreeMap<i32, f64>, String> {
+    let mut table = BTreeMap::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (year, index) = entry.split_once('=').ok_or_else(|| {
+            format!(
+                "Invalid value for {}: {} (expected YEAR=INDEX)",
+                flag, entry
+            )
+        })?;
+        let index: f64 = parse_number(index, flag)?;
+        if index <= 0.0 {
+            return Err(format!(
+                "Invalid value for {}: {} (index must be positive)",
+                flag, entry
+            ));
+        }
+        table.insert(parse_number(year, flag)?, index);
+    }
+    Ok(table)
+}
+
+/// Read a `--column-map` JSON file: an object from canonical column names
+/// to the input file's own headers.
+fn parse_column_map(path: &str) -> Result<BTreeMap<String, String>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read --column-map {}: {}", path, e))?;
+    let map: BTreeMap<String, String> = serde_json::from_str(&text).map_err(|e| {
+        format!(
+            "Invalid --column-map {}: {} (expected a JSON object of strings)",
+            path, e
+        )
+    })?;
+    if let Some(unknown) = map
+        .keys()
+        .find(|k| !loader::CANONICAL_COLUMNS.contains(&k.as_str()))
+    {
+        return Err(format!(
+            "Invalid --column-map {}: unknown column {} (expected one of: {})",
+            path,
+            unknown,
+            loader::CANONICAL_COLUMNS.join(", ")
+        ));
+    }
+    Ok(map)
+}
+
+/// Pull the value that follows a flag like `--sqlite PATH`.
+fn next_value<I>(args: &mut I, flag: &str) -> Result<String, String>
+where
+    I: Iterator<Item = String>,
+{
+    args.next()
+        .ok_or_else(|| format!("Missing value for {}", flag))
+}
+
+/// Parse a flag value as a number, naming the flag in the error message.
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn flags_override_the_config_file_over_defaults() {
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        write!(
+            file,
+            r#"{{"version": "0.0.0",
+                "report": {{"reliability_cap": 80.0, "risk_threshold": 10.0}},
+                "output": {{"csv_format": {{"delimiter": "|", "quote": "Always"}}}}}}"#
+        )
+        .unwrap();
+        let path = file.path().to_str().unwrap();
+
+        let opts = parse_args(args(&["--config", path])).unwrap();
+        assert_eq!(opts.report.reliability_cap, 80.0);
+        assert_eq!(opts.report.risk_threshold, 10.0);
+        assert_eq!(opts.output.csv_format.delimiter, b'|');
+        assert_eq!(opts.output.csv_format.quote, CsvQuote::Always);
+        // Left out of the file: still the default.
+        assert_eq!(opts.report.top_contractors, 15);
+
+        // A flag wins over the file, wherever it appears.
+        let opts = parse_args(args(&["--reliability-cap", "70", "--config", path])).unwrap();
+        assert_eq!(opts.report.reliability_cap, 70.0);
+        assert_eq!(opts.report.risk_threshold, 10.0);
+
+        assert!(parse_args(args(&["--config"])).is_err());
+        assert!(parse_args(args(&["--config", "/nonexistent/options.json"]))
+            .unwrap_err()
+            .starts_with("Invalid --config file"));
+    }
+
+    #[test]
+    fn printed_config_reads_back_unchanged() {
+        let opts = parse_args(args(&[
+            "--comment-char",
+            "#",
+            "--csv-delimiter",
+            "tab",
+            "--deflators",
+            "2021=100,2022=105,2023=110",
+            "--sort",
+            "report1:AvgDelay:asc",
+            "--as-of",
+            "2025-06-30",
+        ]))
+        .unwrap();
+        let json = serde_json::to_string(&opts).unwrap();
+        let back: Options = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.load.comment_char, Some(b'#'));
+        assert_eq!(back.output.csv_format.delimiter, b'\t');
+    }
+
+    #[test]
+    fn printed_config_reflects_an_overridden_option() {
+        let printed = |flags: &[&str]| -> serde_json::Value {
+            let opts = parse_args(args(flags)).unwrap();
+            assert!(opts.print_config);
+            serde_json::from_str(&opts.effective_config_json().unwrap()).unwrap()
+        };
+        let defaults = printed(&["--print-config"]);
+        assert_eq!(defaults["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(defaults["report"]["reliability_cap"], 100.0);
+        assert_eq!(defaults["load"]["delay_days"], "Calendar");
+
+        let overridden = printed(&[
+            "--reliability-cap",
+            "80",
+            "--delay-days",
+            "working",
+            "--print-config",
+        ]);
+        assert_eq!(overridden["report"]["reliability_cap"], 80.0);
+        assert_eq!(overridden["load"]["delay_days"], "Working");
+        assert_eq!(
+            overridden["report"]["risk_threshold"],
+            defaults["report"]["risk_threshold"]
+        );
+    }
+}
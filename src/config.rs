@@ -0,0 +1,914 @@
+// Run-time configuration for the pipeline.
+//
+// Every knob that changes how reports are computed lives here with a
+// `Default` impl that reproduces the original fixed behavior. The values
+// are read from an optional JSON config file and command-line flags by
+// `cli.rs` and stored in `APP_STATE` so both menu options see the same
+// settings.
+use crate::error::PipelineError;
+use crate::util::{days_diff, working_days_diff};
+use chrono::NaiveDate;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// The FundingYear values the analysis covers; the loader drops rows
+/// from any other year.
+pub const FUNDING_YEARS: RangeInclusive<i32> = 2021..=2023;
+
+/// The dataset loaded when `--input` does not name another file.
+pub const DEFAULT_INPUT_PATH: &str = "dpwh_flood_control_projects.csv";
+
+/// Parameters that affect how the CSV is loaded and cleaned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadOptions {
+    /// Trailing unit tokens (e.g. `PHP`, `days`) that may be stripped from
+    /// numeric cells before parsing. Empty by default, which keeps the
+    /// strict behavior of rejecting any value containing letters.
+    pub unit_suffixes: Vec<String>,
+    /// Leading currency symbols or codes (e.g. `₱`, `PHP`) that may be
+    /// stripped from budget and cost cells before parsing. Empty by
+    /// default, like `unit_suffixes`.
+    pub currency_prefixes: Vec<String>,
+    /// Dataset to load instead of `DEFAULT_INPUT_PATH`. A
+    /// `.parquet` extension selects the Parquet reader.
+    pub input_path: Option<String>,
+    /// When `true`, the first data row of a CSV (a second header-like row
+    /// of units such as `PHP` or `days` in some exports) is discarded
+    /// without being counted. Off by default.
+    pub skip_units_row: bool,
+    /// When set, CSV lines starting with this byte (e.g. `#`) are skipped
+    /// as comments, before or after the header. `None` by default, so
+    /// such lines are read as data.
+    #[serde(
+        serialize_with = "serialize_comment_char",
+        deserialize_with = "deserialize_comment_char"
+    )]
+    pub comment_char: Option<u8>,
+    /// Price index per funding year (e.g. CPI). When non-empty, budgets,
+    /// costs and savings are restated in constant `deflator_base_year`
+    /// pesos while loading. Empty by default (no adjustment).
+    pub deflators: BTreeMap<i32, f64>,
+    /// The year whose prices the deflated values are expressed in. `None`
+    /// uses the earliest year in `deflators`.
+    pub deflator_base_year: Option<i32>,
+    /// Values written into empty text fields while cleaning.
+    pub placeholders: Placeholders,
+    /// What to do with rows that have no usable `ActualCompletionDate`.
+    pub completion_imputation: CompletionImputation,
+    /// Whether delays count calendar days or working days.
+    pub delay_days: DelayDays,
+    /// What to do with rows whose `Status` is not completed.
+    pub incomplete_status: IncompleteStatus,
+    /// What to do when two columns share a header.
+    pub duplicate_headers: DuplicateHeaders,
+    /// Canonical DPWH column name (e.g. `ApprovedBudgetForContract`) ->
+    /// the header that holds it in the input file, for datasets that name
+    /// their columns differently. Empty by default (DPWH names).
+    pub column_map: BTreeMap<String, String>,
+    /// When set, a load whose share of rows with imputed coordinates
+    /// (provincial capital or province average) is above this percentage
+    /// is rejected (see `loader::check_imputed_coords`).
+    pub max_imputed_pct: Option<f64>,
+    /// Projects whose StartDate-to-ActualCompletionDate duration is above
+    /// this many days are counted in `LoadReport::long_duration_rows`.
+    pub max_duration_days: Option<f64>,
+    /// Leave the delays of those projects out of delay averages (the rows
+    /// themselves are kept).
+    pub exclude_long_duration: bool,
+    /// Rows with a value outside these ranges are dropped.
+    pub plausibility: PlausibilityRanges,
+    /// When set, a row whose ContractCost is more than this many times its
+    /// ApprovedBudgetForContract is taken to have the two transposed and
+    /// is swapped back, provided both values are within `plausibility`
+    /// once swapped. `None` (the default) leaves every row as read.
+    pub swap_factor: Option<f64>,
+    /// When `true`, a missing `MainIsland` (empty or equal to the
+    /// placeholder) is filled from the region via `util::island_for_region`
+    /// before grouping. Regions not in that table keep the placeholder.
+    pub infer_main_island: bool,
+    /// When set, up to this many distinct non-empty numeric cells that
+    /// failed to parse are collected in `LoadReport::rejected_formats`.
+    pub rejected_formats_limit: Option<usize>,
+}
+
+impl LoadOptions {
+    /// The base year of the deflator table, or `None` when no adjustment
+    /// is configured.
+    pub fn deflator_base(&self) -> Option<i32> {
+        if self.deflators.is_empty() {
+            return None;
+        }
+        self.deflator_base_year
+            .or_else(|| self.deflators.keys().next().copied())
+    }
+
+    /// Multiplier that converts `year` pesos into base-year pesos:
+    /// `index[base] / index[year]`. Returns 1.0 when no table is set or
+    /// either year is missing from it.
+    pub fn price_factor(&self, year: i32) -> f64 {
+        let base = match self.deflator_base().and_then(|b| self.deflators.get(&b)) {
+            Some(b) => *b,
+            None => return 1.0,
+        };
+        match self.deflators.get(&year) {
+            Some(idx) if *idx > 0.0 => base / idx,
+            _ => 1.0,
+        }
+    }
+}
+
+/// The values the loader substitutes for a missing text field. Reports can
+/// recognize them through `ReportConfig::placeholder_groups`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Placeholders {
+    pub region: String,
+    pub main_island: String,
+    pub province: String,
+    pub type_of_work: String,
+    pub contractor: String,
+}
+
+impl Default for Placeholders {
+    fn default() -> Self {
+        Placeholders {
+            region: "Unknown".to_string(),
+            main_island: "Unknown".to_string(),
+            province: "Unknown".to_string(),
+            type_of_work: "Unspecified".to_string(),
+            contractor: "Unknown Contractor".to_string(),
+        }
+    }
+}
+
+/// Inclusive bounds a numeric field must fall within to be plausible;
+/// `None` leaves that side open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValueRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ValueRange {
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// Plausibility ranges checked while cleaning, on top of the basic
+/// validity checks (e.g. a budget must still be positive). Values outside
+/// them are dropped as parse errors. All open by default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlausibilityRanges {
+    /// `ApprovedBudgetForContract` as read, before any deflator.
+    pub approved_budget: ValueRange,
+    /// `ContractCost` as read, before any deflator.
+    pub contract_cost: ValueRange,
+    /// The computed delay (or duration), in days.
+    pub completion_delay_days: ValueRange,
+}
+
+/// What the reports do with groups formed by a placeholder value (e.g. the
+/// "Unknown" region).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaceholderGroups {
+    /// Report them like any other group (original behavior).
+    Keep,
+    /// Leave the affected records out of the report that groups by the
+    /// placeholder field.
+    Exclude,
+    /// Keep them, labelled e.g. `Unknown (imputed)`.
+    Suffix,
+}
+
+impl PlaceholderGroups {
+    /// The `--placeholder-groups` spelling of this variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaceholderGroups::Keep => "keep",
+            PlaceholderGroups::Exclude => "exclude",
+            PlaceholderGroups::Suffix => "suffix",
+        }
+    }
+}
+
+/// How a missing or unparseable `ActualCompletionDate` is filled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompletionImputation {
+    /// Use the start date, so the row's delay is 0 (original behavior).
+    #[default]
+    StartDate,
+    /// Use the start date plus the median start-to-completion duration of
+    /// rows with the same TypeOfWork that have a real completion date.
+    /// Falls back to the start date when the type has no such rows.
+    TypeMedianDuration,
+    /// Drop the row.
+    Drop,
+}
+
+/// What to do with a row whose `Status` says the project is not completed
+/// (see `RawRow::status`). Files without the column are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IncompleteStatus {
+    /// Keep the row and its delay (original behavior); it is only counted.
+    #[default]
+    Keep,
+    /// Keep the row but leave its delay out of delay averages.
+    ExcludeDelay,
+    /// Drop the row.
+    Drop,
+}
+
+/// What to do when the input has several columns with the same header
+/// (after any `--column-map` renaming).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DuplicateHeaders {
+    /// Refuse to load the file, naming the duplicated headers.
+    #[default]
+    Error,
+    /// Read the leftmost column of each duplicated header.
+    FirstWins,
+    /// Read the rightmost column of each duplicated header.
+    LastWins,
+}
+
+/// The unit of `completion_delay_days`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DelayDays {
+    /// Every calendar day counts (original behavior).
+    #[default]
+    Calendar,
+    /// Only Mondays to Fridays count; holidays are not excluded.
+    Working,
+}
+
+impl DelayDays {
+    /// The delay from `start` to `end` in this unit.
+    pub fn between(self, start: NaiveDate, end: NaiveDate) -> f64 {
+        match self {
+            DelayDays::Calendar => days_diff(start, end),
+            DelayDays::Working => working_days_diff(start, end),
+        }
+    }
+}
+
+/// How Report 1 turns MedianSavings and AvgDelay into a raw efficiency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EfficiencyFormula {
+    /// `MedianSavings / AvgDelay`, with 0 when `AvgDelay <= 0`. This is the
+    /// original behavior; note that it scores a region whose projects all
+    /// finished with zero delay as the *least* efficient.
+    ZeroWhenNoDelay,
+    /// `MedianSavings / (max(AvgDelay, 0) + 1)`. Zero delay divides by one
+    /// day, so punctual regions score highest for a given median saving.
+    PlusOneDay,
+}
+
+impl EfficiencyFormula {
+    /// The `--efficiency-formula` spelling of this variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EfficiencyFormula::ZeroWhenNoDelay => "default",
+            EfficiencyFormula::PlusOneDay => "plus-one",
+        }
+    }
+}
+
+/// What Report 3's YoYChange is measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YoyBaseline {
+    /// Each TypeOfWork against its own baseline-year average (original).
+    PerType,
+    /// Each (year, type) against the baseline year's weighted average of
+    /// savings across all types.
+    GlobalPerYear,
+}
+
+impl YoyBaseline {
+    /// The `--yoy-baseline` spelling of this variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            YoyBaseline::PerType => "per-type",
+            YoyBaseline::GlobalPerYear => "global",
+        }
+    }
+}
+
+/// How Report 2 credits a joint-venture contractor such as
+/// `ABC CORP / XYZ BUILDERS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JvSplit {
+    /// The joint venture is one contractor under its full name (original
+    /// behavior).
+    Single,
+    /// Each member firm is credited with the whole project: its count,
+    /// delay, cost and savings.
+    FullCredit,
+    /// Each member firm is credited with the project and its delay, and
+    /// an equal share of its cost and savings.
+    SplitEvenly,
+}
+
+impl JvSplit {
+    /// The `--jv-split` spelling of this variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JvSplit::Single => "single",
+            JvSplit::FullCredit => "full",
+            JvSplit::SplitEvenly => "even",
+        }
+    }
+}
+
+/// How peso amounts are rounded to their displayed decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Ties to even on the stored binary value, exactly as `format!`
+    /// does (original behavior).
+    #[default]
+    HalfEven,
+    /// Commercial rounding of the decimal value, as spreadsheets do: ties
+    /// go away from zero, so 2.675 becomes 2.68.
+    HalfUp,
+}
+
+impl RoundingMode {
+    /// The `--currency-rounding` spelling of this variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoundingMode::HalfEven => "half-even",
+            RoundingMode::HalfUp => "half-up",
+        }
+    }
+}
+
+/// Parameters that affect how the report generators compute their metrics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// Report 1: how raw efficiency is derived before normalization.
+    pub efficiency_formula: EfficiencyFormula,
+    /// Report 1: when `true`, add a RawEfficiency column with the
+    /// unnormalized formula value, before the weighting multipliers below,
+    /// so it stays comparable across runs.
+    pub raw_efficiency: bool,
+    /// Report 1: when `true`, each group's raw efficiency is multiplied by
+    /// its share of the national budget before normalization, so large
+    /// regions weigh more in the 0–100 scaling. Off by default.
+    pub weighted_efficiency: bool,
+    /// Report 1: when `true`, each group's raw efficiency is multiplied by
+    /// the share of its projects with nothing imputed (coordinates or
+    /// completion date), so heavily imputed regions rank lower. Off by
+    /// default.
+    pub completeness_weighted: bool,
+    /// Report 1: a project counts towards `HighDelayPct` when its delay is
+    /// strictly greater than this many days.
+    pub high_delay_threshold_days: f64,
+    /// Report 2: the delay horizon in the reliability formula
+    /// `(1 - AvgDelay / horizon)`.
+    pub reliability_horizon_days: f64,
+    /// Report 2: horizon overrides per TypeOfWork, for types whose normal
+    /// durations differ from the default. Empty by default.
+    pub type_horizons: BTreeMap<String, f64>,
+    /// Report 2: reliability indices above this value are clamped to it.
+    pub reliability_cap: f64,
+    /// Report 2: when set, reliability indices below this value are
+    /// clamped to it (e.g. 0 keeps the column on a 0–100 scale). `None`,
+    /// the default, leaves the lower end unbounded.
+    pub reliability_floor: Option<f64>,
+    /// Report 2: contractors are flagged "High Risk" below this index.
+    pub risk_threshold: f64,
+    /// Report 2: contractors need at least this many projects to qualify.
+    pub min_contractor_projects: usize,
+    /// Report 2: how many contractors (by TotalCost) are kept.
+    pub top_contractors: usize,
+    /// Report 2: whether joint ventures are split into their member firms.
+    pub jv_split: JvSplit,
+    /// Report 3: the funding year whose averages serve as YoY baseline.
+    pub baseline_year: i32,
+    /// Report 3: per-type or all-types baseline for YoYChange.
+    pub yoy_baseline: YoyBaseline,
+    /// Report 3: when `true`, add a SmoothedAvgSavings column holding a
+    /// three-year moving average of each type's AvgSavings.
+    pub smooth_savings: bool,
+    /// Report 3: when `true`, every observed FundingYear x TypeOfWork pair
+    /// gets a row, with TotalProjects 0 for pairs that have no projects.
+    /// Off by default (only pairs with projects are listed).
+    pub dense_report3: bool,
+    /// Report 3: when set, add a CountCollapse column flagging rows whose
+    /// ProjectCountYoY fell by more than this percentage.
+    pub count_drop_pct: Option<f64>,
+    /// All reports: how groups formed by a loader placeholder are shown.
+    pub placeholder_groups: PlaceholderGroups,
+    /// All reports and the summary: rounding of peso amounts.
+    pub currency_rounding: RoundingMode,
+    /// When `true`, a percentage column outside [0, 100] stops report
+    /// generation before any file is written; otherwise it is only warned
+    /// about.
+    pub strict_bounds: bool,
+}
+
+impl ReportConfig {
+    /// The reliability horizon for projects of `type_of_work`: its entry
+    /// in `type_horizons`, or `reliability_horizon_days`.
+    pub fn horizon_for(&self, type_of_work: &str) -> f64 {
+        self.type_horizons
+            .get(type_of_work)
+            .copied()
+            .unwrap_or(self.reliability_horizon_days)
+    }
+
+    /// The parameters Report 1 depends on, as `(name, value)` pairs for
+    /// the `--csv-metadata` comment line.
+    pub fn report1_params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "high_delay_threshold",
+                self.high_delay_threshold_days.to_string(),
+            ),
+            (
+                "efficiency_formula",
+                self.efficiency_formula.as_str().to_string(),
+            ),
+            ("raw_efficiency", self.raw_efficiency.to_string()),
+            ("weighted_efficiency", self.weighted_efficiency.to_string()),
+            (
+                "completeness_weighted",
+                self.completeness_weighted.to_string(),
+            ),
+            (
+                "placeholder_groups",
+                self.placeholder_groups.as_str().to_string(),
+            ),
+            (
+                "currency_rounding",
+                self.currency_rounding.as_str().to_string(),
+            ),
+        ]
+    }
+
+    /// The parameters Report 2 depends on; see `report1_params`.
+    pub fn report2_params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("min_projects", self.min_contractor_projects.to_string()),
+            ("top_contractors", self.top_contractors.to_string()),
+            (
+                "reliability_horizon",
+                self.reliability_horizon_days.to_string(),
+            ),
+            (
+                "type_horizons",
+                if self.type_horizons.is_empty() {
+                    "none".to_string()
+                } else {
+                    self.type_horizons
+                        .iter()
+                        .map(|(t, h)| format!("{}={}", t, h))
+                        .collect::<Vec<_>>()
+                        .join(";")
+                },
+            ),
+            ("risk_threshold", self.risk_threshold.to_string()),
+            ("jv_split", self.jv_split.as_str().to_string()),
+            ("reliability_cap", self.reliability_cap.to_string()),
+            (
+                "reliability_floor",
+                self.reliability_floor
+                    .map(|f| f.to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+            ),
+            (
+                "placeholder_groups",
+                self.placeholder_groups.as_str().to_string(),
+            ),
+            (
+                "currency_rounding",
+                self.currency_rounding.as_str().to_string(),
+            ),
+        ]
+    }
+
+    /// The parameters Report 3 depends on; see `report1_params`.
+    pub fn report3_params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("baseline_year", self.baseline_year.to_string()),
+            ("yoy_baseline", self.yoy_baseline.as_str().to_string()),
+            ("smooth_savings", self.smooth_savings.to_string()),
+            ("dense", self.dense_report3.to_string()),
+            (
+                "count_drop_pct",
+                self.count_drop_pct
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+            ),
+            (
+                "placeholder_groups",
+                self.placeholder_groups.as_str().to_string(),
+            ),
+            (
+                "currency_rounding",
+                self.currency_rounding.as_str().to_string(),
+            ),
+        ]
+    }
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        ReportConfig {
+            efficiency_formula: EfficiencyFormula::ZeroWhenNoDelay,
+            raw_efficiency: false,
+            weighted_efficiency: false,
+            completeness_weighted: false,
+            high_delay_threshold_days: 30.0,
+            reliability_horizon_days: 90.0,
+            type_horizons: BTreeMap::new(),
+            reliability_cap: 100.0,
+            reliability_floor: None,
+            risk_threshold: 50.0,
+            min_contractor_projects: 5,
+            top_contractors: 15,
+            jv_split: JvSplit::Single,
+            baseline_year: 2021,
+            yoy_baseline: YoyBaseline::PerType,
+            smooth_savings: false,
+            dense_report3: false,
+            count_drop_pct: None,
+            placeholder_groups: PlaceholderGroups::Keep,
+            currency_rounding: RoundingMode::HalfEven,
+            strict_bounds: false,
+        }
+    }
+}
+
+/// Column layout for the console and `previews.md` tables. The defaults
+/// leave `tabled`'s output untouched; CSV files are never affected.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewLayout {
+    /// Truncate text cells longer than this many characters, ending them
+    /// with `…`. Headers are never truncated.
+    pub max_text_width: Option<usize>,
+    /// Right-align columns whose cells are all numbers.
+    pub right_align_numbers: bool,
+}
+
+/// When `write_csv` quotes a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CsvQuote {
+    /// Only fields containing the delimiter, a quote or a line break
+    /// (the original behavior).
+    Necessary,
+    /// Every field, including headers and numbers.
+    Always,
+}
+
+/// Delimiter and quoting for every CSV the tool writes. The default is
+/// plain comma-separated output, quoted only where needed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvFormat {
+    #[serde(
+        serialize_with = "serialize_delimiter",
+        deserialize_with = "deserialize_delimiter"
+    )]
+    pub delimiter: u8,
+    pub quote: CsvQuote,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat {
+            delimiter: b',',
+            quote: CsvQuote::Necessary,
+        }
+    }
+}
+
+/// Write the delimiter byte as a one-character string rather than a number.
+fn serialize_delimiter<S: Serializer>(delimiter: &u8, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_char(*delimiter as char)
+}
+
+/// Read a delimiter written by `serialize_delimiter`: one ASCII character.
+fn deserialize_delimiter<'de, D: Deserializer<'de>>(d: D) -> Result<u8, D::Error> {
+    let c = char::deserialize(d)?;
+    u8::try_from(c)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| D::Error::custom(format!("delimiter {:?} is not an ASCII character", c)))
+}
+
+/// Write the comment byte as a one-character string, like the delimiter.
+fn serialize_comment_char<S: Serializer>(c: &Option<u8>, s: S) -> Result<S::Ok, S::Error> {
+    match c {
+        Some(c) => s.serialize_some(&(*c as char)),
+        None => s.serialize_none(),
+    }
+}
+
+/// Read a comment character written by `serialize_comment_char`.
+fn deserialize_comment_char<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u8>, D::Error> {
+    match Option::<char>::deserialize(d)? {
+        Some(c) => u8::try_from(c)
+            .ok()
+            .filter(u8::is_ascii)
+            .map(Some)
+            .ok_or_else(|| {
+                D::Error::custom(format!(
+                    "comment character {:?} is not an ASCII character",
+                    c
+                ))
+            }),
+        None => Ok(None),
+    }
+}
+
+/// One `--sort report:column:direction` request, applied to a report's
+/// rows after they are generated and before they are written or previewed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSort {
+    /// Which report (1, 2 or 3) to re-sort.
+    pub report: usize,
+    /// The column header to sort by, e.g. `AvgDelay`.
+    pub column: String,
+    pub descending: bool,
+}
+
+/// Parameters that control which files are written and where.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputOptions {
+    /// Prepended (with a `_` separator) to every generated file name so
+    /// runs for different slices can share a directory. Empty by default.
+    pub prefix: String,
+    /// When `true`, each report run prepends its local start time
+    /// (`YYYYMMDDTHHMMSS`) to every output file name so reruns keep history.
+    pub timestamp_outputs: bool,
+    /// Number of rows shown in each console preview. `None` keeps the
+    /// per-report defaults (2 for Reports 1–2, 3 for Report 3).
+    pub preview_rows: Option<usize>,
+    /// When `true`, report previews show rows spread evenly across each
+    /// report instead of its first rows.
+    pub preview_sampled: bool,
+    /// When `true`, previews show only each report's significant rows:
+    /// Report 1 and 3 rows more than one standard deviation from the mean
+    /// EfficiencyScore / YoYChange, and Report 2's high-risk contractors.
+    /// `preview_rows`, when set, caps how many are shown.
+    pub preview_significant: bool,
+    /// Re-sort requests for the generated reports. When several target the
+    /// same report, the first one listed is the primary key.
+    pub sorts: Vec<ReportSort>,
+    /// Expected row count per report number (1-3), from `--expect`. After
+    /// generating, any mismatch is reported and the process exits with 1.
+    pub expected_rows: BTreeMap<usize, usize>,
+    /// Column width and alignment for every preview table.
+    pub preview_layout: PreviewLayout,
+    /// When `true`, the previews and the summary are also written to
+    /// `previews.md`.
+    pub previews_markdown: bool,
+    /// When `true`, a readable `report.md` (summary bullets plus the top
+    /// rows of each report) is also written.
+    pub markdown_report: bool,
+    /// How many regions the top/bottom menu view lists on each side.
+    pub extremes_count: usize,
+    /// Rows per page in the page-through-a-report menu view.
+    pub page_size: usize,
+    /// When `true`, each report is also written as a whitespace-aligned
+    /// text table next to its CSV (same name, `.txt` extension).
+    pub fixed_width: bool,
+    /// When `true`, every cleaned record is also written to
+    /// `clean_records.csv` in a stable order.
+    pub export_clean: bool,
+    /// When `true`, summary.json counts the records whose ContractCost
+    /// equals their approved budget exactly, and `clean_records.csv` gets
+    /// an ExactBudgetMatch column.
+    pub flag_exact_budget: bool,
+    /// When `true`, each report is also written as a JSON array of
+    /// objects next to its CSV.
+    pub json_reports: bool,
+    /// When `true`, the summary and load statistics are also written as
+    /// Prometheus gauges to `metrics.prom`.
+    pub prometheus: bool,
+    /// When set, the clean records and all reports are also written to a
+    /// SQLite database at this path.
+    pub sqlite_path: Option<String>,
+    /// When `true`, the records with coordinates are also written to
+    /// `projects.geojson` as points.
+    pub geojson: bool,
+    /// With `geojson`, keep only points at the project's own coordinates.
+    pub geojson_exclude_imputed: bool,
+    /// When `true`, each region's summary and projects are also written to
+    /// their own `by_region/<region>.json`.
+    pub by_region: bool,
+    /// The date the summary's `days_since_latest_completion` and the CSV
+    /// metadata line count from; `None` means today.
+    pub as_of: Option<NaiveDate>,
+    /// When `true`, print diagnostics on the distribution of computed
+    /// metrics (currently Report 2's ReliabilityIndex) after the previews.
+    pub stats: bool,
+    /// When `true`, print how each report's derived columns were computed
+    /// (with the active parameter values) before its preview.
+    pub explain: bool,
+    /// When set, contractor names within this edit distance are clustered
+    /// into `report_contractor_merge_candidates.csv` for review.
+    pub merge_candidates_distance: Option<usize>,
+    /// When set, records whose ContractCost lies outside the IQR fences
+    /// with this multiplier are written to `report_cost_outliers.csv`.
+    pub cost_outlier_fence: Option<f64>,
+    /// Compute the cost-outlier fences per TypeOfWork instead of overall.
+    pub cost_outliers_by_type: bool,
+    /// When `true`, contractors whose average delay rose every year are
+    /// written to `report_worsening_contractors.csv`.
+    pub worsening_contractors: bool,
+    /// When `true`, contractors active in at most `max_contractor_regions`
+    /// regions are written to `single_region_contractors.csv`.
+    pub single_region_contractors: bool,
+    /// The region limit for `single_region_contractors` (default 1).
+    pub max_contractor_regions: usize,
+    /// When set, the summary and Report 2 are recomputed with this
+    /// contractor's contract costs multiplied by the factor, and the
+    /// before/after figures printed. The loaded data is not changed.
+    pub what_if: Option<(String, f64)>,
+    /// When set, a second dataset (e.g. another agency's) is loaded from
+    /// this path and both Report 1 tables are written to
+    /// `report1_by_source.csv`, tagged with their file names.
+    pub side_by_side_path: Option<String>,
+    /// When set, a second dataset (the previous period) is loaded from
+    /// this path and each region's Report 1 rank is compared against it.
+    pub compare_path: Option<String>,
+    /// When set, each report is compared with the same report from a
+    /// previous run in this directory, and only its new, changed and
+    /// removed rows are written to `<report>_changes.csv`.
+    pub changes_since: Option<String>,
+    /// When `true`, Reports 1 and 2 are also computed for each funding
+    /// year on its own and written to `report1_<year>.csv` and
+    /// `report2_<year>.csv`.
+    pub per_year: bool,
+    /// When `true`, a readable headline with total savings in compact form
+    /// (e.g. `₱1.23B`) is printed after the summary line.
+    pub compact_summary: bool,
+    /// When `true`, loading prints how many rows were parsed per second.
+    pub throughput: bool,
+    /// When `true`, every CSV starts with a `# generated ..., rows=...`
+    /// comment line listing the parameters behind it. Off by default so
+    /// the files stay strict CSV.
+    pub csv_metadata: bool,
+    /// Delimiter and quote style of the CSV outputs.
+    pub csv_format: CsvFormat,
+    /// When `true`, report CSVs that already exist get the new rows
+    /// appended below their header instead of being overwritten.
+    pub append_csv: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            prefix: String::new(),
+            timestamp_outputs: false,
+            preview_rows: None,
+            preview_sampled: false,
+            preview_significant: false,
+            sorts: Vec::new(),
+            expected_rows: BTreeMap::new(),
+            preview_layout: PreviewLayout::default(),
+            previews_markdown: false,
+            markdown_report: false,
+            extremes_count: 3,
+            page_size: 10,
+            fixed_width: false,
+            export_clean: false,
+            flag_exact_budget: false,
+            json_reports: false,
+            prometheus: false,
+            sqlite_path: None,
+            geojson: false,
+            geojson_exclude_imputed: false,
+            by_region: false,
+            as_of: None,
+            stats: false,
+            explain: false,
+            merge_candidates_distance: None,
+            cost_outlier_fence: None,
+            cost_outliers_by_type: false,
+            worsening_contractors: false,
+            single_region_contractors: false,
+            max_contractor_regions: 1,
+            what_if: None,
+            side_by_side_path: None,
+            compare_path: None,
+            changes_since: None,
+            per_year: false,
+            compact_summary: false,
+            throughput: false,
+            csv_metadata: false,
+            csv_format: CsvFormat::default(),
+            append_csv: false,
+        }
+    }
+}
+
+impl OutputOptions {
+    /// Return a copy whose file names start with `stamp` (ahead of any
+    /// user prefix), so every file from one run carries the same time.
+    pub fn with_timestamp(&self, stamp: &str) -> OutputOptions {
+        let mut out = self.clone();
+        out.prefix = if self.prefix.is_empty() {
+            stamp.to_string()
+        } else {
+            format!("{}_{}", stamp, self.prefix)
+        };
+        out
+    }
+
+    /// Build the output file name for `base`, e.g. `report1_regional_summary.csv`
+    /// becomes `region4a_report1_regional_summary.csv` with prefix `region4a`.
+    pub fn file_name(&self, base: &str) -> String {
+        if self.prefix.is_empty() {
+            base.to_string()
+        } else {
+            format!("{}_{}", self.prefix, base)
+        }
+    }
+}
+
+/// All options for a single run of the tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Options {
+    pub load: LoadOptions,
+    pub report: ReportConfig,
+    pub output: OutputOptions,
+    /// When `true`, `main` prints these options as JSON and exits instead
+    /// of starting the menu.
+    #[serde(skip)]
+    pub print_config: bool,
+    /// When `true`, `main` prints the crate name and version and exits.
+    #[serde(skip)]
+    pub print_version: bool,
+    /// Set by `serve`: `main` serves the reports over HTTP on this port
+    /// instead of starting the menu.
+    #[serde(skip)]
+    pub serve_port: Option<u16>,
+}
+
+impl Options {
+    /// Read options from a JSON file in the shape `--print-config` prints.
+    /// Every field is optional: anything the file leaves out keeps its
+    /// default, and unknown keys (such as the printed `version`) are
+    /// ignored.
+    pub fn from_json_file(path: &str) -> Result<Options, PipelineError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// What `--print-config` prints: the program version followed by every
+    /// option, as pretty JSON, so a run's settings can be attached to its
+    /// results. `from_json_file` reads it back.
+    pub fn effective_config_json(&self) -> Result<String, PipelineError> {
+        #[derive(Serialize)]
+        struct EffectiveConfig<'a> {
+            version: &'static str,
+            #[serde(flatten)]
+            options: &'a Options,
+        }
+        let config = EffectiveConfig {
+            version: env!("CARGO_PKG_VERSION"),
+            options: self,
+        };
+        Ok(serde_json::to_string_pretty(&config)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp_prefixes_every_output_name() {
+        let plain = OutputOptions::default();
+        assert_eq!(plain.file_name("summary.json"), "summary.json");
+        let stamped = plain.with_timestamp("20240101T120000");
+        assert_eq!(
+            stamped.file_name("summary.json"),
+            "20240101T120000_summary.json"
+        );
+
+        let prefixed = OutputOptions {
+            prefix: "region4a".to_string(),
+            ..OutputOptions::default()
+        };
+        let stamped = prefixed.with_timestamp("20240101T120000");
+        assert_eq!(
+            stamped.file_name("report1_regional_summary.csv"),
+            "20240101T120000_region4a_report1_regional_summary.csv"
+        );
+        // The original options keep their fixed names.
+        assert_eq!(prefixed.file_name("summary.json"), "region4a_summary.json");
+    }
+}
@@ -0,0 +1,82 @@
+// Error type shared by the loader and the output writers.
+//
+// Derived with `thiserror`. Each variant wraps the failure of one layer, so
+// a caller can tell a missing file from a malformed one or a failed write
+// without parsing messages.
+use std::io;
+use thiserror::Error;
+
+/// Why loading the input or writing an output failed.
+#[derive(Debug, Error)]
+pub enum PipelineError {
+    /// Opening, reading or writing a file failed (e.g. it does not exist).
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The CSV reader or writer failed on the file's contents.
+    #[error("{0}")]
+    Csv(#[from] csv::Error),
+    /// A value could not be serialized as JSON.
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    /// The Parquet reader failed on the file's contents.
+    #[error("{0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    /// Writing the SQLite database failed.
+    #[error("{0}")]
+    Sqlite(#[from] rusqlite::Error),
+    /// The file was read but cannot be used as given, e.g. it is empty,
+    /// not a CSV, or has duplicate headers.
+    #[error("{0}")]
+    Validation(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LoadOptions;
+    use crate::loader;
+    use std::error::Error as _;
+    use std::io::Write;
+
+    /// Load `contents` from a temporary `.csv` file.
+    fn load_bytes(contents: &[u8]) -> Result<(), PipelineError> {
+        let mut file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        file.write_all(contents).unwrap();
+        loader::load(file.path().to_str().unwrap(), &LoadOptions::default()).map(|_| ())
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.csv");
+        match loader::load(path.to_str().unwrap(), &LoadOptions::default()) {
+            Err(e @ PipelineError::Io(_)) => {
+                assert!(e.source().is_some());
+                assert!(
+                    matches!(&e, PipelineError::Io(io) if io.kind() == io::ErrorKind::NotFound)
+                );
+            }
+            other => panic!("expected an Io error, got {:?}", other.map(|r| r.0.len())),
+        }
+    }
+
+    #[test]
+    fn malformed_csv_is_a_csv_error() {
+        // A header that is not valid UTF-8 cannot be read as CSV text.
+        match load_bytes(b"MainIsland,Reg\xFF\xFEion,Province\nLuzon,Region I,Abra\n") {
+            Err(e @ PipelineError::Csv(_)) => assert!(e.to_string().contains("UTF-8")),
+            other => panic!("expected a Csv error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn binary_file_is_a_validation_error() {
+        match load_bytes(b"PK\x03\x04\x14\x00\x06\x00[Content_Types].xml") {
+            Err(e @ PipelineError::Validation(_)) => {
+                assert!(e.to_string().contains("appears to be a ZIP archive"));
+                assert!(e.source().is_none());
+            }
+            other => panic!("expected a Validation error, got {:?}", other),
+        }
+    }
+}
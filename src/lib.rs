@@ -0,0 +1,471 @@
+// Library side of the DPWH flood control data pipeline.
+//
+// Loading, cleaning, report generation and file output all live here so
+// the `rust_report` binary's menu, its HTTP server, tests and other
+// programs embedding the pipeline share one implementation.
+// `run_analysis` turns cleaned records into every report in one call.
+pub mod cli;
+pub mod config;
+pub mod error;
+pub mod loader;
+pub mod output;
+pub mod reports;
+pub mod server;
+pub mod types;
+pub mod util;
+
+use chrono::Local;
+use config::Options;
+use error::PipelineError;
+use tabled::Tabled;
+use types::{CleanRecord, ContractorRankingRow, RegionSummaryRow, SummaryStats, TypeTrendRow};
+
+/// Everything one analysis run produces: the three reports as option [2]
+/// writes them, the summary built from them, and the load report of the
+/// data they came from.
+#[derive(Debug)]
+pub struct AnalysisResult {
+    /// Report 1 in the efficiency order that ranks are based on.
+    pub r1: Vec<RegionSummaryRow>,
+    /// Report 1 as written and previewed, after any `--sort`.
+    pub r1_out: Vec<RegionSummaryRow>,
+    pub r2: Vec<ContractorRankingRow>,
+    pub r3: Vec<TypeTrendRow>,
+    pub summary: SummaryStats,
+    /// `None` when the records did not come from `loader::load`.
+    pub load_report: Option<loader::LoadReport>,
+}
+
+/// Generate all three reports from `data`, applying the placeholder
+/// scoping and `--sort` options, and build the summary with its
+/// report-level counts filled in from them.
+pub fn run_analysis(
+    data: &[CleanRecord],
+    load_report: Option<loader::LoadReport>,
+    options: &Options,
+) -> AnalysisResult {
+    let scoped = |report| {
+        reports::apply_placeholder_groups(data, &options.load.placeholders, &options.report, report)
+    };
+    let r1 = reports::generate_report1(&scoped(1), &options.report);
+    let r1_out = apply_sorts(r1.clone(), 1, options);
+    let r2 = apply_sorts(
+        reports::generate_report2(&scoped(2), &options.report),
+        2,
+        options,
+    );
+    let r3 = apply_sorts(
+        reports::generate_report3(&scoped(3), &options.report),
+        3,
+        options,
+    );
+    let mut summary = reports::generate_summary(
+        data,
+        &r2,
+        options
+            .output
+            .as_of
+            .unwrap_or_else(|| Local::now().date_naive()),
+        options.report.currency_rounding,
+    );
+    // Fill in report-level counts to match the JS summary.json shape.
+    summary.report1_regions = r1.len();
+    summary.report2_contractors = r2.len();
+    summary.report3_entries = r3.len();
+    summary.exact_budget_matches = options.output.flag_exact_budget.then(|| {
+        data.iter()
+            .filter(|r| reports::is_exact_budget_match(r))
+            .count()
+    });
+    summary.input_sha256 = load_report.as_ref().map(|r| r.input_sha256.clone());
+    AnalysisResult {
+        r1,
+        r1_out,
+        r2,
+        r3,
+        summary,
+        load_report,
+    }
+}
+
+/// Apply every `--sort` aimed at Report `report` to `rows`. The sorts are
+/// stable, so applying them last-to-first makes the first one primary.
+fn apply_sorts<T: Tabled>(mut rows: Vec<T>, report: usize, options: &Options) -> Vec<T> {
+    for sort in options
+        .output
+        .sorts
+        .iter()
+        .rev()
+        .filter(|s| s.report == report)
+    {
+        // Column names were validated when the flags were parsed, so this
+        // only fails if a report's headers change without updating that.
+        if let Err(e) = reports::sort_by_column(&mut rows, &sort.column, sort.descending) {
+            eprintln!("Sort error (report{}): {}", report, e);
+        }
+    }
+    rows
+}
+
+/// The files every analysis run writes (see `write_reports`), before
+/// `OutputOptions::file_name` adds any prefix.
+pub const REPORT_FILES: [&str; 4] = [
+    "report1_regional_summary.csv",
+    "report2_contractor_ranking.csv",
+    "report3_annual_trends.csv",
+    "summary.json",
+];
+
+/// Write the three report CSVs and `summary.json` of `result` as option
+/// [2] does, in `REPORT_FILES` order. Returns each file's name with the
+/// outcome of writing it; one failed write does not stop the others.
+pub fn write_reports(
+    result: &AnalysisResult,
+    options: &Options,
+) -> [(String, Result<(), PipelineError>); 4] {
+    let out = &options.output;
+    let [file1, file2, file3, summary_file] = REPORT_FILES.map(|base| out.file_name(base));
+    let meta1 = csv_metadata(options, result.r1.len(), &options.report.report1_params());
+    let meta2 = csv_metadata(options, result.r2.len(), &options.report.report2_params());
+    let meta3 = csv_metadata(options, result.r3.len(), &options.report.report3_params());
+    let written1 = output::write_csv(
+        &file1,
+        &result.r1_out,
+        meta1.as_deref(),
+        &out.csv_format,
+        out.append_csv,
+    );
+    let written2 = output::write_csv(
+        &file2,
+        &result.r2,
+        meta2.as_deref(),
+        &out.csv_format,
+        out.append_csv,
+    );
+    let written3 = output::write_csv(
+        &file3,
+        &result.r3,
+        meta3.as_deref(),
+        &out.csv_format,
+        out.append_csv,
+    );
+    let written_summary = output::write_json(&summary_file, &result.summary);
+    [
+        (file1, written1),
+        (file2, written2),
+        (file3, written3),
+        (summary_file, written_summary),
+    ]
+}
+
+/// Build Reports 1 and 2 from funding year `year`'s records alone and
+/// write them to `report1_<year>.csv` and `report2_<year>.csv`. A year
+/// without records gets header-only files with a note saying so. Returns
+/// both file names and how many records went into them.
+pub fn write_year_reports(
+    data: &[CleanRecord],
+    year: i32,
+    options: &Options,
+) -> Result<(String, String, usize), PipelineError> {
+    let format = &options.output.csv_format;
+    let year_data: Vec<CleanRecord> = data
+        .iter()
+        .filter(|r| r.in_funding_year(year))
+        .cloned()
+        .collect();
+    let file1 = options.output.file_name(&format!("report1_{}.csv", year));
+    let file2 = options.output.file_name(&format!("report2_{}.csv", year));
+    if year_data.is_empty() {
+        let note = format!("no records for FundingYear {}", year);
+        output::write_empty_csv::<RegionSummaryRow>(&file1, &note, format)?;
+        output::write_empty_csv::<ContractorRankingRow>(&file2, &note, format)?;
+        return Ok((file1, file2, 0));
+    }
+    let result = run_analysis(&year_data, None, options);
+    let mut params = vec![("funding_year", year.to_string())];
+    params.extend(options.report.report1_params());
+    let meta1 = csv_metadata(options, result.r1_out.len(), &params);
+    let mut params = vec![("funding_year", year.to_string())];
+    params.extend(options.report.report2_params());
+    let meta2 = csv_metadata(options, result.r2.len(), &params);
+    output::write_csv(
+        &file1,
+        &result.r1_out,
+        meta1.as_deref(),
+        format,
+        options.output.append_csv,
+    )?;
+    output::write_csv(
+        &file2,
+        &result.r2,
+        meta2.as_deref(),
+        format,
+        options.output.append_csv,
+    )?;
+    Ok((file1, file2, year_data.len()))
+}
+
+/// Build the `# ...` provenance line for a CSV with `rows` rows, or
+/// `None` when `--csv-metadata` is off. Report-specific `params` follow
+/// the generation date, row count and (if set) the deflator base year.
+pub fn csv_metadata(options: &Options, rows: usize, params: &[(&str, String)]) -> Option<String> {
+    if !options.output.csv_metadata {
+        return None;
+    }
+    let mut meta = format!(
+        "generated {}, rows={}",
+        options
+            .output
+            .as_of
+            .unwrap_or_else(|| Local::now().date_naive())
+            .format("%Y-%m-%d"),
+        rows
+    );
+    if let Some(base) = options.load.deflator_base() {
+        meta.push_str(&format!(", deflator_base={}", base));
+    }
+    for (key, value) in params {
+        meta.push_str(&format!(", {}={}", key, value));
+    }
+    Some(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn summary_counts_match_report_lengths() {
+        let data: Vec<CleanRecord> = (0..30)
+            .map(|i| CleanRecord {
+                funding_year: 2021 + i % 3,
+                type_of_work: format!("Type {}", i % 2),
+                ..CleanRecord::sample(
+                    &format!("Region {}", i % 4),
+                    &format!("Contractor {}", i % 5),
+                    1_000_000.0,
+                    900_000.0 + 1_000.0 * i as f64,
+                    (10 * i) as f64,
+                )
+            })
+            .collect();
+        let result = run_analysis(&data, None, &Options::default());
+        assert_eq!(result.r1.len(), 4);
+        assert_eq!(result.r2.len(), 5);
+        assert_eq!(result.r3.len(), 6);
+        assert_eq!(result.summary.report1_regions, result.r1.len());
+        assert_eq!(result.summary.report2_contractors, result.r2.len());
+        assert_eq!(result.summary.report3_entries, result.r3.len());
+        assert_eq!(result.summary.total_projects, data.len());
+        assert_eq!(result.r1_out.len(), result.r1.len());
+        assert!(result.load_report.is_none());
+    }
+
+    #[test]
+    fn output_prefix_applies_to_all_four_files() {
+        let data = vec![CleanRecord::sample(
+            "Region I", "ABC CORP", 1000.0, 900.0, 10.0,
+        )];
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = Options::default();
+        options.output.prefix = dir.path().join("region1").to_string_lossy().into_owned();
+        let result = run_analysis(&data, None, &options);
+        let written = write_reports(&result, &options);
+        for ((file, outcome), base) in written.iter().zip(REPORT_FILES) {
+            assert!(outcome.is_ok(), "{}: {:?}", file, outcome);
+            assert_eq!(
+                Path::new(file).file_name().unwrap().to_string_lossy(),
+                format!("region1_{}", base)
+            );
+            assert!(Path::new(file).exists());
+        }
+    }
+
+    #[test]
+    fn metadata_comment_is_written_only_when_enabled() {
+        let data = vec![CleanRecord::sample(
+            "Region I", "ABC CORP", 1000.0, 900.0, 10.0,
+        )];
+        let dir = tempfile::tempdir().unwrap();
+        let first_line = |options: &Options| {
+            let result = run_analysis(&data, None, options);
+            let [(file, written), ..] = write_reports(&result, options);
+            written.unwrap();
+            let text = std::fs::read_to_string(file).unwrap();
+            text.lines().next().unwrap().to_string()
+        };
+        let mut options = Options::default();
+        options.output.prefix = dir.path().join("plain").to_string_lossy().into_owned();
+        assert!(first_line(&options).starts_with("Region,"));
+
+        options.output.prefix = dir.path().join("meta").to_string_lossy().into_owned();
+        options.output.csv_metadata = true;
+        options.output.as_of = chrono::NaiveDate::from_ymd_opt(2024, 6, 1);
+        let line = first_line(&options);
+        assert!(
+            line.starts_with("# generated 2024-06-01, rows=1, high_delay_threshold=30"),
+            "{}",
+            line
+        );
+    }
+
+    #[test]
+    fn sort_report1_by_total_budget_ascending() {
+        let data: Vec<CleanRecord> = [
+            ("Region A", 5000.0),
+            ("Region B", 1000.0),
+            ("Region C", 3000.0),
+        ]
+        .iter()
+        .map(|(region, budget)| {
+            CleanRecord::sample(region, "ABC CORP", *budget, budget * 0.9, 10.0)
+        })
+        .collect();
+        let args = |spec: &str| vec!["--sort-report1".to_string(), spec.to_string()];
+        let options = cli::parse_args(args("total_budget:asc")).unwrap();
+        let result = run_analysis(&data, None, &options);
+        let regions: Vec<&str> = result.r1_out.iter().map(|r| r.region.as_str()).collect();
+        assert_eq!(regions, ["Region B", "Region C", "Region A"]);
+
+        let err = cli::parse_args(args("budget:asc")).unwrap_err();
+        assert!(
+            err.contains("expected one of: total_budget, median_savings"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn what_if_ten_percent_cost_rise_cuts_total_savings() {
+        let data: Vec<CleanRecord> = (0..5)
+            .flat_map(|_| {
+                [
+                    CleanRecord::sample("Region I", "ABC CORP", 1_000_000.0, 800_000.0, 10.0),
+                    CleanRecord::sample("NCR", "XYZ INC", 1_000_000.0, 950_000.0, 20.0),
+                ]
+            })
+            .collect();
+        let options = Options::default();
+        let before = run_analysis(&data, None, &options);
+        let (adjusted, count) = reports::adjust_contractor_costs(&data, " abc  corp", 1.1);
+        assert_eq!(count, 5);
+        let after = run_analysis(&adjusted, None, &options);
+
+        let total =
+            |result: &AnalysisResult| -> f64 { result.summary.total_savings.parse().unwrap() };
+        // Five ABC CORP projects each cost 80,000 more.
+        assert_eq!(total(&before), 1_250_000.0);
+        assert!((total(&before) - total(&after) - 400_000.0).abs() < 0.005);
+        // The stored dataset is untouched.
+        assert!(data.iter().all(|r| r.contract_cost != 880_000.0));
+
+        let abc = |result: &AnalysisResult| {
+            result
+                .r2
+                .iter()
+                .find(|r| r.contractor == "ABC CORP")
+                .map(|r| r.total_savings.clone())
+                .unwrap()
+        };
+        assert_eq!(abc(&before), "1000000.00");
+        assert_eq!(abc(&after), "600000.00");
+    }
+
+    #[test]
+    fn input_sha256_of_a_known_file_is_in_the_summary() {
+        let csv = "MainIsland,Region,Province,TypeOfWork,FundingYear,\
+                   ApprovedBudgetForContract,ContractCost,ActualCompletionDate,Contractor,\
+                   StartDate,ProjectLatitude,ProjectLongitude,ProvincialCapitalLatitude,\
+                   ProvincialCapitalLongitude\n\
+                   Luzon,Region I,Ilocos Norte,Flood Control,2022,1000000,900000,2022-01-31,\
+                   ABC CORP,2022-01-01,18.2,120.6,18.19,120.59\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known.csv");
+        std::fs::write(&path, csv).unwrap();
+        // `sha256sum known.csv`
+        let expected = "9c107fbed9d7722a4659a5bbfa89eeefdc2197d9710cc6bc7685e6db3e78685e";
+
+        let options = Options::default();
+        let (data, load_report) = loader::load(path.to_str().unwrap(), &options.load).unwrap();
+        assert_eq!(load_report.input_sha256, expected);
+        let report_json = serde_json::to_value(&load_report).unwrap();
+        assert_eq!(report_json["input_sha256"], expected);
+        assert_eq!(report_json["filtered_rows"], 1);
+        let result = run_analysis(&data, Some(load_report), &options);
+        assert_eq!(result.summary.input_sha256.as_deref(), Some(expected));
+        let json = serde_json::to_value(&result.summary).unwrap();
+        assert_eq!(json["input_sha256"], expected);
+    }
+
+    #[test]
+    fn exact_budget_matches_are_counted_and_flagged_on_request() {
+        let data = vec![
+            CleanRecord::sample("Region I", "ABC CORP", 1000.0, 1000.0, 10.0),
+            CleanRecord::sample("Region I", "ABC CORP", 2500.5, 2500.5, 10.0),
+            CleanRecord::sample("NCR", "XYZ INC", 1000.0, 999.99, 10.0),
+            CleanRecord::sample("NCR", "XYZ INC", 1000.0, 1200.0, 10.0),
+            CleanRecord::sample("NCR", "LMN JV", 3000.0, 3000.0, 10.0),
+        ];
+        let result = run_analysis(&data, None, &Options::default());
+        assert_eq!(result.summary.exact_budget_matches, None);
+
+        let mut options = Options::default();
+        options.output.flag_exact_budget = true;
+        let result = run_analysis(&data, None, &options);
+        assert_eq!(result.summary.exact_budget_matches, Some(3));
+
+        let flags: Vec<Option<bool>> = reports::clean_record_rows(&data, true)
+            .iter()
+            .map(|row| row.exact_budget_match)
+            .collect();
+        assert_eq!(flags.iter().filter(|f| **f == Some(true)).count(), 3);
+        assert_eq!(flags.iter().filter(|f| **f == Some(false)).count(), 2);
+        assert!(reports::clean_record_rows(&data, false)
+            .iter()
+            .all(|row| row.exact_budget_match.is_none()));
+    }
+
+    #[test]
+    fn per_year_reports_write_a_file_for_every_funding_year() {
+        let in_year = |year: i32, region: &str| CleanRecord {
+            funding_year: year,
+            ..CleanRecord::sample(region, "ABC CORP", 1000.0, 900.0, 10.0)
+        };
+        // Nothing in 2022.
+        let data = vec![
+            in_year(2021, "Region I"),
+            in_year(2021, "NCR"),
+            in_year(2023, "Region I"),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = Options::default();
+        options.output.prefix = dir.path().join("out").to_string_lossy().into_owned();
+
+        let mut counts = Vec::new();
+        for year in config::FUNDING_YEARS {
+            let (file1, file2, records) = write_year_reports(&data, year, &options).unwrap();
+            assert!(file1.ends_with(&format!("report1_{}.csv", year)));
+            assert!(file2.ends_with(&format!("report2_{}.csv", year)));
+            counts.push(records);
+        }
+        assert_eq!(counts, [2, 0, 1]);
+        let report1_files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains("report1_"))
+            .collect();
+        assert_eq!(report1_files.len(), 3);
+
+        // The empty year has the note and the same columns as a full one.
+        let read = |year: i32| {
+            std::fs::read_to_string(options.output.file_name(&format!("report1_{}.csv", year)))
+                .unwrap()
+        };
+        let (empty, full) = (read(2022), read(2021));
+        let mut lines = empty.lines();
+        assert_eq!(lines.next(), Some("# no records for FundingYear 2022"));
+        assert_eq!(lines.next(), full.lines().next());
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -1,143 +1,716 @@
 // Data loading and cleaning pipeline.
 //
 // This module is responsible for:
-// - reading the raw CSV file using the `csv` crate,
+// - reading the raw CSV file using the `csv` crate (or a Parquet file
+//   with the same columns using the `parquet` crate),
 // - deserializing rows into `RawRow`,
 // - validating and transforming them into `CleanRecord`, and
 // - tracking basic statistics about parsing/imputation.
-use crate::types::{CleanRecord, RawRow};
-use crate::util::{days_diff, parse_date_safe, parse_f64_safe, parse_i32_safe};
-use chrono::NaiveDate;
-use csv::ReaderBuilder;
-use std::collections::HashMap;
-use std::error::Error;
+use crate::config::{
+    CompletionImputation, DuplicateHeaders, IncompleteStatus, LoadOptions, FUNDING_YEARS,
+};
+use crate::error::PipelineError;
+use crate::types::{CleanRecord, CoordSource, DelayBasis, RawRow};
+use crate::util::{
+    days_diff, island_for_region, median, parse_date_safe, parse_f64_safe, parse_f64_with_units,
+    parse_year_safe, safe_ratio, sha256_file,
+};
+use chrono::{Duration, NaiveDate};
+use csv::{ReaderBuilder, StringRecord};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The DPWH column names `RawRow` is deserialized from. A
+/// `LoadOptions::column_map` maps some of these to a file's own headers.
+pub const CANONICAL_COLUMNS: [&str; 16] = [
+    "MainIsland",
+    "Region",
+    "Province",
+    "TypeOfWork",
+    "FundingYear",
+    "ApprovedBudgetForContract",
+    "ContractCost",
+    "ActualCompletionDate",
+    "PlannedCompletionDate",
+    "Contractor",
+    "StartDate",
+    "ProjectLatitude",
+    "ProjectLongitude",
+    "ProvincialCapitalLatitude",
+    "ProvincialCapitalLongitude",
+    "Status",
+];
 
 /// Summary of what happened while loading and cleaning the CSV.
 ///
 /// This is used to print user-friendly diagnostics after option `[1]`:
 /// how many rows were seen, how many made it through filtering, and how
 /// many required coordinate imputation.
-#[derive(Debug, Clone)]
+///
+/// `parse_errors` is the total of the `dropped_*` error counters below.
+/// Rows outside the 2021–2023 window are not errors, so they are tracked
+/// separately in `dropped_out_of_range_year`.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct LoadReport {
+    /// Hex SHA-256 of the input file's raw bytes, for provenance.
+    pub input_sha256: String,
     pub total_rows: usize,
     pub filtered_rows: usize,
     pub parse_errors: usize,
+    /// Retained rows whose coordinates came from the provincial capital or
+    /// a province average (see `CleanRecord::coords_imputed`).
     pub imputed_coords: usize,
+    /// Rows the CSV reader could not deserialize at all.
+    pub dropped_malformed: usize,
+    /// Rows with a missing or non-integer `FundingYear`.
+    pub dropped_bad_year: usize,
+    /// Rows with a valid `FundingYear` outside 2021–2023.
+    pub dropped_out_of_range_year: usize,
+    /// Rows with a missing, non-numeric, or non-positive budget.
+    pub dropped_bad_budget: usize,
+    /// Rows with a missing, non-numeric, or non-positive contract cost.
+    pub dropped_bad_cost: usize,
+    /// Rows whose budget, cost or delay fell outside the matching
+    /// `LoadOptions::plausibility` range.
+    pub dropped_implausible_budget: usize,
+    pub dropped_implausible_cost: usize,
+    pub dropped_implausible_delay: usize,
+    /// Rows with a missing or unparseable `StartDate`.
+    pub dropped_bad_date: usize,
+    /// Rows without a usable `ActualCompletionDate`, dropped because of
+    /// `CompletionImputation::Drop`. Not counted as parse errors.
+    pub dropped_no_completion: usize,
+    /// Rows whose `Status` says the project is not completed, dropped
+    /// because of `IncompleteStatus::Drop`. Not counted as parse errors.
+    pub dropped_incomplete: usize,
+    /// Retained rows whose `Status` says the project is not completed.
+    pub incomplete_status_rows: usize,
+    /// Retained rows whose completion date was imputed as start date plus
+    /// their TypeOfWork's median duration.
+    pub median_duration_imputed: usize,
+    /// Retained rows whose duration exceeded `LoadOptions::max_duration_days`.
+    pub long_duration_rows: usize,
+    /// Retained rows whose missing `MainIsland` was filled from the region
+    /// (see `LoadOptions::infer_main_island`).
+    pub inferred_main_island_rows: usize,
+    /// Retained rows where a currency prefix or trailing unit (see
+    /// `LoadOptions::currency_prefixes` and `unit_suffixes`) was stripped
+    /// from the budget or cost before parsing.
+    pub unit_stripped_rows: usize,
+    /// Retained rows whose budget and cost were swapped back as
+    /// transposed (see `LoadOptions::swap_factor`).
+    pub swapped_budget_rows: usize,
+    /// Retained rows whose delay was measured against
+    /// `PlannedCompletionDate` rather than `StartDate`.
+    pub planned_delay_rows: usize,
+    /// Body lines that exactly repeated the header record. These are not
+    /// data rows, so they count towards neither `total_rows` nor
+    /// `parse_errors`.
+    pub header_rows_skipped: usize,
+    /// `true` when `LoadOptions::skip_units_row` discarded the first data
+    /// row. That row counts towards neither `total_rows` nor `parse_errors`.
+    pub units_row_skipped: bool,
+    /// Problems that did not stop the load but that the user should see,
+    /// e.g. a file with a valid header and no data rows.
+    pub warnings: Vec<String>,
+    /// (column, cell text) -> occurrences, for numeric cells that could
+    /// not be parsed. Only collected with
+    /// `LoadOptions::rejected_formats_limit`, and only for that many
+    /// distinct values; later ones are counted in `rejected_formats_untracked`.
+    /// Written to `rejected_formats.csv` rather than `load_report.json`.
+    #[serde(skip)]
+    pub rejected_formats: BTreeMap<(&'static str, String), usize>,
+    pub rejected_formats_untracked: usize,
+}
+
+/// Load `path` with the reader matching its extension: Parquet for
+/// `.parquet`, CSV for everything else.
+pub fn load(
+    path: &str,
+    opts: &LoadOptions,
+) -> Result<(Vec<CleanRecord>, LoadReport), PipelineError> {
+    let is_parquet = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("parquet"));
+    // Hashed before parsing, so the digest covers the exact bytes on disk
+    // whatever the reader later skips or rejects.
+    let input_sha256 = sha256_file(path)?;
+    let (data, mut report) = if is_parquet {
+        load_and_clean_parquet(path, opts)?
+    } else {
+        load_and_clean(path, opts)?
+    };
+    report.input_sha256 = input_sha256;
+    Ok((data, report))
+}
+
+/// Fail when more than `max_pct` percent of the retained rows had their
+/// coordinates imputed (see `CleanRecord::coords_imputed`) under
+/// `--max-imputed-pct`, so a spatial analysis does not rest mostly on
+/// guessed locations.
+pub fn check_imputed_coords(report: &LoadReport, max_pct: f64) -> Result<(), PipelineError> {
+    let pct = safe_ratio(report.imputed_coords as f64, report.filtered_rows as f64) * 100.0;
+    if pct > max_pct {
+        return Err(PipelineError::Validation(format!(
+            "{:.2}% of retained rows have imputed coordinates, above --max-imputed-pct {}",
+            pct, max_pct
+        )));
+    }
+    Ok(())
 }
 
 /// Load the CSV at `path`, validate and enrich each row, and return a
 /// vector of `CleanRecord` plus a `LoadReport`.
 ///
 /// The high-level algorithm is:
-/// 1. Stream-deserialize `RawRow` values using `csv::Reader`.
+/// 1. Stream-deserialize `RawRow` values using `csv::Reader`, skipping any
+///    body line that repeats the header record (e.g. from concatenated
+///    exports) so it is not mistaken for a malformed data row.
 /// 2. For each row, validate funding year, numeric fields, and dates.
-/// 3. Compute derived metrics (cost savings, completion delay).
+/// 3. Compute derived metrics (cost savings, completion delay). Delay is
+///    `actual - planned` when both an `ActualCompletionDate` and a
+///    `PlannedCompletionDate` are present, otherwise `actual - start`
+///    (project duration). Each record's `delay_basis` says which was used.
 /// 4. Attempt to fill missing coordinates, first from project, then from
 ///    provincial capital, then later via province-level averages.
-/// 5. Drop rows that fail validation and increment `parse_errors`.
-pub fn load_and_clean(path: &str) -> Result<(Vec<CleanRecord>, LoadReport), Box<dyn Error>> {
+/// 5. Drop rows that fail validation and increment the matching
+///    `dropped_*` counter.
+///
+/// Budget and cost cells may carry a currency prefix from
+/// `opts.currency_prefixes` (e.g. `₱1,234.56`) or a trailing unit from
+/// `opts.unit_suffixes` (e.g. `1234.56 PHP`); both are stripped before
+/// parsing.
+/// With `opts.skip_units_row`, the first row after the header is dropped
+/// unread. With `opts.comment_char`, lines starting with it are skipped
+/// before they are counted.
+pub fn load_and_clean(
+    path: &str,
+    opts: &LoadOptions,
+) -> Result<(Vec<CleanRecord>, LoadReport), PipelineError> {
+    check_not_binary(path)?;
     // `flexible(true)` lets the reader tolerate rows with varying column
     // counts instead of failing hard on minor format issues.
-    let mut rdr = ReaderBuilder::new().flexible(true).from_path(path)?;
-    let mut total_rows = 0usize;
-    let mut parse_errors = 0usize;
-    let mut prelim: Vec<CleanRecord> = Vec::new();
-
-    // Stream over the CSV rows; each `result` is a `Result<RawRow, _>`.
-    for result in rdr.deserialize::<RawRow>() {
-        total_rows += 1;
-        let row = match result {
+    let mut rdr = ReaderBuilder::new()
+        .flexible(true)
+        .comment(opts.comment_char)
+        .from_path(path)?;
+    let mut cleaner = Cleaner::new(opts);
+
+    // Read raw string records first so repeated header lines can be
+    // recognized before deserializing each record into a `RawRow`.
+    // A UTF-8 byte order mark before the header is stripped by the reader.
+    let headers = rdr.headers()?.clone();
+    if headers.iter().all(|h| h.trim().is_empty()) {
+        return Err(PipelineError::Validation(format!(
+            "{} is empty: no header row found.",
+            path
+        )));
+    }
+    let canonical = remap_headers(&headers, opts, &mut cleaner.report.warnings);
+    let canonical = resolve_duplicate_headers(canonical, opts, &mut cleaner.report.warnings)?;
+    let mut records = rdr.records();
+    if opts.skip_units_row {
+        cleaner.report.units_row_skipped = records.next().is_some();
+    }
+    for result in records {
+        let record = match result {
             Ok(r) => r,
             Err(_) => {
-                parse_errors += 1;
+                cleaner.report.total_rows += 1;
+                cleaner.report.dropped_malformed += 1;
                 continue;
             }
         };
+        if is_header_repeat(&record, &headers) {
+            cleaner.report.header_rows_skipped += 1;
+            continue;
+        }
+        cleaner.add_record(&record, &canonical);
+    }
+    Ok(cleaner.finish())
+}
+
+/// Whether a `Status` cell means the project is finished. A blank status
+/// says nothing, so it counts as completed (as does a file without the
+/// column); anything else, e.g. `Not Yet Started` or `On-Going`, does not.
+fn is_completed_status(status: &str) -> bool {
+    let status = status.trim();
+    status.is_empty()
+        || ["completed", "complete", "done", "finished"]
+            .iter()
+            .any(|s| status.eq_ignore_ascii_case(s))
+}
+
+/// A trimmed text field, or `placeholder` when it is missing or blank.
+/// A whitespace-only cell (e.g. `"   "`) counts as missing, so it joins
+/// the placeholder group instead of forming an empty-named one.
+fn text_or_placeholder(value: Option<String>, placeholder: &str) -> String {
+    match value.as_deref().map(str::trim) {
+        Some(v) if !v.is_empty() => v.to_string(),
+        _ => placeholder.to_string(),
+    }
+}
+
+/// Rename the file's `headers` to the canonical DPWH names given by
+/// `opts.column_map` (canonical name -> source header), so `RawRow` can be
+/// deserialized from a dataset with its own column names. Unmapped headers
+/// keep their names. A source header the file does not have is reported in
+/// `warnings`; when it is present, any column that already carried the
+/// canonical name is blanked (and so ignored) to avoid a duplicate field.
+fn remap_headers(
+    headers: &StringRecord,
+    opts: &LoadOptions,
+    warnings: &mut Vec<String>,
+) -> StringRecord {
+    if opts.column_map.is_empty() {
+        return headers.clone();
+    }
+    let mut names: Vec<String> = headers.iter().map(|h| h.trim().to_string()).collect();
+    for (canonical, source) in &opts.column_map {
+        let Some(idx) = names.iter().position(|h| h == source) else {
+            warnings.push(format!(
+                "Column mapping: no column named {} (for {}).",
+                source, canonical
+            ));
+            continue;
+        };
+        for name in names.iter_mut() {
+            if name == canonical {
+                name.clear();
+            }
+        }
+        names[idx] = canonical.clone();
+    }
+    StringRecord::from(names)
+}
+
+/// Apply `opts.duplicate_headers` to `headers` that name a column more
+/// than once, which would otherwise make every row fail to deserialize.
+/// `Error` fails with the duplicated names and their 1-based column
+/// numbers; `FirstWins`/`LastWins` blank the other copies (so they are
+/// ignored) and note the choice in `warnings`.
+fn resolve_duplicate_headers(
+    headers: StringRecord,
+    opts: &LoadOptions,
+    warnings: &mut Vec<String>,
+) -> Result<StringRecord, PipelineError> {
+    let mut columns: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, name) in headers.iter().enumerate() {
+        if !name.is_empty() {
+            columns.entry(name).or_default().push(i);
+        }
+    }
+    columns.retain(|_, idx| idx.len() > 1);
+    if columns.is_empty() {
+        return Ok(headers);
+    }
+    let listed = |idx: &[usize]| {
+        idx.iter()
+            .map(|i| (i + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    // The one column each duplicated header is read from.
+    let kept: BTreeMap<&str, usize> = match opts.duplicate_headers {
+        DuplicateHeaders::Error => {
+            let found: Vec<String> = columns
+                .iter()
+                .map(|(name, idx)| format!("{} (columns {})", name, listed(idx)))
+                .collect();
+            return Err(PipelineError::Validation(format!(
+                "Duplicate column headers: {}. Remove the extra columns or pass \
+                 --duplicate-headers first|last.",
+                found.join("; ")
+            )));
+        }
+        DuplicateHeaders::FirstWins => columns.iter().map(|(n, idx)| (*n, idx[0])).collect(),
+        DuplicateHeaders::LastWins => columns
+            .iter()
+            .map(|(n, idx)| (*n, idx[idx.len() - 1]))
+            .collect(),
+    };
+    for (name, idx) in &columns {
+        warnings.push(format!(
+            "Duplicate column {} (columns {}): reading column {}.",
+            name,
+            listed(idx),
+            kept[name] + 1
+        ));
+    }
+    let blanked: Vec<&str> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, name)| match kept.get(name) {
+            Some(&k) if k != i => "",
+            _ => name,
+        })
+        .collect();
+    Ok(StringRecord::from(blanked))
+}
+
+/// Load a Parquet file with the same columns as the CSV export and run it
+/// through the same validation as `load_and_clean`.
+///
+/// Each row is rendered to strings column by column (nulls become empty
+/// cells, `DATE`/timestamp columns become `YYYY-MM-DD`) and then
+/// deserialized into `RawRow` by column name, so the two formats share
+/// every cleaning rule and `LoadReport` counter.
+pub fn load_and_clean_parquet(
+    path: &str,
+    opts: &LoadOptions,
+) -> Result<(Vec<CleanRecord>, LoadReport), PipelineError> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let headers: StringRecord = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect();
+    let mut cleaner = Cleaner::new(opts);
+    let headers = remap_headers(&headers, opts, &mut cleaner.report.warnings);
+    let headers = resolve_duplicate_headers(headers, opts, &mut cleaner.report.warnings)?;
+    for row in reader.get_row_iter(None)? {
+        let row = match row {
+            Ok(r) => r,
+            Err(_) => {
+                cleaner.report.total_rows += 1;
+                cleaner.report.dropped_malformed += 1;
+                continue;
+            }
+        };
+        let record: StringRecord = row
+            .get_column_iter()
+            .map(|(_, field)| parquet_field_to_string(field))
+            .collect();
+        cleaner.add_record(&record, &headers);
+    }
+    Ok(cleaner.finish())
+}
+
+/// Render a Parquet cell the way the CSV export would spell it.
+///
+/// Floats are written with `{}` rather than `Field`'s own `Display`, which
+/// switches to exponent notation that `parse_f64_safe` rejects.
+fn parquet_field_to_string(field: &Field) -> String {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    match field {
+        Field::Null => String::new(),
+        Field::Str(s) => s.clone(),
+        Field::Float(v) => format!("{}", v),
+        Field::Double(v) => format!("{}", v),
+        Field::Date(days) => (epoch + Duration::days(*days as i64))
+            .format("%Y-%m-%d")
+            .to_string(),
+        Field::TimestampMillis(ms) => (epoch + Duration::milliseconds(*ms))
+            .format("%Y-%m-%d")
+            .to_string(),
+        Field::TimestampMicros(us) => (epoch + Duration::microseconds(*us))
+            .format("%Y-%m-%d")
+            .to_string(),
+        Field::Bytes(b) => String::from_utf8_lossy(b.data()).into_owned(),
+        other => other.to_string(),
+    }
+}
+
+/// Accumulates validated `CleanRecord`s and the matching `LoadReport`
+/// counters, independent of the input file format.
+struct Cleaner<'a> {
+    opts: &'a LoadOptions,
+    records: Vec<CleanRecord>,
+    report: LoadReport,
+    /// Start-to-completion days of rows with a real completion date, per
+    /// TypeOfWork, for `CompletionImputation::TypeMedianDuration`.
+    durations_by_type: HashMap<String, Vec<f64>>,
+    /// Index into `records` and start date of rows waiting for a
+    /// type-median completion date.
+    pending_completion: Vec<(usize, NaiveDate)>,
+}
+
+impl<'a> Cleaner<'a> {
+    fn new(opts: &'a LoadOptions) -> Self {
+        Cleaner {
+            opts,
+            records: Vec::new(),
+            report: LoadReport::default(),
+            durations_by_type: HashMap::new(),
+            pending_completion: Vec::new(),
+        }
+    }
+
+    /// Count one data row, deserialize it by column name, and clean it.
+    fn add_record(&mut self, record: &StringRecord, headers: &StringRecord) {
+        self.report.total_rows += 1;
+        // Parquet rows carry no position; their row number stands in.
+        let line = record
+            .position()
+            .map_or(self.report.total_rows as u64, |p| p.line());
+        match record.deserialize::<RawRow>(Some(headers)) {
+            Ok(row) => self.add(row, line),
+            Err(_) => self.report.dropped_malformed += 1,
+        }
+    }
+
+    /// The row's ContractCost when `budget` and the cost cell `raw_cost`
+    /// look transposed under `LoadOptions::swap_factor`: the cost is more
+    /// than the factor times the budget, and swapped, the budget and cost
+    /// are both within the plausibility ranges. A genuine overrun is rarely
+    /// that large, so the factor should be generous (e.g. 10). `None` when
+    /// the option is off or the pair does not qualify.
+    fn transposed_cost(&self, budget: f64, raw_cost: Option<&str>) -> Option<f64> {
+        let factor = self.opts.swap_factor?;
+        let (cost, _) = parse_f64_with_units(
+            raw_cost,
+            &self.opts.currency_prefixes,
+            &self.opts.unit_suffixes,
+        );
+        let cost = cost.filter(|c| c.is_finite() && *c > budget * factor)?;
+        let ranges = &self.opts.plausibility;
+        (ranges.approved_budget.contains(cost) && ranges.contract_cost.contains(budget))
+            .then_some(cost)
+    }
+
+    /// Record `raw` as a rejected numeric format of `column` when it is
+    /// non-empty but did not parse.
+    fn note_rejected(&mut self, column: &'static str, raw: Option<&str>, parsed: Option<f64>) {
+        let Some(limit) = self.opts.rejected_formats_limit else {
+            return;
+        };
+        let raw = raw.unwrap_or("").trim();
+        if parsed.is_some() || raw.is_empty() {
+            return;
+        }
+        let rejected = &mut self.report.rejected_formats;
+        let key = (column, raw.to_string());
+        if let Some(count) = rejected.get_mut(&key) {
+            *count += 1;
+        } else if rejected.len() < limit {
+            rejected.insert(key, 1);
+        } else {
+            self.report.rejected_formats_untracked += 1;
+        }
+    }
 
-        // Filter FundingYear 2021..=2023
-        let funding_year = match parse_i32_safe(row.funding_year.as_deref()) {
-            Some(y) if (2021..=2023).contains(&y) => y,
-            _ => continue,
+    /// Validate one `RawRow`; either keep it as a `CleanRecord` or bump the
+    /// `dropped_*` counter for the first check it fails.
+    fn add(&mut self, row: RawRow, source_line: u64) {
+        // Keep only FUNDING_YEARS
+        let funding_year = match parse_year_safe(row.funding_year.as_deref()) {
+            Some(y) if FUNDING_YEARS.contains(&y) => y,
+            Some(_) => {
+                self.report.dropped_out_of_range_year += 1;
+                return;
+            }
+            None => {
+                self.report.dropped_bad_year += 1;
+                return;
+            }
         };
 
-        let approved_budget = match parse_f64_safe(row.approved_budget_for_contract.as_deref()) {
+        let (budget, budget_stripped) = parse_f64_with_units(
+            row.approved_budget_for_contract.as_deref(),
+            &self.opts.currency_prefixes,
+            &self.opts.unit_suffixes,
+        );
+        self.note_rejected(
+            "ApprovedBudgetForContract",
+            row.approved_budget_for_contract.as_deref(),
+            budget,
+        );
+        let approved_budget = match budget {
             Some(v) if v > 0.0 => v,
             _ => {
-                parse_errors += 1;
-                continue;
+                self.report.dropped_bad_budget += 1;
+                return;
             }
         };
-        let contract_cost = match parse_f64_safe(row.contract_cost.as_deref()) {
+        // A transposed pair is swapped back before any range check, so
+        // the budget is checked as the budget it really is.
+        let transposed_cost = self.transposed_cost(approved_budget, row.contract_cost.as_deref());
+        let (approved_budget, read_budget) = match transposed_cost {
+            Some(cost) => (cost, approved_budget),
+            None => (approved_budget, approved_budget),
+        };
+        if !self
+            .opts
+            .plausibility
+            .approved_budget
+            .contains(approved_budget)
+        {
+            self.report.dropped_implausible_budget += 1;
+            return;
+        }
+        let (cost, cost_stripped) = parse_f64_with_units(
+            row.contract_cost.as_deref(),
+            &self.opts.currency_prefixes,
+            &self.opts.unit_suffixes,
+        );
+        self.note_rejected("ContractCost", row.contract_cost.as_deref(), cost);
+        let contract_cost = match cost {
+            Some(_) if transposed_cost.is_some() => read_budget,
             Some(v) if v > 0.0 => v,
             _ => {
-                parse_errors += 1;
-                continue;
+                self.report.dropped_bad_cost += 1;
+                return;
             }
         };
+        if !self.opts.plausibility.contract_cost.contains(contract_cost) {
+            self.report.dropped_implausible_cost += 1;
+            return;
+        }
         // Both `StartDate` and `ActualCompletionDate` are required to
         // compute a completion delay. Missing start dates are treated as
-        // fatal parse errors; missing completion dates are imputed from
-        // the start date 
+        // fatal parse errors; missing completion dates are handled per
+        // `LoadOptions::completion_imputation` (by default, the start date).
         let start_date: NaiveDate = match parse_date_safe(row.start_date.as_deref()) {
             Some(d) => d,
             None => {
-                parse_errors += 1;
-                continue;
+                self.report.dropped_bad_date += 1;
+                return;
             }
         };
-        let actual_date: NaiveDate = match parse_date_safe(row.actual_completion_date.as_deref()) {
-            Some(d) => d,
-            None => start_date,
-        };
+        let actual_parsed = parse_date_safe(row.actual_completion_date.as_deref());
+        if actual_parsed.is_none() && self.opts.completion_imputation == CompletionImputation::Drop
+        {
+            self.report.dropped_no_completion += 1;
+            return;
+        }
+        let incomplete = row
+            .status
+            .as_deref()
+            .is_some_and(|s| !is_completed_status(s));
+        if incomplete {
+            if self.opts.incomplete_status == IncompleteStatus::Drop {
+                self.report.dropped_incomplete += 1;
+                return;
+            }
+            self.report.incomplete_status_rows += 1;
+        }
+        let actual_date: NaiveDate = actual_parsed.unwrap_or(start_date);
+        // A row waiting for a type-median completion date has no delay yet;
+        // `finish` checks it once the date is filled in.
+        let awaits_median = actual_parsed.is_none()
+            && self.opts.completion_imputation == CompletionImputation::TypeMedianDuration;
+        let planned_date = parse_date_safe(row.planned_completion_date.as_deref());
 
         // Derived metrics:
-        // - `completion_delay_days` is the raw day difference.
-        // - `cost_savings` is ApprovedBudget - ContractCost.
-        let completion_delay_days = days_diff(start_date, actual_date);
+        // - `completion_delay_days` is the day difference against the plan
+        //   when one exists (and the completion date is real, not imputed),
+        //   otherwise the start-to-completion duration.
+        // - `cost_savings` is ApprovedBudget - ContractCost, after both are
+        //   restated in base-year pesos when a deflator table is set.
+        let (completion_delay_days, delay_basis) = match (planned_date, actual_parsed) {
+            (Some(planned), Some(actual)) => (
+                self.opts.delay_days.between(planned, actual),
+                DelayBasis::Planned,
+            ),
+            _ => (
+                self.opts.delay_days.between(start_date, actual_date),
+                DelayBasis::Duration,
+            ),
+        };
+        if !awaits_median
+            && !self
+                .opts
+                .plausibility
+                .completion_delay_days
+                .contains(completion_delay_days)
+        {
+            self.report.dropped_implausible_delay += 1;
+            return;
+        }
+        let factor = self.opts.price_factor(funding_year);
+        let approved_budget = approved_budget * factor;
+        let contract_cost = contract_cost * factor;
         let cost_savings = approved_budget - contract_cost;
 
-        let region = row
-            .region
-            .unwrap_or_else(|| "Unknown".to_string())
-            .trim()
-            .to_string();
-        let main_island = row
-            .main_island
-            .unwrap_or_else(|| "Unknown".to_string())
-            .trim()
-            .to_string();
-        let province = row
-            .province
-            .unwrap_or_else(|| "Unknown".to_string())
-            .trim()
-            .to_string();
-        let type_of_work = row
-            .type_of_work
-            .unwrap_or_else(|| "Unspecified".to_string())
-            .trim()
-            .to_string();
-        let contractor = row
-            .contractor
-            .unwrap_or_else(|| "Unknown Contractor".to_string())
-            .trim()
-            .to_string();
+        let region = text_or_placeholder(row.region, &self.opts.placeholders.region);
+        let mut main_island =
+            text_or_placeholder(row.main_island, &self.opts.placeholders.main_island);
+        if self.opts.infer_main_island && main_island == self.opts.placeholders.main_island {
+            if let Some(island) = island_for_region(&region) {
+                main_island = island.to_string();
+                self.report.inferred_main_island_rows += 1;
+            }
+        }
+        let province = text_or_placeholder(row.province, &self.opts.placeholders.province);
+        let type_of_work =
+            text_or_placeholder(row.type_of_work, &self.opts.placeholders.type_of_work);
+        let contractor = text_or_placeholder(row.contractor, &self.opts.placeholders.contractor);
 
         // Prefer explicit project coordinates, but fall back to the
         // provincial capital coordinates if needed.
         let mut lat = parse_f64_safe(row.project_latitude.as_deref());
         let mut lon = parse_f64_safe(row.project_longitude.as_deref());
+        self.note_rejected("ProjectLatitude", row.project_latitude.as_deref(), lat);
+        self.note_rejected("ProjectLongitude", row.project_longitude.as_deref(), lon);
+        let has_project_coords = lat.is_some() && lon.is_some();
         if lat.is_none() || lon.is_none() {
             // Try provincial capital
-            if let (Some(clat), Some(clon)) = (
-                parse_f64_safe(row.provincial_capital_latitude.as_deref()),
-                parse_f64_safe(row.provincial_capital_longitude.as_deref()),
-            ) {
+            let capital_lat = parse_f64_safe(row.provincial_capital_latitude.as_deref());
+            let capital_lon = parse_f64_safe(row.provincial_capital_longitude.as_deref());
+            self.note_rejected(
+                "ProvincialCapitalLatitude",
+                row.provincial_capital_latitude.as_deref(),
+                capital_lat,
+            );
+            self.note_rejected(
+                "ProvincialCapitalLongitude",
+                row.provincial_capital_longitude.as_deref(),
+                capital_lon,
+            );
+            if let (Some(clat), Some(clon)) = (capital_lat, capital_lon) {
                 lat = lat.or(Some(clat));
                 lon = lon.or(Some(clon));
             }
         }
+        let coord_source = if has_project_coords {
+            Some(CoordSource::Project)
+        } else if lat.is_some() && lon.is_some() {
+            Some(CoordSource::ProvincialCapital)
+        } else {
+            None
+        };
 
-        prelim.push(CleanRecord {
+        match actual_parsed {
+            Some(actual) => self
+                .durations_by_type
+                .entry(type_of_work.clone())
+                .or_default()
+                .push(days_diff(start_date, actual)),
+            None if awaits_median => {
+                self.pending_completion
+                    .push((self.records.len(), start_date));
+            }
+            None => {}
+        }
+        if budget_stripped || cost_stripped {
+            self.report.unit_stripped_rows += 1;
+        }
+        if transposed_cost.is_some() {
+            self.report.swapped_budget_rows += 1;
+        }
+        if delay_basis == DelayBasis::Planned {
+            self.report.planned_delay_rows += 1;
+        }
+        // Only a reported completion date gives a real duration here; a
+        // type-median one is checked in `finish`.
+        let long_duration = match (actual_parsed, self.opts.max_duration_days) {
+            (Some(actual), Some(max_days)) => days_diff(start_date, actual) > max_days,
+            _ => false,
+        };
+        if long_duration {
+            self.report.long_duration_rows += 1;
+        }
+        self.records.push(CleanRecord {
+            source_line,
             funding_year,
             region,
             main_island,
@@ -148,42 +721,957 @@ pub fn load_and_clean(path: &str) -> Result<(Vec<CleanRecord>, LoadReport), Box<
             contract_cost,
             cost_savings,
             completion_delay_days,
+            delay_basis,
+            completion_date: actual_date,
+            completion_date_imputed: actual_parsed.is_none(),
             lat,
             lon,
+            coord_source,
+            delay_excluded: (long_duration && self.opts.exclude_long_duration)
+                || (incomplete && self.opts.incomplete_status == IncompleteStatus::ExcludeDelay),
         });
     }
 
-    // Province-level averages imputation if coordinates are still
-    // missing: compute (sum_lat, sum_lon, count) per province.
-    let mut by_prov: HashMap<String, (f64, f64, usize)> = HashMap::new();
-    for r in &prelim {
-        if let (Some(lat), Some(lon)) = (r.lat, r.lon) {
-            let e = by_prov.entry(r.province.clone()).or_insert((0.0, 0.0, 0));
-            e.0 += lat;
-            e.1 += lon;
-            e.2 += 1;
+    /// Fill in type-median completion dates, impute remaining coordinates
+    /// from province averages and total up the report.
+    fn finish(self) -> (Vec<CleanRecord>, LoadReport) {
+        let Cleaner {
+            opts,
+            records: mut prelim,
+            mut report,
+            durations_by_type,
+            pending_completion,
+            ..
+        } = self;
+        // Rows without a completion date were provisionally given their
+        // start date; move them to start + the type's median duration, then
+        // run the delay range and duration cap checks `add` deferred.
+        let median_days: HashMap<String, f64> = durations_by_type
+            .into_iter()
+            .map(|(tow, durations)| (tow, median(durations).round()))
+            .collect();
+        let mut implausible: HashSet<usize> = HashSet::new();
+        for (i, start_date) in pending_completion {
+            let r = &mut prelim[i];
+            let imputed_days = median_days.get(&r.type_of_work).copied();
+            if let Some(days) = imputed_days {
+                r.completion_date = start_date + Duration::days(days as i64);
+                r.completion_delay_days = opts.delay_days.between(start_date, r.completion_date);
+            }
+            if !opts
+                .plausibility
+                .completion_delay_days
+                .contains(r.completion_delay_days)
+            {
+                implausible.insert(i);
+                continue;
+            }
+            if let Some(days) = imputed_days {
+                report.median_duration_imputed += 1;
+                if opts
+                    .max_duration_days
+                    .is_some_and(|max_days| days > max_days)
+                {
+                    report.long_duration_rows += 1;
+                    r.delay_excluded |= opts.exclude_long_duration;
+                }
+            }
+        }
+        if !implausible.is_empty() {
+            report.dropped_implausible_delay += implausible.len();
+            let mut i = 0;
+            prelim.retain(|_| {
+                i += 1;
+                !implausible.contains(&(i - 1))
+            });
+        }
+
+        // Province-level averages imputation if coordinates are still
+        // missing: compute (sum_lat, sum_lon, count) per province.
+        let mut by_prov: HashMap<String, (f64, f64, usize)> = HashMap::new();
+        for r in &prelim {
+            if let (Some(lat), Some(lon)) = (r.lat, r.lon) {
+                let e = by_prov.entry(r.province.clone()).or_insert((0.0, 0.0, 0));
+                e.0 += lat;
+                e.1 += lon;
+                e.2 += 1;
+            }
+        }
+
+        for r in &mut prelim {
+            if r.lat.is_none() || r.lon.is_none() {
+                if let Some((s_lat, s_lon, c)) = by_prov.get(&r.province) {
+                    if *c > 0 {
+                        r.lat = r.lat.or(Some(s_lat / *c as f64));
+                        r.lon = r.lon.or(Some(s_lon / *c as f64));
+                        r.coord_source = Some(CoordSource::ProvinceAverage);
+                    }
+                }
+            }
+        }
+
+        report.filtered_rows = prelim.len();
+        report.parse_errors = report.dropped_malformed
+            + report.dropped_bad_year
+            + report.dropped_bad_budget
+            + report.dropped_bad_cost
+            + report.dropped_implausible_budget
+            + report.dropped_implausible_cost
+            + report.dropped_implausible_delay
+            + report.dropped_bad_date;
+        report.imputed_coords = prelim.iter().filter(|r| r.coords_imputed()).count();
+        if report.total_rows == 0 {
+            report
+                .warnings
+                .push("Header parsed but no data rows found.".to_string());
+        }
+        (prelim, report)
+    }
+}
+
+/// Fail fast when the file at `path` starts with the signature of a
+/// common binary format, instead of letting the CSV reader turn it into
+/// thousands of garbage rows and parse errors.
+fn check_not_binary(path: &str) -> Result<(), PipelineError> {
+    const SIGNATURES: [(&[u8], &str); 4] = [
+        (b"PK\x03\x04", "a ZIP archive (e.g. an .xlsx workbook)"),
+        (b"%PDF", "a PDF document"),
+        (
+            b"\xD0\xCF\x11\xE0",
+            "a legacy Office document (e.g. an .xls workbook)",
+        ),
+        (b"\x1F\x8B", "a gzip-compressed file"),
+    ];
+    let mut head = [0u8; 8];
+    let n = File::open(path)?.read(&mut head)?;
+    for (magic, kind) in SIGNATURES {
+        if head[..n].starts_with(magic) {
+            return Err(PipelineError::Validation(format!(
+                "{} doesn't look like a CSV file: it appears to be {}. \
+                 Please export the data as CSV and try again.",
+                path, kind
+            )));
         }
     }
+    Ok(())
+}
+
+/// `true` when `record` is a copy of the header line (field by field,
+/// ignoring surrounding whitespace).
+fn is_header_repeat(record: &StringRecord, headers: &StringRecord) -> bool {
+    record.len() == headers.len()
+        && record
+            .iter()
+            .zip(headers.iter())
+            .all(|(a, b)| a.trim() == b.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PlausibilityRanges, ValueRange};
+    use std::collections::BTreeMap;
+    use std::io::Write;
+
+    const HEADER: &str = "MainIsland,Region,Province,TypeOfWork,FundingYear,\
+        ApprovedBudgetForContract,ContractCost,ActualCompletionDate,Contractor,StartDate,\
+        ProjectLatitude,ProjectLongitude,ProvincialCapitalLatitude,ProvincialCapitalLongitude";
 
-    let mut imputed_coords = 0usize;
-    for r in &mut prelim {
-        if r.lat.is_none() || r.lon.is_none() {
-            if let Some((s_lat, s_lon, c)) = by_prov.get(&r.province) {
-                if *c > 0 {
-                    r.lat = r.lat.or(Some(s_lat / *c as f64));
-                    r.lon = r.lon.or(Some(s_lon / *c as f64));
-                    imputed_coords += 1;
+    /// A data row for `HEADER`: a project in Region I with the given
+    /// funding year, budget, cost, start and completion dates.
+    fn row(year: &str, budget: &str, cost: &str, start: &str, actual: &str) -> String {
+        format!(
+            "Luzon,Region I,Ilocos Norte,Flood Control,{},{},{},{},ABC CORP,{},18.2,120.6,18.19,120.59",
+            year, budget, cost, actual, start
+        )
+    }
+
+    /// A valid 2022 row: 1,000,000 budget, 900,000 cost, 30 days long.
+    fn good_row() -> String {
+        row("2022", "1000000", "900000", "2022-01-01", "2022-01-31")
+    }
+
+    /// Write `contents` to a temporary file with the given extension.
+    fn temp_file(contents: &[u8], extension: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(extension)
+            .tempfile()
+            .unwrap();
+        file.write_all(contents).unwrap();
+        file
+    }
+
+    /// Load `HEADER` followed by `rows` as a CSV file.
+    fn load_rows(rows: &[String], opts: &LoadOptions) -> (Vec<CleanRecord>, LoadReport) {
+        load_csv(&format!("{}\n{}\n", HEADER, rows.join("\n")), opts)
+    }
+
+    /// Load `contents` (header included) as a CSV file.
+    fn load_csv(contents: &str, opts: &LoadOptions) -> (Vec<CleanRecord>, LoadReport) {
+        let file = temp_file(contents.as_bytes(), ".csv");
+        load_and_clean(file.path().to_str().unwrap(), opts).unwrap()
+    }
+
+    #[test]
+    fn each_drop_path_is_counted() {
+        let rows = vec![
+            good_row(),
+            row("2019", "1000000", "900000", "2019-01-01", "2019-01-31"),
+            row("twenty", "1000000", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "-5", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "1000000", "n/a", "2022-01-01", "2022-01-31"),
+            row("2022", "1000000", "900000", "not a date", "2022-01-31"),
+        ];
+        let (data, report) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(data.len(), 1);
+        assert_eq!(report.total_rows, 7);
+        assert_eq!(report.filtered_rows, 1);
+        assert_eq!(report.dropped_out_of_range_year, 1);
+        assert_eq!(report.dropped_bad_year, 1);
+        assert_eq!(report.dropped_bad_budget, 2);
+        assert_eq!(report.dropped_bad_cost, 1);
+        assert_eq!(report.dropped_bad_date, 1);
+        assert_eq!(report.dropped_malformed, 0);
+        // An out-of-range year is not a parse error.
+        assert_eq!(report.parse_errors, 5);
+    }
+
+    #[test]
+    fn unreadable_row_is_counted_as_malformed() {
+        let mut contents = format!("{}\n{}\n", HEADER, good_row()).into_bytes();
+        contents.extend_from_slice(b"Luzon,Region \xFF,Ilocos Norte\n");
+        let file = temp_file(&contents, ".csv");
+        let (data, report) =
+            load_and_clean(file.path().to_str().unwrap(), &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(report.total_rows, 2);
+        assert_eq!(report.dropped_malformed, 1);
+        assert_eq!(report.parse_errors, 1);
+    }
+
+    #[test]
+    fn delay_is_measured_against_the_planned_date_when_present() {
+        let contents = "FundingYear,ApprovedBudgetForContract,ContractCost,StartDate,\
+                        PlannedCompletionDate,ActualCompletionDate\n\
+                        2022,1000,900,2022-01-01,2022-03-01,2022-03-11\n\
+                        2022,1000,900,2022-01-01,,2022-03-11\n\
+                        2022,1000,900,2022-01-01,2022-03-01,\n";
+        let (data, report) = load_csv(contents, &LoadOptions::default());
+        assert_eq!(data.len(), 3);
+        // 10 days late against the plan, not 69 days from the start.
+        assert_eq!(data[0].completion_delay_days, 10.0);
+        assert_eq!(data[0].delay_basis, DelayBasis::Planned);
+        // No plan: start-to-completion duration.
+        assert_eq!(data[1].completion_delay_days, 69.0);
+        assert_eq!(data[1].delay_basis, DelayBasis::Duration);
+        // No actual completion date: the imputed date is not compared
+        // with the plan.
+        assert_eq!(data[2].completion_delay_days, 0.0);
+        assert_eq!(data[2].delay_basis, DelayBasis::Duration);
+        assert_eq!(report.planned_delay_rows, 1);
+    }
+
+    #[test]
+    fn repeated_header_line_is_skipped() {
+        let rows = vec![good_row(), HEADER.to_string(), good_row()];
+        let (data, report) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(data.len(), 2);
+        assert_eq!(report.header_rows_skipped, 1);
+        assert_eq!(report.total_rows, 2);
+        assert_eq!(report.parse_errors, 0);
+    }
+
+    #[test]
+    fn pdf_file_is_rejected_before_parsing() {
+        let file = temp_file(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n1 0 obj\n", ".csv");
+        let path = file.path().to_str().unwrap();
+        match load_and_clean(path, &LoadOptions::default()) {
+            Err(PipelineError::Validation(message)) => {
+                assert!(
+                    message.contains("appears to be a PDF document"),
+                    "{}",
+                    message
+                )
+            }
+            other => panic!(
+                "expected a validation error, got {:?}",
+                other.map(|(d, _)| d.len())
+            ),
+        }
+    }
+
+    /// Write the rows of `csv` (header included) as a Parquet file with
+    /// typed columns: `FundingYear` as INT32, amounts and coordinates as
+    /// DOUBLE, dates as DATE and everything else as UTF8 strings. Empty
+    /// cells become nulls.
+    fn write_parquet(csv: &str, path: &Path) {
+        use parquet::basic::Type as PhysicalType;
+        use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int32Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let rows: Vec<StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        let columns: Vec<String> = headers
+            .iter()
+            .map(|name| match name {
+                "FundingYear" => format!("OPTIONAL INT32 {};", name),
+                "StartDate" | "ActualCompletionDate" => format!("OPTIONAL INT32 {} (DATE);", name),
+                "ApprovedBudgetForContract"
+                | "ContractCost"
+                | "ProjectLatitude"
+                | "ProjectLongitude"
+                | "ProvincialCapitalLatitude"
+                | "ProvincialCapitalLongitude" => format!("OPTIONAL DOUBLE {};", name),
+                _ => format!("OPTIONAL BINARY {} (UTF8);", name),
+            })
+            .collect();
+        let schema = Arc::new(
+            parse_message_type(&format!("message dpwh {{ {} }}", columns.join(" "))).unwrap(),
+        );
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(File::create(path).unwrap(), schema.clone(), props).unwrap();
+        let mut group = writer.next_row_group().unwrap();
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        for (index, field) in schema.get_fields().iter().enumerate() {
+            let cells: Vec<&str> = rows
+                .iter()
+                .map(|r| &r[index])
+                .filter(|c| !c.is_empty())
+                .collect();
+            let levels: Vec<i16> = rows
+                .iter()
+                .map(|r| i16::from(!r[index].is_empty()))
+                .collect();
+            let mut column = group.next_column().unwrap().unwrap();
+            match field.get_physical_type() {
+                PhysicalType::INT32 => {
+                    let values: Vec<i32> = cells
+                        .iter()
+                        .map(|c| match NaiveDate::parse_from_str(c, "%Y-%m-%d") {
+                            Ok(date) => (date - epoch).num_days() as i32,
+                            Err(_) => c.parse().unwrap(),
+                        })
+                        .collect();
+                    column
+                        .typed::<Int32Type>()
+                        .write_batch(&values, Some(&levels), None)
+                }
+                PhysicalType::DOUBLE => {
+                    let values: Vec<f64> = cells.iter().map(|c| c.parse().unwrap()).collect();
+                    column
+                        .typed::<DoubleType>()
+                        .write_batch(&values, Some(&levels), None)
+                }
+                _ => {
+                    let values: Vec<ByteArray> =
+                        cells.iter().map(|c| ByteArray::from(*c)).collect();
+                    column
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)
                 }
             }
+            .unwrap();
+            column.close().unwrap();
         }
+        group.close().unwrap();
+        writer.close().unwrap();
     }
 
-    let filtered_rows = prelim.len();
-    let report = LoadReport {
-        total_rows,
-        filtered_rows,
-        parse_errors,
-        imputed_coords,
-    };
-    Ok((prelim, report))
+    #[test]
+    fn parquet_and_csv_give_the_same_records() {
+        let rows = [
+            good_row(),
+            row("2021", "2500000.5", "2400000", "2021-03-01", "2021-06-15"),
+            row("2023", "1000000", "900000", "2023-02-01", ""),
+            row("2019", "1000000", "900000", "2019-01-01", "2019-01-31"),
+            row("2022", "-5", "900000", "2022-01-01", "2022-01-31"),
+        ];
+        let csv = format!("{}\n{}\n", HEADER, rows.join("\n"));
+        let opts = LoadOptions::default();
+        let (from_csv, csv_report) = load_csv(&csv, &opts);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.parquet");
+        write_parquet(&csv, &path);
+        let (from_parquet, parquet_report) = load(path.to_str().unwrap(), &opts).unwrap();
+
+        assert_eq!(from_csv.len(), 3);
+        assert_eq!(from_parquet.len(), from_csv.len());
+        assert_eq!(parquet_report.total_rows, csv_report.total_rows);
+        assert_eq!(
+            parquet_report.dropped_out_of_range_year,
+            csv_report.dropped_out_of_range_year
+        );
+        assert_eq!(
+            parquet_report.dropped_bad_budget,
+            csv_report.dropped_bad_budget
+        );
+        assert_eq!(
+            parquet_report.dropped_no_completion,
+            csv_report.dropped_no_completion
+        );
+        // Only the source line differs: a Parquet row has no line number.
+        for (p, c) in from_parquet.iter().zip(&from_csv) {
+            let c = CleanRecord {
+                source_line: p.source_line,
+                ..c.clone()
+            };
+            assert_eq!(format!("{:?}", p), format!("{:?}", c));
+        }
+    }
+
+    #[test]
+    fn deflators_restate_2023_pesos_in_base_year_prices() {
+        let rows = vec![
+            row("2021", "1000000", "900000", "2021-01-01", "2021-01-31"),
+            row("2023", "1000000", "900000", "2023-01-01", "2023-01-31"),
+        ];
+        let opts = LoadOptions {
+            deflators: BTreeMap::from([(2021, 100.0), (2022, 110.0), (2023, 125.0)]),
+            deflator_base_year: Some(2021),
+            ..LoadOptions::default()
+        };
+        let (data, _) = load_rows(&rows, &opts);
+        let (base, later) = (&data[0], &data[1]);
+        assert_eq!(base.approved_budget, 1_000_000.0);
+        assert_eq!(base.contract_cost, 900_000.0);
+        // 2023 pesos are worth 100/125 of a 2021 peso.
+        assert!((later.approved_budget - 800_000.0).abs() < 1e-6);
+        assert!((later.contract_cost - 720_000.0).abs() < 1e-6);
+        assert!((later.cost_savings - 80_000.0).abs() < 1e-6);
+
+        let (unadjusted, _) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(unadjusted[1].approved_budget, 1_000_000.0);
+    }
+
+    #[test]
+    fn header_only_file_loads_empty_with_a_warning() {
+        for contents in [format!("{}\n", HEADER), format!("\u{feff}{}\r\n", HEADER)] {
+            let (data, report) = load_csv(&contents, &LoadOptions::default());
+            assert!(data.is_empty());
+            assert_eq!(report.total_rows, 0);
+            assert_eq!(report.parse_errors, 0);
+            assert_eq!(
+                report.warnings,
+                vec!["Header parsed but no data rows found."]
+            );
+        }
+    }
+
+    #[test]
+    fn units_row_is_skipped_when_requested() {
+        let units = ",,,,,PHP,PHP,YYYY-MM-DD,,YYYY-MM-DD,deg,deg,deg,deg".to_string();
+        let rows = [units, good_row(), good_row()];
+        let opts = LoadOptions {
+            skip_units_row: true,
+            ..LoadOptions::default()
+        };
+        let (records, report) = load_rows(&rows, &opts);
+        assert!(report.units_row_skipped);
+        assert_eq!(records.len(), 2);
+        assert_eq!(report.total_rows, 2);
+        assert_eq!(report.parse_errors, 0);
+
+        // Without the option the units row is read as data and rejected.
+        let (records, report) = load_rows(&rows, &LoadOptions::default());
+        assert!(!report.units_row_skipped);
+        assert_eq!(records.len(), 2);
+        assert_eq!(report.total_rows, 3);
+        assert_eq!(report.parse_errors, 1);
+    }
+
+    #[test]
+    fn quoted_embedded_newline_stays_in_one_record() {
+        let split = good_row().replace("ABC CORP", "\"ABC\r\nCORP\"");
+        // Mixed line endings between records.
+        let contents = format!("{}\r\n{}\n{}\r\n", HEADER, split, good_row());
+        let (data, report) = load_csv(&contents, &LoadOptions::default());
+        assert_eq!(report.total_rows, 2);
+        assert_eq!(report.parse_errors, 0);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].contractor, "ABC\r\nCORP");
+        assert_eq!(data[0].approved_budget, 1_000_000.0);
+        assert_eq!(data[1].contractor, "ABC CORP");
+    }
+
+    #[test]
+    fn each_completion_fallback_gives_a_different_delay() {
+        let rows = vec![
+            row("2022", "1000000", "900000", "2022-01-01", "2022-01-11"),
+            row("2022", "1000000", "900000", "2022-02-01", "2022-03-03"),
+            row("2022", "1000000", "900000", "2022-03-01", "2022-05-10"),
+            row("2022", "1000000", "900000", "2022-06-01", ""),
+        ];
+        let load_with = |strategy| {
+            let opts = LoadOptions {
+                completion_imputation: strategy,
+                ..LoadOptions::default()
+            };
+            load_rows(&rows, &opts)
+        };
+
+        let (data, _) = load_with(CompletionImputation::StartDate);
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[3].completion_delay_days, 0.0);
+        assert!(data[3].completion_date_imputed);
+
+        // Durations of 10, 30 and 70 days: the median is 30.
+        let (data, report) = load_with(CompletionImputation::TypeMedianDuration);
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[3].completion_delay_days, 30.0);
+        assert_eq!(
+            data[3].completion_date,
+            NaiveDate::from_ymd_opt(2022, 7, 1).unwrap()
+        );
+        assert_eq!(report.median_duration_imputed, 1);
+
+        let (data, report) = load_with(CompletionImputation::Drop);
+        assert_eq!(data.len(), 3);
+        assert_eq!(report.dropped_no_completion, 1);
+    }
+
+    #[test]
+    fn type_median_completion_faces_the_range_and_duration_checks() {
+        let rows = vec![
+            row("2022", "1000000", "900000", "2022-01-01", "2022-01-11"),
+            row("2022", "1000000", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "1000000", "900000", "2022-03-01", "2022-05-10"),
+            row("2022", "1000000", "900000", "2022-06-01", ""),
+        ];
+        let median_opts = LoadOptions {
+            completion_imputation: CompletionImputation::TypeMedianDuration,
+            ..LoadOptions::default()
+        };
+
+        // The imputed 30-day delay is over the cap, so it is flagged like
+        // a reported one would be.
+        let opts = LoadOptions {
+            max_duration_days: Some(20.0),
+            exclude_long_duration: true,
+            ..median_opts.clone()
+        };
+        let (data, report) = load_rows(&rows, &opts);
+        assert_eq!(data.len(), 4);
+        assert_eq!(report.long_duration_rows, 3);
+        assert!(data[3].delay_excluded);
+
+        // Checked against its start date (0 days) the row would fail a
+        // 10-day minimum; checked against the imputed 30 days it passes.
+        let range = |min, max| ValueRange {
+            min: Some(min),
+            max: Some(max),
+        };
+        let bounded = |delay| LoadOptions {
+            plausibility: PlausibilityRanges {
+                completion_delay_days: delay,
+                ..PlausibilityRanges::default()
+            },
+            ..median_opts.clone()
+        };
+        let (data, report) = load_rows(&rows, &bounded(range(10.0, 100.0)));
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[3].completion_delay_days, 30.0);
+        assert_eq!(report.dropped_implausible_delay, 0);
+
+        // Delays measured against a plan stay small while the durations
+        // behind the median (70, 70 and 10 days) do not, so the imputed
+        // 70-day delay is past a 20-day maximum and the row is dropped.
+        let csv = format!(
+            "{},PlannedCompletionDate\n{},2022-03-10\n{},2022-03-10\n{},\n{},\n",
+            HEADER,
+            row("2022", "1000000", "900000", "2022-01-01", "2022-03-12"),
+            row("2022", "1000000", "900000", "2022-01-01", "2022-03-12"),
+            row("2022", "1000000", "900000", "2022-01-01", "2022-01-11"),
+            row("2022", "1000000", "900000", "2022-06-01", "")
+        );
+        let (data, report) = load_csv(&csv, &bounded(range(0.0, 20.0)));
+        assert_eq!(data.len(), 3);
+        assert!(data.iter().all(|r| !r.completion_date_imputed));
+        assert_eq!(report.dropped_implausible_delay, 1);
+        assert_eq!(report.median_duration_imputed, 0);
+        assert_eq!(report.parse_errors, 1);
+    }
+
+    #[test]
+    fn mostly_imputed_coordinates_trip_the_threshold() {
+        let located = good_row();
+        let unlocated = good_row().replace("18.2,120.6,18.19,120.59", ",,,");
+        let rows = vec![located, unlocated.clone(), unlocated.clone(), unlocated];
+        let (data, report) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(data.len(), 4);
+        assert_eq!(report.imputed_coords, 3);
+
+        match check_imputed_coords(&report, 50.0) {
+            Err(PipelineError::Validation(message)) => assert_eq!(
+                message,
+                "75.00% of retained rows have imputed coordinates, above --max-imputed-pct 50"
+            ),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert!(check_imputed_coords(&report, 75.0).is_ok());
+    }
+
+    #[test]
+    fn capital_coordinates_count_as_imputed() {
+        let at_capital = good_row().replace("18.2,120.6,", ",,");
+        let (data, report) = load_rows(&[good_row(), at_capital], &LoadOptions::default());
+        assert_eq!(data[1].coord_source, Some(CoordSource::ProvincialCapital));
+        assert!(data[1].coords_imputed() && data[1].is_imputed());
+        assert!(!data[0].is_imputed());
+        assert_eq!(report.imputed_coords, 1);
+        assert!(check_imputed_coords(&report, 40.0).is_err());
+    }
+
+    #[test]
+    fn column_map_reads_a_renamed_csv() {
+        let renamed = "island,reg,prov,work,year,budget,cost,end,firm,start,\
+                       lat,lon,cap_lat,cap_lon\n\
+                       Luzon,Region I,Ilocos Norte,Flood Control,2022,1000000,900000,\
+                       2022-01-31,ABC CORP,2022-01-01,18.2,120.6,18.19,120.59\n";
+        let column_map: BTreeMap<String, String> = [
+            ("MainIsland", "island"),
+            ("Region", "reg"),
+            ("Province", "prov"),
+            ("TypeOfWork", "work"),
+            ("FundingYear", "year"),
+            ("ApprovedBudgetForContract", "budget"),
+            ("ContractCost", "cost"),
+            ("ActualCompletionDate", "end"),
+            ("Contractor", "firm"),
+            ("StartDate", "start"),
+            ("ProjectLatitude", "lat"),
+            ("ProjectLongitude", "lon"),
+            ("ProvincialCapitalLatitude", "cap_lat"),
+            ("ProvincialCapitalLongitude", "cap_lon"),
+        ]
+        .iter()
+        .map(|(canonical, source)| (canonical.to_string(), source.to_string()))
+        .collect();
+        let opts = LoadOptions {
+            column_map,
+            ..LoadOptions::default()
+        };
+        let (mapped, report) = load_csv(renamed, &opts);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        let (dpwh, _) = load_rows(&[good_row()], &LoadOptions::default());
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(format!("{:?}", mapped[0]), format!("{:?}", dpwh[0]));
+
+        // Without the mapping nothing can be read.
+        let (unmapped, _) = load_csv(renamed, &LoadOptions::default());
+        assert!(unmapped.is_empty());
+    }
+
+    #[test]
+    fn project_over_the_duration_cap_is_flagged() {
+        // 2,000 days from start to completion.
+        let long = row("2021", "1000000", "900000", "2021-01-01", "2026-06-24");
+        let rows = vec![good_row(), long];
+        let opts = LoadOptions {
+            max_duration_days: Some(1000.0),
+            ..LoadOptions::default()
+        };
+        let (data, report) = load_rows(&rows, &opts);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].completion_delay_days, 2000.0);
+        assert_eq!(report.long_duration_rows, 1);
+        assert!(!data[1].delay_excluded);
+
+        let opts = LoadOptions {
+            exclude_long_duration: true,
+            ..opts
+        };
+        let (data, report) = load_rows(&rows, &opts);
+        assert_eq!(report.long_duration_rows, 1);
+        assert!(data[1].delay_excluded);
+        assert!(!data[0].delay_excluded);
+    }
+
+    #[test]
+    fn plausibility_ranges_drop_values_past_each_bound() {
+        let range = |min, max| ValueRange {
+            min: Some(min),
+            max: Some(max),
+        };
+        let opts = LoadOptions {
+            plausibility: PlausibilityRanges {
+                approved_budget: range(1000.0, 5_000_000.0),
+                contract_cost: range(1000.0, 5_000_000.0),
+                completion_delay_days: range(10.0, 1000.0),
+            },
+            ..LoadOptions::default()
+        };
+        let rows = vec![
+            // Exactly on every bound: kept.
+            row("2022", "1000", "1000", "2022-01-01", "2022-01-11"),
+            row("2022", "5000000", "5000000", "2022-01-01", "2024-09-27"),
+            row("2022", "999", "900", "2022-01-01", "2022-01-31"),
+            row("2022", "5000001", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "1000000", "999", "2022-01-01", "2022-01-31"),
+            row("2022", "1000000", "5000001", "2022-01-01", "2022-01-31"),
+            row("2022", "1000000", "900000", "2022-01-01", "2022-01-10"),
+            row("2022", "1000000", "900000", "2022-01-01", "2024-09-28"),
+        ];
+        let (data, report) = load_rows(&rows, &opts);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].completion_delay_days, 1000.0);
+        assert_eq!(report.dropped_implausible_budget, 2);
+        assert_eq!(report.dropped_implausible_cost, 2);
+        assert_eq!(report.dropped_implausible_delay, 2);
+        assert_eq!(report.parse_errors, 6);
+
+        // The default ranges are open.
+        let (data, _) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(data.len(), rows.len());
+    }
+
+    #[test]
+    fn rejected_numeric_strings_are_recorded_with_counts() {
+        let rows = [
+            row("2022", "1.2.3", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "N/A", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "N/A", "900000", "2022-01-01", "2022-01-31"),
+            row("2022", "\u{20b1}500", "900000", "2022-01-01", "2022-01-31"),
+            good_row(),
+        ];
+        let opts = LoadOptions {
+            rejected_formats_limit: Some(10),
+            ..LoadOptions::default()
+        };
+        let (records, report) = load_rows(&rows, &opts);
+        assert_eq!(records.len(), 1);
+        let rejected: Vec<(&str, &str, usize)> = report
+            .rejected_formats
+            .iter()
+            .map(|((column, raw), n)| (*column, raw.as_str(), *n))
+            .collect();
+        assert_eq!(
+            rejected,
+            [
+                ("ApprovedBudgetForContract", "1.2.3", 1),
+                ("ApprovedBudgetForContract", "N/A", 2),
+                ("ApprovedBudgetForContract", "\u{20b1}500", 1),
+            ]
+        );
+        assert_eq!(report.rejected_formats_untracked, 0);
+
+        // Past the limit, new strings are only counted.
+        let opts = LoadOptions {
+            rejected_formats_limit: Some(2),
+            ..LoadOptions::default()
+        };
+        let (_, report) = load_rows(&rows, &opts);
+        assert_eq!(report.rejected_formats.len(), 2);
+        assert_eq!(report.rejected_formats_untracked, 1);
+
+        // Without a limit nothing is collected.
+        let (_, report) = load_rows(&rows, &LoadOptions::default());
+        assert!(report.rejected_formats.is_empty());
+    }
+
+    #[test]
+    fn missing_island_is_inferred_from_the_region() {
+        let blank_island =
+            |region: &str| good_row().replacen("Luzon,Region I,", &format!(",{},", region), 1);
+        let rows = [
+            good_row(),
+            blank_island("Region I"),
+            blank_island("Region VII"),
+            blank_island("Mystery Region"),
+        ];
+        let opts = LoadOptions {
+            infer_main_island: true,
+            ..LoadOptions::default()
+        };
+        let (records, report) = load_rows(&rows, &opts);
+        let islands: Vec<(&str, &str)> = records
+            .iter()
+            .map(|r| (r.region.as_str(), r.main_island.as_str()))
+            .collect();
+        assert_eq!(
+            islands,
+            [
+                ("Region I", "Luzon"),
+                ("Region I", "Luzon"),
+                ("Region VII", "Visayas"),
+                ("Mystery Region", "Unknown"),
+            ]
+        );
+        assert_eq!(report.inferred_main_island_rows, 2);
+
+        // The inferred row groups with the one that named its island.
+        let report1 = crate::reports::generate_report1(&records, &Default::default());
+        let groups: Vec<(&str, &str, &str)> = report1
+            .iter()
+            .map(|r| {
+                (
+                    r.region.as_str(),
+                    r.main_island.as_str(),
+                    r.total_budget.as_str(),
+                )
+            })
+            .collect();
+        assert!(
+            groups.contains(&("Region I", "Luzon", "2000000.00")),
+            "{:?}",
+            groups
+        );
+        assert_eq!(groups.len(), 3);
+
+        // Without the option the blank island stays the placeholder.
+        let (records, _) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(records[1].main_island, "Unknown");
+    }
+
+    #[test]
+    fn whitespace_only_region_and_province_become_placeholders() {
+        let rows = [
+            good_row().replacen("Region I,Ilocos Norte,", "   ,Ilocos Norte,", 1),
+            good_row().replacen("Region I,Ilocos Norte,", "Region I,\t ,", 1),
+            good_row().replacen("Region I,Ilocos Norte,", "\"  \",\"  \",", 1),
+        ];
+        let (records, _) = load_rows(&rows, &LoadOptions::default());
+        let names: Vec<(&str, &str)> = records
+            .iter()
+            .map(|r| (r.region.as_str(), r.province.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("Unknown", "Ilocos Norte"),
+                ("Region I", "Unknown"),
+                ("Unknown", "Unknown"),
+            ]
+        );
+
+        let mut opts = LoadOptions::default();
+        opts.placeholders.region = "No Region".to_string();
+        let (records, _) = load_rows(&rows[..1], &opts);
+        assert_eq!(records[0].region, "No Region");
+    }
+
+    #[test]
+    fn not_yet_started_rows_follow_the_incomplete_status_policy() {
+        let csv = format!(
+            "{},Status\n{},Completed\n{},Not Yet Started\n",
+            HEADER,
+            good_row(),
+            row("2022", "500000", "400000", "2022-03-01", "2022-06-30")
+        );
+        let with = |policy: IncompleteStatus| {
+            let opts = LoadOptions {
+                incomplete_status: policy,
+                ..LoadOptions::default()
+            };
+            load_csv(&csv, &opts)
+        };
+
+        let (records, report) = with(IncompleteStatus::Keep);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| !r.delay_excluded));
+        assert_eq!(report.incomplete_status_rows, 1);
+
+        let (records, report) = with(IncompleteStatus::ExcludeDelay);
+        let excluded: Vec<bool> = records.iter().map(|r| r.delay_excluded).collect();
+        assert_eq!(excluded, [false, true]);
+        assert_eq!(report.incomplete_status_rows, 1);
+        assert_eq!(report.dropped_incomplete, 0);
+
+        let (records, report) = with(IncompleteStatus::Drop);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].approved_budget, 1_000_000.0);
+        assert_eq!(report.dropped_incomplete, 1);
+        assert_eq!(report.incomplete_status_rows, 0);
+        assert_eq!(report.parse_errors, 0);
+    }
+
+    #[test]
+    fn duplicated_contract_cost_column_follows_the_policy() {
+        let csv = format!("{},ContractCost\n{},950000\n", HEADER, good_row());
+        let file = temp_file(csv.as_bytes(), ".csv");
+        let with = |duplicate_headers: DuplicateHeaders| {
+            let opts = LoadOptions {
+                duplicate_headers,
+                ..LoadOptions::default()
+            };
+            load(file.path().to_str().unwrap(), &opts)
+        };
+
+        match with(DuplicateHeaders::Error) {
+            Err(PipelineError::Validation(message)) => assert_eq!(
+                message,
+                "Duplicate column headers: ContractCost (columns 7, 15). Remove the extra \
+                 columns or pass --duplicate-headers first|last."
+            ),
+            other => panic!("expected a validation error, got {:?}", other.map(|r| r.0)),
+        }
+
+        let (records, report) = with(DuplicateHeaders::FirstWins).unwrap();
+        assert_eq!(records[0].contract_cost, 900_000.0);
+        assert!(report
+            .warnings
+            .contains(&"Duplicate column ContractCost (columns 7, 15): reading column 7.".into()));
+
+        let (records, report) = with(DuplicateHeaders::LastWins).unwrap();
+        assert_eq!(records[0].contract_cost, 950_000.0);
+        assert!(report
+            .warnings
+            .contains(&"Duplicate column ContractCost (columns 7, 15): reading column 15.".into()));
+    }
+
+    #[test]
+    fn leading_comment_line_is_skipped_when_the_prefix_is_set() {
+        let csv = format!(
+            "# note: exported 2024-06-01 from the DPWH portal\n{}\n{}\n# trailing note\n",
+            HEADER,
+            good_row()
+        );
+        let opts = LoadOptions {
+            comment_char: Some(b'#'),
+            ..LoadOptions::default()
+        };
+        let (records, report) = load_csv(&csv, &opts);
+        assert_eq!(records.len(), 1);
+        assert_eq!(report.parse_errors, 0);
+        assert_eq!(report.total_rows, 1);
+
+        // Without the prefix the note is read as the header, and every
+        // line after it fails to parse.
+        let (records, report) = load_csv(&csv, &LoadOptions::default());
+        assert!(records.is_empty());
+        assert_eq!(report.parse_errors, 3);
+    }
+
+    #[test]
+    fn transposed_pair_is_swapped_but_a_modest_overrun_is_kept() {
+        let rows = [
+            // Budget and cost entered in each other's columns.
+            row("2022", "90000", "1000000", "2022-01-01", "2022-01-31"),
+            // A real 30% overrun.
+            row("2022", "1000000", "1300000", "2022-01-01", "2022-01-31"),
+        ];
+        let opts = LoadOptions {
+            swap_factor: Some(10.0),
+            ..LoadOptions::default()
+        };
+        let (records, report) = load_rows(&rows, &opts);
+        let pairs: Vec<(f64, f64)> = records
+            .iter()
+            .map(|r| (r.approved_budget, r.contract_cost))
+            .collect();
+        assert_eq!(pairs, [(1_000_000.0, 90_000.0), (1_000_000.0, 1_300_000.0)]);
+        assert_eq!(records[0].cost_savings, 910_000.0);
+        assert_eq!(report.swapped_budget_rows, 1);
+
+        // Off by default: both rows are read as written.
+        let (records, report) = load_rows(&rows, &LoadOptions::default());
+        assert_eq!(records[0].approved_budget, 90_000.0);
+        assert_eq!(report.swapped_budget_rows, 0);
+    }
 }
@@ -4,33 +4,75 @@
 // Paradigm(s): Procedural, Functional
 // ********************
 
-// - Option [1] loads and cleans the CSV, printing diagnostics.
+// - Option [1] loads and cleans the CSV, printing diagnostics and writing
+//   them to load_report.json.
 // - Option [2] generates three reports and a JSON summary.
+// - Option [3] lists the best and worst regions from the last Report 1.
+// - Option [4] shows Report 1's metrics for one region by name.
+// - Option [6] narrows the records options [2], [4] and [5] use by region,
+//   type of work, funding year and minimum budget.
+// - `serve` answers HTTP requests with the reports as JSON instead of
+//   showing the menu.
 // - After generating reports, the user can choose to go back to the
 //   selection menu or exit.
-mod loader;
-mod output;
-mod reports;
-mod types;
-mod util;
-
+use chrono::Local;
+use config::{IncompleteStatus, Options, DEFAULT_INPUT_PATH, FUNDING_YEARS};
 use once_cell::sync::Lazy;
-use std::io::{self, Write};
+use rust_report::error::PipelineError;
+use rust_report::{cli, config, loader, output, reports, server, types, util};
+use rust_report::{csv_metadata, run_analysis, write_reports, write_year_reports, AnalysisResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 use std::sync::Mutex;
+use std::time::Instant;
+use tabled::Tabled;
 use types::{
-    CleanRecord,
-    ContractorRankingRowPreview,
-    RegionSummaryRowPreview,
-    TypeTrendRowPreview,
+    CleanRecord, ContractorRankingRow, ContractorRankingRowPreview, RecordFilter,
+    RegionExtremesRow, RegionSummaryRow, RegionSummaryRowPreview, RejectedFormatRow, SummaryStats,
+    TypeTrendRow, TypeTrendRowPreview,
 };
-use util::format_number;
+use util::{format_number, parse_and_format};
 
 // Simple in-memory app state so we only load/clean the CSV once but can
-// generate reports multiple times in a single run.
-static APP_STATE: Lazy<Mutex<AppState>> = Lazy::new(|| Mutex::new(AppState { data: None }));
+// generate reports multiple times in a single run. The parsed command-line
+// options are kept alongside the data so every menu action sees them.
+static APP_STATE: Lazy<Mutex<AppState>> = Lazy::new(|| {
+    Mutex::new(AppState {
+        data: None,
+        load_report: None,
+        options: Options::default(),
+        last_report1: None,
+        filter: RecordFilter::default(),
+    })
+});
 
 struct AppState {
     data: Option<Vec<CleanRecord>>,
+    /// Statistics from the load that produced `data`.
+    load_report: Option<loader::LoadReport>,
+    options: Options,
+    /// Report 1 rows (sorted by EfficiencyScore) from the most recent
+    /// option [2] run, reused by the top/bottom regions view.
+    last_report1: Option<Vec<RegionSummaryRow>>,
+    /// Filters from option [6]; reports use only the records that pass.
+    filter: RecordFilter,
+}
+
+impl AppState {
+    /// The loaded records that pass `filter`, or `None` before a load.
+    fn filtered_data(&self) -> Option<Vec<CleanRecord>> {
+        let data = self.data.as_ref()?;
+        Some(if self.filter.is_empty() {
+            data.clone()
+        } else {
+            data.iter()
+                .filter(|r| self.filter.matches(r))
+                .cloned()
+                .collect()
+        })
+    }
 }
 
 /// Read a single line of input after printing the common "Enter choice:" prompt.
@@ -63,37 +105,279 @@ fn prompt_back_to_menu() -> bool {
     }
 }
 
-/// Handle option [1]: load and clean the CSV file.
+/// Handle option [1]: load and clean the dataset (the default CSV unless
+/// `--input` names another file).
 ///
 /// On success, we store the `Vec<ClanRecord>` in `APP_STATE` and print
-/// a short textual summary of what happened.
-fn handle_load() {
-    let path = "dpwh_flood_control_projects.csv";
-    match loader::load_and_clean(path) {
+/// a short textual summary of what happened. A file that cannot be loaded
+/// is reported here and leaves the state unchanged. A load that fails
+/// `--max-imputed-pct` is returned as an error instead, so the menu can
+/// carry on while `serve` exits with status 1.
+fn handle_load() -> Result<(), PipelineError> {
+    let options = APP_STATE.lock().unwrap().options.clone();
+    let path = options
+        .load
+        .input_path
+        .as_deref()
+        .unwrap_or(DEFAULT_INPUT_PATH);
+    let started = Instant::now();
+    match loader::load(path, &options.load) {
         Ok((data, load_report)) => {
+            let elapsed = started.elapsed().as_secs_f64();
             println!(
                 "Processing dataset... ({} rows loaded, {} filtered for 2021–2023)",
                 util::format_int(load_report.total_rows as i64),
                 util::format_int(load_report.filtered_rows as i64)
             );
+            if options.output.throughput {
+                let rate = util::rate_per_sec(load_report.total_rows, elapsed)
+                    .map_or("too fast to measure".to_string(), |r| {
+                        format!("{} rows/s", util::format_number_compact(r, 0))
+                    });
+                println!(
+                    "Parsed {} rows in {:.1}s ({})",
+                    util::format_int(load_report.total_rows as i64),
+                    elapsed,
+                    rate
+                );
+            }
             println!(
                 "Note: {} rows skipped due to parse/validation errors.",
                 util::format_int(load_report.parse_errors as i64)
             );
+            print_drop_breakdown(&load_report);
+            for warning in &load_report.warnings {
+                println!("Warning: {}", warning);
+            }
+            if load_report.units_row_skipped {
+                println!("Info: Skipped the units row below the header.");
+            }
+            if load_report.unit_stripped_rows > 0 {
+                println!(
+                    "Info: Stripped currency prefixes or trailing units from {} rows.",
+                    util::format_int(load_report.unit_stripped_rows as i64)
+                );
+            }
+            if load_report.swapped_budget_rows > 0 {
+                println!(
+                    "Info: Swapped back transposed budget and cost on {} rows.",
+                    util::format_int(load_report.swapped_budget_rows as i64)
+                );
+            }
+            println!("Info: Input SHA-256: {}", load_report.input_sha256);
+            if load_report.incomplete_status_rows > 0 {
+                println!(
+                    "Info: {} rows have a Status other than Completed{}.",
+                    util::format_int(load_report.incomplete_status_rows as i64),
+                    if options.load.incomplete_status == IncompleteStatus::ExcludeDelay {
+                        " (left out of delay averages)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            if load_report.inferred_main_island_rows > 0 {
+                println!(
+                    "Info: Filled a missing MainIsland from the region for {} rows.",
+                    util::format_int(load_report.inferred_main_island_rows as i64)
+                );
+            }
+            if load_report.header_rows_skipped > 0 {
+                println!(
+                    "Info: Skipped {} repeated header lines in the data.",
+                    util::format_int(load_report.header_rows_skipped as i64)
+                );
+            }
+            if load_report.planned_delay_rows > 0 {
+                println!(
+                    "Info: Delay measured against PlannedCompletionDate for {} rows \
+                     (others use StartDate to ActualCompletionDate duration).",
+                    util::format_int(load_report.planned_delay_rows as i64)
+                );
+            }
+            if load_report.median_duration_imputed > 0 {
+                println!(
+                    "Info: Imputed ActualCompletionDate as StartDate + the TypeOfWork's median \
+                     duration for {} rows.",
+                    util::format_int(load_report.median_duration_imputed as i64)
+                );
+            }
+            if let Some(max_days) = options.load.max_duration_days {
+                println!(
+                    "Info: {} rows took more than {} days from StartDate to \
+                     ActualCompletionDate{}.",
+                    util::format_int(load_report.long_duration_rows as i64),
+                    max_days,
+                    if options.load.exclude_long_duration {
+                        " (left out of delay averages)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+            if let Some(base) = options.load.deflator_base() {
+                println!(
+                    "Info: Budgets, costs and savings restated in constant {} pesos.",
+                    base
+                );
+            }
+            if options.load.rejected_formats_limit.is_some() {
+                write_rejected_formats(&options, &load_report);
+            }
+            let report_file = options.output.file_name("load_report.json");
+            report_written(output::write_json(&report_file, &load_report), |()| {
+                format!("Info: Load report written to {}.", report_file)
+            });
             if load_report.imputed_coords > 0 {
                 println!(
                     "Info: Imputed coordinates for {} rows.",
                     util::format_int(load_report.imputed_coords as i64)
                 );
             }
-            println!("");
+            println!();
+            if let Some(max_pct) = options.load.max_imputed_pct {
+                loader::check_imputed_coords(&load_report, max_pct)?;
+            }
             let mut state = APP_STATE.lock().unwrap();
             state.data = Some(data);
+            state.load_report = Some(load_report);
+        }
+        Err(PipelineError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!(
+                "Failed to load file: {} not found. Run from the dataset's folder or \
+                 pass --input PATH.\n",
+                path
+            );
         }
         Err(e) => {
             eprintln!("Failed to load file: {}\n", e);
         }
     }
+    Ok(())
+}
+
+/// Write the numeric cells the loader rejected (`--log-rejected`) to
+/// `rejected_formats.csv`, most frequent first.
+fn write_rejected_formats(options: &Options, load_report: &loader::LoadReport) {
+    let mut rows: Vec<RejectedFormatRow> = load_report
+        .rejected_formats
+        .iter()
+        .map(|((column, value), count)| RejectedFormatRow {
+            column: column.to_string(),
+            value: value.clone(),
+            count: *count,
+        })
+        .collect();
+    rows.sort_by_key(|r| std::cmp::Reverse(r.count));
+    let file = options.output.file_name("rejected_formats.csv");
+    let written = output::write_csv(&file, &rows, None, &options.output.csv_format, false);
+    report_written(written, |()| {
+        format!(
+            "Info: {} distinct rejected numeric values written to {}{}.",
+            util::format_int(rows.len() as i64),
+            file,
+            if load_report.rejected_formats_untracked > 0 {
+                format!(
+                    " ({} more occurrences beyond the limit not listed)",
+                    util::format_int(load_report.rejected_formats_untracked as i64)
+                )
+            } else {
+                String::new()
+            }
+        )
+    });
+}
+
+/// Print how many rows were retained and why the rest were dropped.
+///
+/// Only non-zero reasons are listed so a clean file stays quiet.
+fn print_drop_breakdown(load_report: &loader::LoadReport) {
+    let reasons = [
+        (
+            "outside 2021–2023 (not an error)",
+            load_report.dropped_out_of_range_year,
+        ),
+        ("unreadable row", load_report.dropped_malformed),
+        ("missing/invalid FundingYear", load_report.dropped_bad_year),
+        (
+            "missing/invalid ApprovedBudgetForContract",
+            load_report.dropped_bad_budget,
+        ),
+        ("missing/invalid ContractCost", load_report.dropped_bad_cost),
+        (
+            "ApprovedBudgetForContract outside --range",
+            load_report.dropped_implausible_budget,
+        ),
+        (
+            "ContractCost outside --range",
+            load_report.dropped_implausible_cost,
+        ),
+        (
+            "completion delay outside --range",
+            load_report.dropped_implausible_delay,
+        ),
+        ("missing/invalid StartDate", load_report.dropped_bad_date),
+        (
+            "missing ActualCompletionDate (--completion-fallback drop)",
+            load_report.dropped_no_completion,
+        ),
+        (
+            "Status not completed (--incomplete-status drop)",
+            load_report.dropped_incomplete,
+        ),
+    ];
+    print!(
+        "Retained {} of {} rows.",
+        util::format_int(load_report.filtered_rows as i64),
+        util::format_int(load_report.total_rows as i64)
+    );
+    if reasons.iter().all(|(_, c)| *c == 0) {
+        println!();
+        return;
+    }
+    println!(" Dropped by reason:");
+    for (reason, count) in reasons.iter().filter(|(_, c)| *c > 0) {
+        println!("  - {}: {}", reason, util::format_int(*count as i64));
+    }
+}
+
+/// Run `serve` mode: load the dataset once, compute the reports and
+/// summary, and answer HTTP requests with them as JSON until killed.
+fn handle_serve(port: u16) {
+    if let Err(e) = handle_load() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    let (data, load_report, options) = {
+        let state = APP_STATE.lock().unwrap();
+        (
+            state.data.clone(),
+            state.load_report.clone(),
+            state.options.clone(),
+        )
+    };
+    let bodies = data.map(|data| {
+        let AnalysisResult {
+            r1_out,
+            r2,
+            r3,
+            summary,
+            ..
+        } = run_analysis(&data, load_report, &options);
+        let json = |value: serde_json::Result<String>| {
+            value.unwrap_or_else(|e| server::error_body(&e.to_string()))
+        };
+        BTreeMap::from([
+            ("/report1", json(serde_json::to_string(&r1_out))),
+            ("/report2", json(serde_json::to_string(&r2))),
+            ("/report3", json(serde_json::to_string(&r3))),
+            ("/summary", json(serde_json::to_string(&summary))),
+        ])
+    });
+    if let Err(e) = server::serve(port, bodies.as_ref()) {
+        eprintln!("Server error: {}", e);
+        std::process::exit(1);
+    }
 }
 
 /// Handle option [2]: generate all reports and the JSON summary.
@@ -102,29 +386,630 @@ fn handle_load() {
 /// - writes three CSV files,
 /// - writes a JSOn summary
 /// - and prints Markdown previews of each report to the console.
-fn handle_generate_reports() {
-    let data = {
+///
+/// A `--expect` row count that does not hold is returned as an error
+/// once everything is written, so the caller decides whether to exit.
+fn handle_generate_reports() -> Result<(), PipelineError> {
+    let (data, load_report, mut options, filter) = {
         let state = APP_STATE.lock().unwrap();
-        state.data.clone()
+        (
+            state.filtered_data(),
+            state.load_report.clone(),
+            state.options.clone(),
+            state.filter.clone(),
+        )
     };
     let Some(data) = data else {
         println!("Error: No data loaded. Please load the CSV file first (option 1).\n");
-        return;
+        return Ok(());
     };
+    if !filter.is_empty() {
+        println!(
+            "Filter: {} ({} records)",
+            filter.describe(),
+            util::format_int(data.len() as i64)
+        );
+    }
+    if options.output.timestamp_outputs {
+        // One stamp per run so all of this run's files share it.
+        let stamp = Local::now().format("%Y%m%dT%H%M%S").to_string();
+        options.output = options.output.with_timestamp(&stamp);
+    }
 
     println!("Generating reports...");
+    let result = run_analysis(&data, load_report, &options);
+    // Debug builds only: a cell the previews cannot re-parse is a bug in a
+    // generator's formatting, not in the data.
+    #[cfg(debug_assertions)]
+    {
+        let bad_cells = reports::check_numeric_cells(&result.r1, &result.r2, &result.r3);
+        debug_assert!(
+            bad_cells.is_empty(),
+            "Unparseable report cells: {bad_cells:?}"
+        );
+    }
+    let violations = reports::check_percentage_bounds(&result.r1, &result.r2, &result.r3);
+    if !violations.is_empty() {
+        let label = if options.report.strict_bounds {
+            "Error"
+        } else {
+            "Warning"
+        };
+        for v in &violations {
+            eprintln!("{}: {}", label, v);
+        }
+        if options.report.strict_bounds {
+            eprintln!("No files written (--strict-bounds).\n");
+            return Ok(());
+        }
+    }
+    let [file1, file2, file3, summary_file] =
+        write_reports(&result, &options).map(|(file, written)| {
+            if let Err(e) = written {
+                eprintln!("Write error: {}", e);
+            }
+            file
+        });
+    let AnalysisResult {
+        r1,
+        r1_out,
+        r2,
+        r3,
+        summary,
+        load_report,
+    } = result;
     println!("Outputs saved to individual files...\n");
+    // Rendered preview tables, kept for the optional previews.md export.
+    let mut previews: Vec<(String, String)> = Vec::new();
 
-    let r1 = reports::generate_report1(&data);
-    let file1 = "report1_regional_summary.csv";
-    if let Err(e) = output::write_csv(file1, &r1) {
-        eprintln!("Write error: {}", e);
-    }
     println!("Report 1: Regional Flood Mitigation Efficiency Summary\n");
     println!("Regional Flood Mitigation Efficiency Summary");
     println!("(Filtered: 2021–2023 Projects)\n");
-    let r1_preview: Vec<RegionSummaryRowPreview> = r1
-        .iter()
+    if options.output.explain {
+        println!("{}", reports::explain_report1(&options.report));
+    }
+    let r1_preview = r1_preview_rows(&r1_out);
+    let r1_table = output::render_preview(
+        &r1_preview,
+        2,
+        &reports::significant_report1_rows(&r1_out),
+        &options.output,
+    );
+    println!("{}\n", r1_table);
+    previews.push((
+        "Report 1: Regional Flood Mitigation Efficiency Summary".to_string(),
+        r1_table,
+    ));
+    println!("(Full table exported to {})\n", file1);
+
+    println!("Report 2: Top Contractors Performance Ranking\n");
+    println!("Top Contractors Performance Ranking");
+    println!(
+        "(Top {} by TotalCost, >={} Projects)\n",
+        options.report.top_contractors, options.report.min_contractor_projects
+    );
+    if options.output.explain {
+        println!("{}", reports::explain_report2(&options.report));
+    }
+    let r2_preview = r2_preview_rows(&r2);
+    let r2_table = output::render_preview(
+        &r2_preview,
+        2,
+        &reports::significant_report2_rows(&r2),
+        &options.output,
+    );
+    println!("{}\n", r2_table);
+    previews.push((
+        "Report 2: Top Contractors Performance Ranking".to_string(),
+        r2_table,
+    ));
+    println!("(Full table exported to {})\n", file2);
+    if options.output.stats {
+        let scoped = reports::apply_placeholder_groups(
+            &data,
+            &options.load.placeholders,
+            &options.report,
+            2,
+        );
+        match reports::reliability_stats(&scoped, &options.report) {
+            Some(stats) => println!(
+                "ReliabilityIndex across all {} qualifying contractors (before the top-{} cut, \
+                 cap and floor): min {}, median {}, max {}, {} negative\n",
+                stats.qualifying,
+                options.report.top_contractors,
+                format_number(stats.min, 2),
+                format_number(stats.median, 2),
+                format_number(stats.max, 2),
+                stats.negative
+            ),
+            None => println!("ReliabilityIndex: no qualifying contractors.\n"),
+        }
+    }
+
+    println!("Report 3: Annual Project Type Cost Overrun Trends");
+    println!("Annual Project Type Cost Overrun Trends");
+    println!("(Grouped by FundingYear and TypeOfWork)\n");
+    if options.output.explain {
+        println!("{}", reports::explain_report3(&options.report));
+    }
+    let r3_preview = r3_preview_rows(&r3);
+    let r3_table = output::render_preview(
+        &r3_preview,
+        3,
+        &reports::significant_report3_rows(&r3),
+        &options.output,
+    );
+    println!("{}\n", r3_table);
+    previews.push((
+        "Report 3: Annual Project Type Cost Overrun Trends".to_string(),
+        r3_table,
+    ));
+    println!("(Full table exported to {})\n", file3);
+
+    println!("Summary Stats ({}):", summary_file);
+    println!(
+        "{{\"global_avg_delay_days\": \"{}\", \"total_savings\": {}}}\n",
+        summary.global_avg_delay_days,
+        // A malformed or overflowing value is shown as-is, not as 0.
+        parse_and_format(&summary.total_savings)
+    );
+    if let Some(matches) = summary.exact_budget_matches {
+        println!(
+            "Projects with ContractCost equal to the approved budget: {} of {}\n",
+            util::format_int(matches as i64),
+            util::format_int(summary.total_projects as i64)
+        );
+    }
+    if options.output.compact_summary {
+        // Printed on its own line so the JSON-shaped line above stays
+        // unchanged for anything that scrapes it.
+        match util::parse_f64_safe(Some(&summary.total_savings)).filter(|v| v.is_finite()) {
+            Some(total) => println!(
+                "Total savings: ₱{} (₱{})\n",
+                util::format_number_compact(total, 2),
+                format_number(total, 2)
+            ),
+            None => println!("Total savings: {}\n", summary.total_savings),
+        }
+    }
+    if options.output.prometheus {
+        let metrics_file = options.output.file_name("metrics.prom");
+        let load_report = load_report.unwrap_or_default();
+        report_written(
+            output::write_prometheus(&metrics_file, &summary, &load_report),
+            |()| format!("Prometheus metrics written to {}\n", metrics_file),
+        );
+    }
+    if options.output.fixed_width {
+        let written = [
+            write_fixed_width(&options, "report1_regional_summary.txt", &r1_out),
+            write_fixed_width(&options, "report2_contractor_ranking.txt", &r2),
+            write_fixed_width(&options, "report3_annual_trends.txt", &r3),
+        ];
+        let files: Vec<String> = written.into_iter().flatten().collect();
+        if !files.is_empty() {
+            println!("Fixed-width tables saved to {}\n", files.join(", "));
+        }
+    }
+    if options.output.json_reports {
+        let written = [
+            write_json_array(&options, "report1_regional_summary.json", &r1_out),
+            write_json_array(&options, "report2_contractor_ranking.json", &r2),
+            write_json_array(&options, "report3_annual_trends.json", &r3),
+        ];
+        let files: Vec<String> = written.into_iter().flatten().collect();
+        if !files.is_empty() {
+            println!("JSON reports saved to {}\n", files.join(", "));
+        }
+    }
+    if options.output.previews_markdown {
+        let md_file = options.output.file_name("previews.md");
+        report_written(
+            output::write_previews_markdown(&md_file, &previews, &summary),
+            |()| format!("Previews saved to {}\n", md_file),
+        );
+    }
+    if options.output.markdown_report {
+        let md_file = options.output.file_name("report.md");
+        let sections = [
+            (
+                format!(
+                    "Report 1: Regional Flood Mitigation Efficiency Summary ({} rows)",
+                    r1_out.len()
+                ),
+                output::render_markdown_table(
+                    &r1_preview,
+                    options.output.preview_rows.unwrap_or(2),
+                ),
+            ),
+            (
+                format!(
+                    "Report 2: Top Contractors Performance Ranking ({} rows)",
+                    r2.len()
+                ),
+                output::render_markdown_table(
+                    &r2_preview,
+                    options.output.preview_rows.unwrap_or(2),
+                ),
+            ),
+            (
+                format!(
+                    "Report 3: Annual Project Type Cost Overrun Trends ({} rows)",
+                    r3.len()
+                ),
+                output::render_markdown_table(
+                    &r3_preview,
+                    options.output.preview_rows.unwrap_or(3),
+                ),
+            ),
+        ];
+        report_written(
+            output::write_markdown_report(&md_file, &summary, &sections),
+            |()| format!("Markdown report saved to {}\n", md_file),
+        );
+    }
+    if let Some(max_distance) = options.output.merge_candidates_distance {
+        let candidates = reports::generate_contractor_merge_candidates(&data, max_distance);
+        let file = options
+            .output
+            .file_name("report_contractor_merge_candidates.csv");
+        let params = [("max_distance", max_distance.to_string())];
+        write_and_report(&options, &file, &candidates, &params, || {
+            format!(
+                "Contractor merge candidates: {} names in {} clusters (exported to {})\n",
+                candidates.len(),
+                candidates.last().map(|c| c.cluster).unwrap_or(0),
+                file
+            )
+        });
+    }
+    if let Some(multiplier) = options.output.cost_outlier_fence {
+        let outliers = reports::generate_cost_outliers(
+            &data,
+            multiplier,
+            options.output.cost_outliers_by_type,
+        );
+        let file = options.output.file_name("report_cost_outliers.csv");
+        let params = [
+            ("fence_multiplier", multiplier.to_string()),
+            (
+                "by_type_of_work",
+                options.output.cost_outliers_by_type.to_string(),
+            ),
+        ];
+        write_and_report(&options, &file, &outliers, &params, || {
+            format!(
+                "Cost outliers: {} records outside the IQR fences (exported to {})\n",
+                outliers.len(),
+                file
+            )
+        });
+    }
+    if options.output.worsening_contractors {
+        let rows = reports::generate_worsening_contractors(&data);
+        let file = options.output.file_name("report_worsening_contractors.csv");
+        write_and_report(&options, &file, &rows, &[], || {
+            format!(
+                "Worsening contractors: {} with a higher average delay every year \
+                 (exported to {})\n",
+                rows.len(),
+                file
+            )
+        });
+    }
+    if options.output.single_region_contractors {
+        let max_regions = options.output.max_contractor_regions;
+        let rows = reports::single_region_contractors(&data, &options.report, max_regions);
+        let file = options.output.file_name("single_region_contractors.csv");
+        let params = [("max_regions", max_regions.to_string())];
+        write_and_report(&options, &file, &rows, &params, || {
+            format!(
+                "Contractors active in at most {} region(s): {} (exported to {})\n",
+                max_regions,
+                rows.len(),
+                file
+            )
+        });
+    }
+    if let Some((contractor, factor)) = &options.output.what_if {
+        print_what_if(contractor, *factor, &data, &summary, &r2, &options);
+    }
+    if options.output.per_year {
+        write_per_year(&data, &options);
+    }
+    if let Some(other_path) = &options.output.side_by_side_path {
+        write_side_by_side(other_path, &r1_out, &options);
+    }
+    if let Some(prev_path) = &options.output.compare_path {
+        print_rank_changes(prev_path, &r1, &options);
+    }
+    if let Some(prev_dir) = &options.output.changes_since {
+        let delimiter = options.output.csv_format.delimiter;
+        let current = [
+            (
+                "report1_regional_summary",
+                output::csv_records(&r1_out, delimiter),
+            ),
+            (
+                "report2_contractor_ranking",
+                output::csv_records(&r2, delimiter),
+            ),
+            ("report3_annual_trends", output::csv_records(&r3, delimiter)),
+        ];
+        let keys: [&[&str]; 3] = [
+            &["Region", "MainIsland"],
+            &["Contractor"],
+            &["FundingYear", "TypeOfWork"],
+        ];
+        for ((base, records), keys) in current.into_iter().zip(keys) {
+            match records {
+                Ok(records) => write_changes(prev_dir, base, &records, keys, &options),
+                Err(e) => eprintln!("Write error: {}", e),
+            }
+        }
+    }
+    if options.output.export_clean {
+        let rows = reports::clean_record_rows(&data, options.output.flag_exact_budget);
+        let file = options.output.file_name("clean_records.csv");
+        write_and_report(&options, &file, &rows, &[], || {
+            format!("Clean records: {} rows exported to {}\n", rows.len(), file)
+        });
+    }
+    if options.output.geojson {
+        let file = options.output.file_name("projects.geojson");
+        report_written(
+            output::write_geojson(&file, &data, options.output.geojson_exclude_imputed),
+            |count| {
+                format!(
+                    "GeoJSON with {} project points written to {}\n",
+                    count, file
+                )
+            },
+        );
+    }
+    if options.output.by_region {
+        let dir = options.output.file_name("by_region");
+        let regions =
+            reports::region_drill_downs(&data, &options.report, options.output.flag_exact_budget);
+        report_written(output::write_region_json(&dir, &regions), |files| {
+            format!(
+                "Per-region JSON: {} files written to {}/\n",
+                files.len(),
+                dir
+            )
+        });
+    }
+    if let Some(db_path) = &options.output.sqlite_path {
+        report_written(
+            output::write_sqlite(db_path, &data, &r1_out, &r2, &r3),
+            |()| format!("SQLite database written to {}\n", db_path),
+        );
+    }
+    let mismatches = reports::check_row_counts(
+        &options.output.expected_rows,
+        [r1.len(), r2.len(), r3.len()],
+    );
+    APP_STATE.lock().unwrap().last_report1 = Some(r1);
+    if !mismatches.is_empty() {
+        return Err(PipelineError::Validation(mismatches.join("; ")));
+    }
+    Ok(())
+}
+
+/// Handle option [3]: show the top and bottom N regions side by side.
+///
+/// Uses the Report 1 rows cached by the last option [2] run, which are
+/// already sorted by EfficiencyScore descending. The bottom column lists
+/// the worst region first.
+fn handle_region_extremes() {
+    let (r1, n, layout) = {
+        let state = APP_STATE.lock().unwrap();
+        (
+            state.last_report1.clone(),
+            state.options.output.extremes_count,
+            state.options.output.preview_layout,
+        )
+    };
+    let Some(r1) = r1 else {
+        println!("Error: No reports generated yet. Please generate reports first (option 2).\n");
+        return;
+    };
+    let (top, bottom) = util::head_tail(&r1, n);
+    let rows: Vec<RegionExtremesRow> = (0..top.len().max(bottom.len()))
+        .map(|i| {
+            let best = top.get(i);
+            let worst = bottom.iter().rev().nth(i);
+            RegionExtremesRow {
+                position: i + 1,
+                top_region: best.map(|r| r.region.clone()).unwrap_or_default(),
+                top_score: best
+                    .map(|r| parse_and_format(&r.efficiency_score))
+                    .unwrap_or_default(),
+                bottom_region: worst.map(|r| r.region.clone()).unwrap_or_default(),
+                bottom_score: worst
+                    .map(|r| parse_and_format(&r.efficiency_score))
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    println!("Top and Bottom {} Regions by EfficiencyScore\n", n);
+    output::preview_table_rows(&rows, rows.len(), &layout);
+}
+
+/// Handle option [4]: prompt for a region name and show its Report 1
+/// metrics, computed from the loaded data for that region alone.
+fn handle_region_lookup() {
+    let (data, options) = {
+        let state = APP_STATE.lock().unwrap();
+        (state.filtered_data(), state.options.clone())
+    };
+    let Some(data) = data else {
+        println!("Error: No data loaded. Please load the CSV file first (option 1).\n");
+        return;
+    };
+    print!("Region name: ");
+    let _ = io::stdout().flush();
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf).ok();
+    let name = buf.trim();
+    match reports::region_summary(&data, name, &options.report) {
+        Some(row) => {
+            let preview = vec![RegionSummaryRowPreview {
+                region: row.region,
+                main_island: row.main_island,
+                total_budget: parse_and_format(&row.total_budget),
+                median_savings: parse_and_format(&row.median_savings),
+                avg_delay: parse_and_format(&row.avg_delay),
+                high_delay_pct: parse_and_format(&row.high_delay_pct),
+                efficiency_score: row.efficiency_score,
+                budget_share_pct: parse_and_format(&row.budget_share_pct),
+                iqr: parse_and_format(&row.iqr),
+                percentile_rank: parse_and_format(&row.percentile_rank),
+            }];
+            println!();
+            output::preview_table_rows(&preview, 1, &options.output.preview_layout);
+            println!(
+                "(EfficiencyScore is only defined relative to other regions; see Report 1.)\n"
+            );
+        }
+        None => println!("No records found for region \"{}\".\n", name),
+    }
+}
+
+/// Handle option [6]: prompt for each filter field (blank for no filter
+/// on it), store the combined filter for options [2], [4] and [5], and
+/// show how many loaded records pass it. Leaving every field blank clears
+/// the filter. Offers to generate the reports straight away.
+fn handle_filter_builder() {
+    let Some(total) = APP_STATE.lock().unwrap().data.as_ref().map(Vec::len) else {
+        println!("Error: No data loaded. Please load the CSV file first (option 1).\n");
+        return;
+    };
+    let prompt = |label: &str| {
+        print!("{} (blank = any): ", label);
+        let _ = io::stdout().flush();
+        let mut buf = String::new();
+        io::stdin().read_line(&mut buf).ok();
+        let value = buf.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+    let region = prompt("Region");
+    let type_of_work = prompt("TypeOfWork");
+    let funding_year = match prompt("FundingYear") {
+        None => None,
+        Some(v) => match util::parse_year_safe(Some(&v)) {
+            Some(year) => Some(year),
+            None => {
+                println!("Invalid FundingYear: {}. Filter not changed.\n", v);
+                return;
+            }
+        },
+    };
+    let min_budget = match prompt("Minimum ApprovedBudgetForContract") {
+        None => None,
+        Some(v) => match util::parse_f64_safe(Some(&v)) {
+            Some(min) => Some(min),
+            None => {
+                println!("Invalid budget: {}. Filter not changed.\n", v);
+                return;
+            }
+        },
+    };
+    let filter = RecordFilter {
+        region,
+        type_of_work,
+        funding_year,
+        min_budget,
+    };
+    let matching = {
+        let mut state = APP_STATE.lock().unwrap();
+        state.filter = filter.clone();
+        state.filtered_data().map_or(0, |d| d.len())
+    };
+    println!(
+        "\nFilter: {}\n{} of {} loaded records match.\n",
+        filter.describe(),
+        util::format_int(matching as i64),
+        util::format_int(total as i64)
+    );
+    print!("Generate reports with this filter now? (Y/N): ");
+    let _ = io::stdout().flush();
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf).ok();
+    if buf.trim().eq_ignore_ascii_case("Y") {
+        println!();
+        if let Err(e) = handle_generate_reports() {
+            eprintln!("Error: {}\n", e);
+        }
+    } else {
+        println!();
+    }
+}
+
+/// Handle option [5]: pick a report and page through all of its rows,
+/// `--page-size` at a time, with next/previous/quit prompts. The reports
+/// are generated from the loaded data; no files are written.
+fn handle_page_report() {
+    let (data, options) = {
+        let state = APP_STATE.lock().unwrap();
+        (state.filtered_data(), state.options.clone())
+    };
+    let Some(data) = data else {
+        println!("Error: No data loaded. Please load the CSV file first (option 1).\n");
+        return;
+    };
+    println!("Which report? [1] Regional Summary, [2] Contractor Ranking, [3] Annual Trends");
+    let choice = read_choice();
+    let AnalysisResult { r1_out, r2, r3, .. } = run_analysis(&data, None, &options);
+    match choice.as_str() {
+        "1" => page_through("Report 1", &r1_preview_rows(&r1_out), &options),
+        "2" => page_through("Report 2", &r2_preview_rows(&r2), &options),
+        "3" => page_through("Report 3", &r3_preview_rows(&r3), &options),
+        _ => println!("Invalid choice. Please enter 1, 2, or 3.\n"),
+    }
+}
+
+/// Show `rows` one page at a time until the user quits (or input ends).
+fn page_through<T: Tabled + Clone>(title: &str, rows: &[T], options: &Options) {
+    let size = options.output.page_size;
+    let pages = util::page_count(rows.len(), size);
+    if pages == 0 {
+        println!("{} has no rows.\n", title);
+        return;
+    }
+    let mut current = 0;
+    loop {
+        let shown = util::page(rows, size, current);
+        println!(
+            "\n{}, page {} of {} (rows {}-{} of {})\n",
+            title,
+            current + 1,
+            pages,
+            current * size + 1,
+            current * size + shown.len(),
+            rows.len()
+        );
+        output::preview_table_rows(shown, size, &options.output.preview_layout);
+        println!("[N] Next page, [P] Previous page, [Q] Back to menu");
+        match read_choice().to_uppercase().as_str() {
+            "N" if current + 1 < pages => current += 1,
+            "N" => println!("Already on the last page."),
+            "P" if current > 0 => current -= 1,
+            "P" => println!("Already on the first page."),
+            // An empty read at end of input also leaves, so a piped run
+            // cannot loop here forever.
+            "Q" | "" => {
+                println!();
+                return;
+            }
+            _ => println!("Invalid choice. Please enter N, P, or Q."),
+        }
+    }
+}
+
+/// Report 1 rows with comma-formatted numbers for console tables.
+fn r1_preview_rows(rows: &[RegionSummaryRow]) -> Vec<RegionSummaryRowPreview> {
+    rows.iter()
         .map(|row| RegionSummaryRowPreview {
             region: row.region.clone(),
             main_island: row.main_island.clone(),
@@ -133,21 +1018,16 @@ fn handle_generate_reports() {
             avg_delay: parse_and_format(&row.avg_delay),
             high_delay_pct: parse_and_format(&row.high_delay_pct),
             efficiency_score: parse_and_format(&row.efficiency_score),
+            budget_share_pct: parse_and_format(&row.budget_share_pct),
+            iqr: parse_and_format(&row.iqr),
+            percentile_rank: parse_and_format(&row.percentile_rank),
         })
-        .collect();
-    output::preview_table_rows(&r1_preview, 2);
-    println!("(Full table exported to {})\n", file1);
+        .collect()
+}
 
-    let r2 = reports::generate_report2(&data);
-    let file2 = "report2_contractor_ranking.csv";
-    if let Err(e) = output::write_csv(file2, &r2) {
-        eprintln!("Write error: {}", e);
-    }
-    println!("Report 2: Top Contractors Performance Ranking\n");
-    println!("Top Contractors Performance Ranking");
-    println!("(Top 15 by TotalCost, >=5 Projects)\n");
-    let r2_preview: Vec<ContractorRankingRowPreview> = r2
-        .iter()
+/// Report 2 rows with comma-formatted numbers for console tables.
+fn r2_preview_rows(rows: &[ContractorRankingRow]) -> Vec<ContractorRankingRowPreview> {
+    rows.iter()
         .map(|row| ContractorRankingRowPreview {
             rank: row.rank,
             contractor: row.contractor.clone(),
@@ -157,21 +1037,17 @@ fn handle_generate_reports() {
             total_savings: parse_and_format(&row.total_savings),
             reliability_index: parse_and_format(&row.reliability_index),
             risk_flag: row.risk_flag.clone(),
+            savings_per_delay_day: parse_and_format(&row.savings_per_delay_day),
+            cost_share_pct: parse_and_format(&row.cost_share_pct),
+            primary_province: row.primary_province.clone(),
+            budget_utilization_pct: parse_and_format(&row.budget_utilization_pct),
         })
-        .collect();
-    output::preview_table_rows(&r2_preview, 2);
-    println!("(Full table exported to {})\n", file2);
+        .collect()
+}
 
-    let r3 = reports::generate_report3(&data);
-    let file3 = "report3_annual_trends.csv";
-    if let Err(e) = output::write_csv(file3, &r3) {
-        eprintln!("Write error: {}", e);
-    }
-    println!("Report 3: Annual Project Type Cost Overrun Trends");
-    println!("Annual Project Type Cost Overrun Trends");
-    println!("(Grouped by FundingYear and TypeOfWork)\n");
-    let r3_preview: Vec<TypeTrendRowPreview> = r3
-        .iter()
+/// Report 3 rows with comma-formatted numbers for console tables.
+fn r3_preview_rows(rows: &[TypeTrendRow]) -> Vec<TypeTrendRowPreview> {
+    rows.iter()
         .map(|row| TypeTrendRowPreview {
             funding_year: row.funding_year,
             type_of_work: row.type_of_work.clone(),
@@ -180,60 +1056,328 @@ fn handle_generate_reports() {
             avg_savings: parse_and_format(&row.avg_savings),
             overrun_rate: parse_and_format(&row.overrun_rate),
             yoy_change: parse_and_format(&row.yoy_change),
+            project_count_yoy: parse_and_format(&row.project_count_yoy),
         })
-        .collect();
-    output::preview_table_rows(&r3_preview, 3);
-    println!("(Full table exported to {})\n", file3);
+        .collect()
+}
 
-    let mut summary = reports::generate_summary(&data, &r2);
-    // Fill in report-level counts to match the JS summary.json shape.
-    summary.report1_regions = r1.len();
-    summary.report2_contractors = r2.len();
-    summary.report3_entries = r3.len();
-    if let Err(e) = output::write_json("summary.json", &summary) {
-        eprintln!("Write error: {}", e);
+/// Print `message` with what a successful write returned, or the write
+/// error if it failed.
+fn report_written<T>(written: Result<T, PipelineError>, message: impl FnOnce(T) -> String) {
+    match written {
+        Ok(value) => println!("{}", message(value)),
+        Err(e) => eprintln!("Write error: {}", e),
+    }
+}
+
+/// Write `rows` to the CSV export `file` with the run's CSV format,
+/// `--append-csv` and a `--csv-metadata` line carrying `params`, then
+/// print `message` or the write error.
+fn write_and_report<T: Serialize>(
+    options: &Options,
+    file: &str,
+    rows: &[T],
+    params: &[(&str, String)],
+    message: impl FnOnce() -> String,
+) {
+    let meta = csv_metadata(options, rows.len(), params);
+    let written = output::write_csv(
+        file,
+        rows,
+        meta.as_deref(),
+        &options.output.csv_format,
+        options.output.append_csv,
+    );
+    report_written(written, |()| message());
+}
+
+/// Write `rows` as a fixed-width table to the output file for `base`,
+/// returning its name, or `None` (after reporting the error) on failure.
+fn write_fixed_width<T: Tabled>(options: &Options, base: &str, rows: &[T]) -> Option<String> {
+    let file = options.output.file_name(base);
+    match output::write_fixed_width(&file, rows) {
+        Ok(()) => Some(file),
+        Err(e) => {
+            eprintln!("Write error: {}", e);
+            None
+        }
     }
-    println!("Summary Stats (summary.json):");
+}
+
+/// Write `rows` as a JSON array to the output file for `base`, returning
+/// its name, or `None` (after reporting the error) on failure.
+fn write_json_array<T: Serialize>(options: &Options, base: &str, rows: &[T]) -> Option<String> {
+    let file = options.output.file_name(base);
+    match output::write_json_array(&file, rows) {
+        Ok(()) => Some(file),
+        Err(e) => {
+            eprintln!("Write error: {}", e);
+            None
+        }
+    }
+}
+
+/// Recompute the summary and Report 2 with `contractor`'s contract costs
+/// multiplied by `factor`, and print total savings and the contractor's
+/// Report 2 figures before and after.
+fn print_what_if(
+    contractor: &str,
+    factor: f64,
+    data: &[CleanRecord],
+    summary: &SummaryStats,
+    r2: &[ContractorRankingRow],
+    options: &Options,
+) {
+    let (adjusted, count) = reports::adjust_contractor_costs(data, contractor, factor);
+    if count == 0 {
+        println!("What-if: no records for contractor {}.\n", contractor);
+        return;
+    }
+    let after = run_analysis(&adjusted, None, options);
+    let total = |s: &SummaryStats| util::parse_f64_safe(Some(&s.total_savings)).unwrap_or(0.0);
+    let (before_total, after_total) = (total(summary), total(&after.summary));
     println!(
-        "{{\"global_avg_delay_days\": \"{}\", \"total_savings\": {}}}\n",
-        summary.global_avg_delay_days,
-        format_number(summary
-            .total_savings
-            .replace(",", "")
-            .parse::<f64>()
-            .unwrap_or(0.0),
-            2
-        )
+        "What-if: {}'s contract costs x{} ({} projects)",
+        contractor, factor, count
+    );
+    println!(
+        "  Total savings: ₱{} -> ₱{} (change ₱{})",
+        format_number(before_total, 2),
+        format_number(after_total, 2),
+        format_number(after_total - before_total, 2)
     );
+    let target = util::canonical_name(contractor);
+    let find = |rows: &[ContractorRankingRow]| {
+        rows.iter()
+            .find(|r| util::canonical_name(&r.contractor) == target)
+            .map(|r| {
+                format!(
+                    "rank {}, TotalCost {}, TotalSavings {}, ReliabilityIndex {}",
+                    r.rank,
+                    parse_and_format(&r.total_cost),
+                    parse_and_format(&r.total_savings),
+                    parse_and_format(&r.reliability_index)
+                )
+            })
+            .unwrap_or_else(|| "not in Report 2".to_string())
+    };
+    println!("  Report 2 before: {}", find(r2));
+    println!("  Report 2 after:  {}\n", find(&after.r2));
 }
 
-/// Helper: parse a numeric string and format with commas and two decimals
-fn parse_and_format(s: &str) -> String {
-    match s.replace(",", "").parse::<f64>() {
-        Ok(v) => format_number(v, 2),
-        Err(_) => s.to_string(),
+/// Write Reports 1 and 2 for each of `FUNDING_YEARS` on its own (see
+/// `write_year_reports`), reporting each year's files.
+fn write_per_year(data: &[CleanRecord], options: &Options) {
+    for year in FUNDING_YEARS {
+        report_written(
+            write_year_reports(data, year, options),
+            |(file1, file2, records)| {
+                format!(
+                    "FundingYear {}: Reports 1 and 2 from {} records written to {} and {}",
+                    year,
+                    util::format_int(records as i64),
+                    file1,
+                    file2
+                )
+            },
+        );
     }
+    println!();
+}
+
+/// Load the dataset at `other_path`, build its Report 1 with the same
+/// options, and write it next to the current Report 1 (`current`) in
+/// `report1_by_source.csv`. Each source is labelled with its file name,
+/// or its full path when both files share a name.
+fn write_side_by_side(other_path: &str, current: &[RegionSummaryRow], options: &Options) {
+    let other_data = match loader::load(other_path, &options.load) {
+        Ok((data, _)) => data,
+        Err(e) => {
+            eprintln!("Failed to load side-by-side file {}: {}\n", other_path, e);
+            return;
+        }
+    };
+    let other = run_analysis(&other_data, None, options).r1_out;
+    let current_path = options
+        .load
+        .input_path
+        .as_deref()
+        .unwrap_or(DEFAULT_INPUT_PATH);
+    let file_name = |p: &str| {
+        Path::new(p)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| p.to_string())
+    };
+    let (mut label_a, mut label_b) = (file_name(current_path), file_name(other_path));
+    if label_a == label_b {
+        label_a = current_path.to_string();
+        label_b = other_path.to_string();
+    }
+    let rows = reports::combine_report1_sources(&[
+        (label_a.clone(), current.to_vec()),
+        (label_b.clone(), other),
+    ]);
+    let file = options.output.file_name("report1_by_source.csv");
+    let mut params = vec![("sources", format!("{};{}", label_a, label_b))];
+    params.extend(options.report.report1_params());
+    write_and_report(options, &file, &rows, &params, || {
+        format!(
+            "Report 1 for {} and {}: {} rows exported to {}\n",
+            label_a,
+            label_b,
+            rows.len(),
+            file
+        )
+    });
+}
+
+/// Load the previous-period dataset at `prev_path`, build its Report 1,
+/// and print/export how each region's efficiency rank moved.
+fn print_rank_changes(prev_path: &str, current: &[RegionSummaryRow], options: &Options) {
+    let prev_data = match loader::load(prev_path, &options.load) {
+        Ok((data, _)) => data,
+        Err(e) => {
+            eprintln!("Failed to load comparison file {}: {}\n", prev_path, e);
+            return;
+        }
+    };
+    let previous = reports::generate_report1(&prev_data, &options.report);
+    let changes = reports::compare_report1_ranks(&previous, current);
+    let file = options.output.file_name("report1_rank_changes.csv");
+    let mut params = vec![("compare", prev_path.to_string())];
+    params.extend(options.report.report1_params());
+    let meta = csv_metadata(options, changes.len(), &params);
+    if let Err(e) = output::write_csv(
+        &file,
+        &changes,
+        meta.as_deref(),
+        &options.output.csv_format,
+        options.output.append_csv,
+    ) {
+        eprintln!("Write error: {}", e);
+    }
+    println!("Report 1 Rank Changes (vs {})\n", prev_path);
+    output::preview_table_rows(&changes, changes.len(), &options.output.preview_layout);
+    println!("(Full table exported to {})\n", file);
+}
+
+/// Compare one report's `current` records with `<prev_dir>/<base>.csv`
+/// from a previous run and write the rows that differ to
+/// `<base>_changes.csv` (see `reports::report_changes`).
+fn write_changes(
+    prev_dir: &str,
+    base: &str,
+    current: &(csv::StringRecord, Vec<csv::StringRecord>),
+    keys: &[&str],
+    options: &Options,
+) {
+    let prev_path = Path::new(prev_dir)
+        .join(format!("{}.csv", base))
+        .to_string_lossy()
+        .into_owned();
+    let (prev_header, prev_rows) =
+        match output::read_csv_records(&prev_path, options.output.csv_format.delimiter) {
+            Ok(previous) => previous,
+            Err(e) => {
+                eprintln!("Failed to read previous report {}: {}\n", prev_path, e);
+                return;
+            }
+        };
+    let (header, rows) =
+        reports::report_changes((&prev_header, &prev_rows), (&current.0, &current.1), keys);
+    let file = options.output.file_name(&format!("{}_changes.csv", base));
+    let meta = csv_metadata(options, rows.len(), &[("changes_since", prev_path.clone())]);
+    let written = output::write_csv_records(
+        &file,
+        &header,
+        &rows,
+        meta.as_deref(),
+        &options.output.csv_format,
+    );
+    report_written(written, |()| {
+        format!(
+            "{}: {} rows new, changed or removed since {} (written to {})\n",
+            base,
+            rows.len(),
+            prev_path,
+            file
+        )
+    });
 }
 
 fn main() {
+    match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) if options.print_version => {
+            println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Ok(options) if options.print_config => {
+            match options.effective_config_json() {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize options: {}", e),
+            }
+            return;
+        }
+        Ok(options) if options.serve_port.is_some() => {
+            let port = options.serve_port.unwrap_or(8080);
+            APP_STATE.lock().unwrap().options = options;
+            handle_serve(port);
+            return;
+        }
+        Ok(options) => APP_STATE.lock().unwrap().options = options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    }
     loop {
         println!("Select Language Implementation:");
         println!("[1] Load the file");
-        println!("[2] Generate Reports\n");
+        println!("[2] Generate Reports");
+        println!("[3] Show Top/Bottom Regions");
+        println!("[4] Show One Region's Summary");
+        println!("[5] Page Through a Report");
+        println!("[6] Filter Records\n");
         match read_choice().as_str() {
             "1" => {
-                handle_load();
+                if let Err(e) = handle_load() {
+                    eprintln!("Error: {}\n", e);
+                }
             }
             "2" => {
-                println!("");
-                handle_generate_reports();
+                println!();
+                if let Err(e) = handle_generate_reports() {
+                    eprintln!("Error: {}\n", e);
+                    // Scripted runs (e.g. CI piping menu choices in) fail
+                    // here; an interactive session carries on.
+                    if !io::stdin().is_terminal() {
+                        std::process::exit(1);
+                    }
+                }
                 if !prompt_back_to_menu() {
                     println!(" Exiting DPWH Flood Control Data Pipeline...");
                     break;
                 }
             }
+            "3" => {
+                println!();
+                handle_region_extremes();
+            }
+            "4" => {
+                println!();
+                handle_region_lookup();
+            }
+            "5" => {
+                println!();
+                handle_page_report();
+            }
+            "6" => {
+                println!();
+                handle_filter_builder();
+            }
             _ => {
-                println!("Invalid choice. Please enter 1 or 2.\n");
+                println!("Invalid choice. Please enter 1, 2, 3, 4, 5, or 6.\n");
             }
         }
     }
@@ -4,16 +4,93 @@
 // - `serde` drives serialization of our structs.
 // - `csv` writes properly escaped CSV with headers.
 // - `tabled` renders Markdown-compatible preview tables
+// - `rusqlite` writes everything into a single SQLite database on request
+use crate::config::{CsvFormat, CsvQuote, OutputOptions, PreviewLayout};
+use crate::error::PipelineError;
+use crate::loader::LoadReport;
+use crate::types::{
+    CleanRecord, ContractorRankingRow, RegionDrillDown, RegionSummaryRow, SummaryStats,
+    TypeTrendRow,
+};
+use crate::util::{format_int, format_number, parse_f64_safe, safe_file_name, sample_indices};
+use csv::{QuoteStyle, ReaderBuilder, StringRecord, WriterBuilder};
+use rusqlite::{params, Connection};
 use serde::Serialize;
-use std::error::Error;
-use tabled::{settings::Style, Table, Tabled};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use tabled::settings::object::{Columns, Object, Rows};
+use tabled::settings::{Alignment, Format, Modify, Style, Width};
+use tabled::{Table, Tabled};
 
 /// Write a sequence of `rows` to a CSV file at `path`.
 ///
 /// The type `T` only has to implement `Serialize`; column headers come from
 /// the `serde(rename = ..)` attributes on the structsin `types.rs`.
-pub fn write_csv<T: Serialize>(path: &str, rows: &[T]) -> Result<(), Box<dyn Error>> {
-    let mut wtr = csv::Writer::from_path(path)?;
+///
+/// When `metadata` is given it is written as a single `# ...` comment line
+/// above the header (for provenance); `None` keeps the file strict CSV.
+/// `format` sets the delimiter and when fields are quoted.
+///
+/// With `append`, rows are added to the end of an existing non-empty file
+/// instead of replacing it. The file's header (the first line that is not
+/// a `#` comment) must match `T`'s columns; it is not written again, and
+/// neither is `metadata`. A missing or empty file is written as usual.
+pub fn write_csv<T: Serialize>(
+    path: &str,
+    rows: &[T],
+    metadata: Option<&str>,
+    format: &CsvFormat,
+    append: bool,
+) -> Result<(), PipelineError> {
+    let existing = if append {
+        existing_csv_header(path, format.delimiter)?
+    } else {
+        None
+    };
+    let mut file = match &existing {
+        Some(header) => {
+            // Nothing to add, and no row to learn our own header from.
+            let Some(first) = rows.first() else {
+                return Ok(());
+            };
+            let ours = csv_header(first, format.delimiter)?;
+            if *header != ours {
+                return Err(PipelineError::Validation(format!(
+                    "cannot append to {}: its columns ({}) differ from this report's ({})",
+                    path,
+                    header.iter().collect::<Vec<_>>().join(","),
+                    ours.iter().collect::<Vec<_>>().join(",")
+                )));
+            }
+            let mut file = OpenOptions::new().read(true).append(true).open(path)?;
+            // Start on a fresh line if the last row was left unterminated.
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                writeln!(file)?;
+            }
+            file
+        }
+        None => {
+            let mut file = File::create(path)?;
+            if let Some(meta) = metadata {
+                writeln!(file, "# {}", meta)?;
+            }
+            file
+        }
+    };
+    let quote_style = match format.quote {
+        CsvQuote::Necessary => QuoteStyle::Necessary,
+        CsvQuote::Always => QuoteStyle::Always,
+    };
+    let mut wtr = WriterBuilder::new()
+        .delimiter(format.delimiter)
+        .quote_style(quote_style)
+        .has_headers(existing.is_none())
+        .from_writer(&mut file);
     for r in rows {
         wtr.serialize(r)?;
     }
@@ -22,31 +99,1256 @@ pub fn write_csv<T: Serialize>(path: &str, rows: &[T]) -> Result<(), Box<dyn Err
     Ok(())
 }
 
+/// The header record of the CSV at `path`, skipping `#` comment lines, or
+/// `None` when the file does not exist or has no header.
+fn existing_csv_header(path: &str, delimiter: u8) -> Result<Option<StringRecord>, PipelineError> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .from_reader(file);
+    let header = rdr.headers()?.clone();
+    Ok(if header.is_empty() {
+        None
+    } else {
+        Some(header)
+    })
+}
+
+/// The header and rows of the CSV at `path`, skipping `#` comment lines.
+pub fn read_csv_records(
+    path: &str,
+    delimiter: u8,
+) -> Result<(StringRecord, Vec<StringRecord>), PipelineError> {
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .from_path(path)?;
+    let header = rdr.headers()?.clone();
+    let rows = rdr.records().collect::<Result<Vec<_>, _>>()?;
+    Ok((header, rows))
+}
+
+/// `rows` as the header and records `write_csv` would write for them, so
+/// they can be compared cell by cell with a CSV read back from disk.
+pub fn csv_records<T: Serialize>(
+    rows: &[T],
+    delimiter: u8,
+) -> Result<(StringRecord, Vec<StringRecord>), PipelineError> {
+    let mut wtr = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    for r in rows {
+        wtr.serialize(r)?;
+    }
+    let bytes = wtr.into_inner().map_err(|e| e.into_error())?;
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(bytes.as_slice());
+    let header = rdr.headers()?.clone();
+    let records = rdr.records().collect::<Result<Vec<_>, _>>()?;
+    Ok((header, records))
+}
+
+/// Write `header` and `rows` as a CSV at `path`, in `format`, with an
+/// optional `# metadata` line as in `write_csv`. For tables whose columns
+/// are only known at run time.
+pub fn write_csv_records(
+    path: &str,
+    header: &StringRecord,
+    rows: &[StringRecord],
+    metadata: Option<&str>,
+    format: &CsvFormat,
+) -> Result<(), PipelineError> {
+    let mut file = File::create(path)?;
+    if let Some(meta) = metadata {
+        writeln!(file, "# {}", meta)?;
+    }
+    let quote_style = match format.quote {
+        CsvQuote::Necessary => QuoteStyle::Necessary,
+        CsvQuote::Always => QuoteStyle::Always,
+    };
+    let mut wtr = WriterBuilder::new()
+        .delimiter(format.delimiter)
+        .quote_style(quote_style)
+        .from_writer(&mut file);
+    wtr.write_record(header)?;
+    for r in rows {
+        wtr.write_record(r)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write a CSV at `path` that has no rows: a `# note` line, then `T`'s
+/// columns as the header. Optional columns left out of the `Tabled`
+/// headers (e.g. RawEfficiency) are not included.
+pub fn write_empty_csv<T: Tabled>(
+    path: &str,
+    note: &str,
+    format: &CsvFormat,
+) -> Result<(), PipelineError> {
+    let header: StringRecord = T::headers().iter().map(|h| h.as_ref()).collect();
+    write_csv_records(path, &header, &[], Some(note), format)
+}
+
+/// The header `write_csv` would write for rows shaped like `row`.
+fn csv_header<T: Serialize>(row: &T, delimiter: u8) -> Result<StringRecord, PipelineError> {
+    let mut wtr = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    wtr.serialize(row)?;
+    let bytes = wtr.into_inner().map_err(|e| e.into_error())?;
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(bytes.as_slice());
+    Ok(rdr.headers()?.clone())
+}
+
 /// Serialize `value` as pretty-printed JSON and write it to `path`.
-pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<(), Box<dyn Error>> {
+pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<(), PipelineError> {
     let s = serde_json::to_string_pretty(value)?;
     std::fs::write(path, s)?;
     Ok(())
 }
 
+/// Write report `rows` to `path` as one pretty-printed JSON array of
+/// objects, keyed by the same serde renames as the CSV headers. Rows are
+/// serialized straight into the file rather than built up in memory; no
+/// rows gives `[]`.
+pub fn write_json_array<T: Serialize>(path: &str, rows: &[T]) -> Result<(), PipelineError> {
+    let mut out = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut out, rows)?;
+    out.write_all(b"\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write the summary and load statistics as Prometheus gauges in the text
+/// exposition format, for a node_exporter textfile collector or similar.
+///
+/// Summary fields stored as formatted strings are parsed back so every
+/// sample is a plain number (no thousands separators).
+pub fn write_prometheus(
+    path: &str,
+    summary: &SummaryStats,
+    load_report: &LoadReport,
+) -> Result<(), PipelineError> {
+    let number = |s: &str| parse_f64_safe(Some(s)).unwrap_or(0.0);
+    let mut metrics: Vec<(&str, &str, f64)> = vec![
+        (
+            "dpwh_total_projects",
+            "Records retained after cleaning.",
+            summary.total_projects as f64,
+        ),
+        (
+            "dpwh_total_contractors",
+            "Contractors listed in Report 2.",
+            summary.total_contractors as f64,
+        ),
+        (
+            "dpwh_total_provinces",
+            "Distinct provinces among retained records.",
+            summary.total_provinces as f64,
+        ),
+        (
+            "dpwh_total_savings",
+            "Sum of ApprovedBudgetForContract - ContractCost, in pesos.",
+            number(&summary.total_savings),
+        ),
+        (
+            "dpwh_avg_delay_days",
+            "Average completion delay over retained records, in days.",
+            number(&summary.global_avg_delay_days),
+        ),
+        (
+            "dpwh_contractor_cost_gini",
+            "Gini coefficient of total ContractCost per contractor.",
+            number(&summary.contractor_cost_gini),
+        ),
+        (
+            "dpwh_rows_total",
+            "Data rows read from the input file.",
+            load_report.total_rows as f64,
+        ),
+        (
+            "dpwh_parse_errors",
+            "Rows dropped by parse or validation errors.",
+            load_report.parse_errors as f64,
+        ),
+        (
+            "dpwh_imputed_coords",
+            "Retained rows with coordinates imputed from a province average.",
+            load_report.imputed_coords as f64,
+        ),
+    ];
+    if let Some(days) = summary.days_since_latest_completion {
+        metrics.push((
+            "dpwh_days_since_latest_completion",
+            "Days since the latest reported ActualCompletionDate.",
+            days as f64,
+        ));
+    }
+    let mut out = String::new();
+    for (name, help, value) in metrics {
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} gauge\n{} {}\n",
+            name, help, name, name, value
+        ));
+    }
+    out.push_str(
+        "# HELP dpwh_report_rows Rows in each generated report.\n\
+         # TYPE dpwh_report_rows gauge\n",
+    );
+    for (report, rows) in [
+        (1, summary.report1_regions),
+        (2, summary.report2_contractors),
+        (3, summary.report3_entries),
+    ] {
+        out.push_str(&format!(
+            "dpwh_report_rows{{report=\"{}\"}} {}\n",
+            report, rows
+        ));
+    }
+    std::fs::write(path, out)?;
+    Ok(())
+}
+
 /// Render up to `max_rows` as a Markdown table preview in the console.
 ///
 /// `tabled` inspects the `Tabledd` implementation (derived from struct
 /// fields) and uses the configured`Style::markdown()` to emit a header
 /// row, a divider, and aligned coluns.
-pub fn preview_table_rows<T>(rows: &[T], max_rows: usize)
+pub fn preview_table_rows<T>(rows: &[T], max_rows: usize, layout: &PreviewLayout)
+where
+    T: Tabled + Clone,
+{
+    println!("{}\n", render_table_rows(rows, max_rows, layout));
+}
+
+/// Render up to `max_rows` as a Markdown table string, or `(no rows)` when
+/// there is nothing to show. Shared by the console preview and the
+/// `previews.md` export so both always look the same.
+///
+/// A column counts as numeric when every non-empty cell shown parses as a
+/// number; `layout` may right-align those and truncate the others.
+pub fn render_table_rows<T>(rows: &[T], max_rows: usize, layout: &PreviewLayout) -> String
 where
     T: Tabled + Clone,
 {
     // Clone just the first `max_rows` 
-    let slice: Vec<T> = rows.iter().cloned().take(max_rows).collect();
+    let slice: Vec<T> = rows.iter().take(max_rows).cloned().collect();
     if slice.is_empty() {
-        println!("(no rows)\n");
-        return;
+        return "(no rows)".to_string();
+    }
+    let mut numeric = vec![true; T::LENGTH];
+    let mut seen = vec![false; T::LENGTH];
+    for row in &slice {
+        for (i, cell) in row.fields().iter().enumerate() {
+            if cell.trim().is_empty() {
+                continue;
+            }
+            seen[i] = true;
+            numeric[i] &= parse_f64_safe(Some(cell)).is_some();
+        }
+    }
+    let mut table = Table::new(slice);
+    table.with(Style::markdown());
+    // A quoted CSV field may contain line breaks (e.g. a contractor name
+    // split over two lines). Flatten them to spaces so every record stays
+    // on one table row instead of breaking the Markdown layout.
+    table.with(Modify::new(Rows::new(1..)).with(Format::content(flatten_line_breaks)));
+    for i in 0..T::LENGTH {
+        if numeric[i] && seen[i] {
+            if layout.right_align_numbers {
+                table.with(Modify::new(Columns::single(i)).with(Alignment::right()));
+            }
+        } else if let Some(width) = layout.max_text_width {
+            // Body cells only, so the header keeps its full name.
+            let cells = Columns::single(i).intersect(Rows::new(1..));
+            table.with(Modify::new(cells).with(Width::truncate(width).suffix("…")));
+        }
     }
     // Render the Markdon table. On Windows terminals, `tabled` may include
     // `\r` characters, which can mess up the divider line, so we strip them.
-    let table_str = Table::new(slice).with(Style::markdown()).to_string();
-    let normalized = table_str.replace('\r', "");
-    println!("{}\n", normalized);
+    let table_str = table.to_string();
+    table_str.replace('\r', "")
+}
+
+/// Write every row of `rows` to `path` as a fixed-width text table: a
+/// header line, then one line per row, with columns padded by spaces and
+/// no borders. Unlike the previews nothing is truncated; line breaks in a
+/// cell are flattened to spaces so each row stays on one line.
+pub fn write_fixed_width<T: Tabled>(path: &str, rows: &[T]) -> Result<(), PipelineError> {
+    let mut table = Table::new(rows);
+    table
+        .with(Style::blank())
+        .with(Modify::new(Rows::new(1..)).with(Format::content(flatten_line_breaks)));
+    let mut text = table.to_string().replace('\r', "");
+    text.push('\n');
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// Replace each `\r\n`, `\n` or `\r` in a preview cell with one space.
+fn flatten_line_breaks(cell: &str) -> String {
+    cell.replace("\r\n", " ").replace(['\n', '\r'], " ")
+}
+
+/// Like `render_table_rows`, but shows `n` rows picked at evenly spaced
+/// positions (always including the first and last) instead of the first
+/// `n`, so a sorted report's whole range is visible. Deterministic: the
+/// same rows are picked on every run.
+pub fn render_table_sampled<T>(rows: &[T], n: usize, layout: &PreviewLayout) -> String
+where
+    T: Tabled + Clone,
+{
+    let picked: Vec<T> = sample_indices(rows.len(), n)
+        .into_iter()
+        .map(|i| rows[i].clone())
+        .collect();
+    render_table_rows(&picked, picked.len(), layout)
+}
+
+/// Render a report preview with `--preview-rows` rows (or `default_rows`),
+/// taking either the first rows or, with `--preview-sample`, rows spread
+/// evenly from first to last. With `--preview-significant`, only the rows
+/// at the `significant` positions are shown, all of them unless
+/// `--preview-rows` is set.
+pub fn render_preview<T: Tabled + Clone>(
+    rows: &[T],
+    default_rows: usize,
+    significant: &[usize],
+    options: &OutputOptions,
+) -> String {
+    if options.preview_significant {
+        let picked: Vec<T> = significant.iter().map(|&i| rows[i].clone()).collect();
+        let n = options.preview_rows.unwrap_or(picked.len());
+        return render_table_rows(&picked, n, &options.preview_layout);
+    }
+    let n = options.preview_rows.unwrap_or(default_rows);
+    if options.preview_sampled {
+        render_table_sampled(rows, n, &options.preview_layout)
+    } else {
+        render_table_rows(rows, n, &options.preview_layout)
+    }
+}
+
+/// Write the records that have coordinates as a GeoJSON `FeatureCollection`
+/// of `Point`s at `path`, and return how many features were written.
+///
+/// Each feature carries the record's region, province, contractor, funding
+/// year, budget, savings and `coord_source`. With `exclude_imputed`, only
+/// records located by their own project coordinates are kept (points placed
+/// at the provincial capital or a province average are dropped).
+pub fn write_geojson(
+    path: &str,
+    records: &[CleanRecord],
+    exclude_imputed: bool,
+) -> Result<usize, PipelineError> {
+    let features: Vec<serde_json::Value> = records
+        .iter()
+        .filter(|r| !exclude_imputed || !r.coords_imputed())
+        .filter_map(|r| {
+            let (lat, lon) = (r.lat?, r.lon?);
+            Some(json!({
+                "type": "Feature",
+                // GeoJSON positions are [longitude, latitude].
+                "geometry": { "type": "Point", "coordinates": [lon, lat] },
+                "properties": {
+                    "region": r.region,
+                    "province": r.province,
+                    "contractor": r.contractor,
+                    "funding_year": r.funding_year,
+                    "approved_budget": r.approved_budget,
+                    "cost_savings": r.cost_savings,
+                    "coord_source": r.coord_source.map(|c| c.as_str()),
+                }
+            }))
+        })
+        .collect();
+    let count = features.len();
+    let collection = json!({ "type": "FeatureCollection", "features": features });
+    std::fs::write(path, serde_json::to_string(&collection)?)?;
+    Ok(count)
+}
+
+/// Write each region's drill-down to `<dir>/<region>.json` with
+/// `write_json`, creating `dir` if needed, and return the files written.
+///
+/// Region names go through `safe_file_name`; if two names sanitize to the
+/// same stem, the later ones get `_2`, `_3`, ... appended.
+pub fn write_region_json(
+    dir: &str,
+    regions: &[RegionDrillDown],
+) -> Result<Vec<String>, PipelineError> {
+    std::fs::create_dir_all(dir)?;
+    let mut used: HashSet<String> = HashSet::new();
+    let mut files = Vec::with_capacity(regions.len());
+    for region in regions {
+        let stem = safe_file_name(&region.region);
+        let mut name = stem.clone();
+        let mut n = 1;
+        while !used.insert(name.to_lowercase()) {
+            n += 1;
+            name = format!("{}_{}", stem, n);
+        }
+        let path = format!("{}/{}.json", dir, name);
+        write_json(&path, region)?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// Write the rendered preview tables plus the summary into one Markdown
+/// file at `path`.
+///
+/// `sections` are `(heading, rendered table)` pairs, typically produced by
+/// `render_table_rows`; the summary is embedded as a JSON code block.
+pub fn write_previews_markdown<S: Serialize>(
+    path: &str,
+    sections: &[(String, String)],
+    summary: &S,
+) -> Result<(), PipelineError> {
+    let mut doc = String::from("# DPWH Flood Control Report Previews\n\n");
+    for (heading, table) in sections {
+        doc.push_str(&format!("## {}\n\n{}\n\n", heading, table));
+    }
+    doc.push_str("## Summary\n\n```json\n");
+    doc.push_str(&serde_json::to_string_pretty(summary)?);
+    doc.push_str("\n```\n");
+    std::fs::write(path, doc)?;
+    Ok(())
+}
+
+/// Render the first `max_rows` of `rows` as a Markdown table for
+/// `report.md`. Unlike the previews, `|` inside a cell is escaped as `\|`
+/// so it cannot split the cell when the document is rendered.
+pub fn render_markdown_table<T>(rows: &[T], max_rows: usize) -> String
+where
+    T: Tabled + Clone,
+{
+    let slice: Vec<T> = rows.iter().take(max_rows).cloned().collect();
+    if slice.is_empty() {
+        return "(no rows)".to_string();
+    }
+    let mut table = Table::new(slice);
+    table.with(Style::markdown());
+    table.with(Modify::new(Rows::new(1..)).with(Format::content(|cell| {
+        flatten_line_breaks(cell).replace('|', "\\|")
+    })));
+    table.to_string().replace('\r', "")
+}
+
+/// Write `report.md`: a title, the summary as a bullet list, then each
+/// `(heading, table)` section (tables from `render_markdown_table`).
+pub fn write_markdown_report(
+    path: &str,
+    summary: &SummaryStats,
+    sections: &[(String, String)],
+) -> Result<(), PipelineError> {
+    // A value that does not parse is shown as-is rather than as ₱0.00.
+    let peso = |s: &str| match parse_f64_safe(Some(s)).filter(|v| v.is_finite()) {
+        Some(v) => format!("₱{}", format_number(v, 2)),
+        None => s.to_string(),
+    };
+    let mut doc = String::from("# DPWH Flood Control Projects Report\n\n## Summary\n\n");
+    doc.push_str(&format!(
+        "- Projects analyzed: {}\n\
+         - Provinces: {}\n\
+         - Contractors in Report 2: {}\n\
+         - Average completion delay: {} days\n\
+         - Total savings: {}\n\
+         - Contractor spending Gini: {}\n",
+        format_int(summary.total_projects as i64),
+        format_int(summary.total_provinces as i64),
+        format_int(summary.total_contractors as i64),
+        summary.global_avg_delay_days,
+        peso(&summary.total_savings),
+        summary.contractor_cost_gini,
+    ));
+    if let (Some(first), Some(last)) = (
+        &summary.earliest_completion_date,
+        &summary.latest_completion_date,
+    ) {
+        doc.push_str(&format!("- Completion dates: {} to {}\n", first, last));
+    }
+    doc.push_str("- Average savings per project by funding year:\n");
+    for y in &summary.year_weighted_avg_savings {
+        doc.push_str(&format!(
+            "  - {}: {} ({} projects)\n",
+            y.funding_year,
+            peso(&y.weighted_avg_savings),
+            format_int(y.total_projects as i64)
+        ));
+    }
+    doc.push_str("- Total savings by main island:\n");
+    for island in &summary.island_savings {
+        doc.push_str(&format!(
+            "  - {}: {}\n",
+            island.main_island.replace('|', "\\|"),
+            peso(&island.total_savings)
+        ));
+    }
+    for (heading, table) in sections {
+        doc.push_str(&format!("\n## {}\n\n{}\n", heading, table));
+    }
+    std::fs::write(path, doc)?;
+    Ok(())
+}
+
+/// Write the clean records and Reports 1–3 into a SQLite database at `path`.
+///
+/// Each report gets its own table with typed columns: the pre-formatted
+/// numeric strings in the report rows are parsed back into `REAL` values
+/// (a cell that fails to parse is stored as `NULL`). Any existing tables
+/// with the same names are replaced, all inserts run in one transaction,
+/// and the obvious lookup keys (region, contractor) are indexed.
+pub fn write_sqlite(
+    path: &str,
+    records: &[CleanRecord],
+    r1: &[RegionSummaryRow],
+    r2: &[ContractorRankingRow],
+    r3: &[TypeTrendRow],
+) -> Result<(), PipelineError> {
+    // Parse a formatted report cell back into a number for a REAL column.
+    fn num(s: &str) -> Option<f64> {
+        parse_f64_safe(Some(s))
+    }
+
+    let mut conn = Connection::open(path)?;
+    let tx = conn.transaction()?;
+    tx.execute_batch(
+        "DROP TABLE IF EXISTS clean_records;
+         DROP TABLE IF EXISTS report1_regional_summary;
+         DROP TABLE IF EXISTS report2_contractor_ranking;
+         DROP TABLE IF EXISTS report3_annual_trends;
+         CREATE TABLE clean_records (
+             funding_year INTEGER NOT NULL,
+             region TEXT NOT NULL,
+             main_island TEXT NOT NULL,
+             province TEXT NOT NULL,
+             type_of_work TEXT NOT NULL,
+             contractor TEXT NOT NULL,
+             approved_budget REAL NOT NULL,
+             contract_cost REAL NOT NULL,
+             cost_savings REAL NOT NULL,
+             completion_delay_days REAL NOT NULL,
+             delay_basis TEXT NOT NULL,
+             completion_date TEXT NOT NULL,
+             completion_date_imputed INTEGER NOT NULL,
+             lat REAL,
+             lon REAL,
+             coord_source TEXT
+         );
+         CREATE TABLE report1_regional_summary (
+             region TEXT NOT NULL,
+             main_island TEXT NOT NULL,
+             total_budget REAL,
+             median_savings REAL,
+             avg_delay REAL,
+             high_delay_pct REAL,
+             efficiency_score REAL,
+             budget_share_pct REAL,
+             iqr REAL,
+             percentile_rank REAL,
+             raw_efficiency REAL
+         );
+         CREATE TABLE report2_contractor_ranking (
+             rank INTEGER NOT NULL,
+             contractor TEXT NOT NULL,
+             total_cost REAL,
+             num_projects INTEGER NOT NULL,
+             avg_delay REAL,
+             total_savings REAL,
+             reliability_index REAL,
+             risk_flag TEXT NOT NULL,
+             savings_per_delay_day REAL,
+             cost_share_pct REAL,
+             primary_province TEXT,
+             budget_utilization_pct REAL
+         );
+         CREATE TABLE report3_annual_trends (
+             funding_year INTEGER NOT NULL,
+             type_of_work TEXT NOT NULL,
+             total_projects INTEGER NOT NULL,
+             avg_savings REAL,
+             overrun_rate REAL,
+             yoy_change REAL,
+             smoothed_avg_savings REAL,
+             project_count_yoy REAL,
+             count_collapse TEXT
+         );
+         CREATE INDEX idx_clean_records_region ON clean_records (region);
+         CREATE INDEX idx_clean_records_contractor ON clean_records (contractor);
+         CREATE INDEX idx_report1_region ON report1_regional_summary (region);
+         CREATE INDEX idx_report2_contractor ON report2_contractor_ranking (contractor);",
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO clean_records \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        )?;
+        for r in records {
+            stmt.execute(params![
+                r.funding_year,
+                r.region,
+                r.main_island,
+                r.province,
+                r.type_of_work,
+                r.contractor,
+                r.approved_budget,
+                r.contract_cost,
+                r.cost_savings,
+                r.completion_delay_days,
+                r.delay_basis.as_str(),
+                r.completion_date.format("%Y-%m-%d").to_string(),
+                r.completion_date_imputed,
+                r.lat,
+                r.lon,
+                r.coord_source.map(|c| c.as_str()),
+            ])?;
+        }
+        let mut stmt = tx.prepare(
+            "INSERT INTO report1_regional_summary \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )?;
+        for r in r1 {
+            stmt.execute(params![
+                r.region,
+                r.main_island,
+                num(&r.total_budget),
+                num(&r.median_savings),
+                num(&r.avg_delay),
+                num(&r.high_delay_pct),
+                num(&r.efficiency_score),
+                num(&r.budget_share_pct),
+                num(&r.iqr),
+                num(&r.percentile_rank),
+                r.raw_efficiency.as_deref().and_then(num),
+            ])?;
+        }
+        let mut stmt = tx.prepare(
+            "INSERT INTO report2_contractor_ranking \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        for r in r2 {
+            stmt.execute(params![
+                r.rank as i64,
+                r.contractor,
+                num(&r.total_cost),
+                r.num_projects as i64,
+                num(&r.avg_delay),
+                num(&r.total_savings),
+                num(&r.reliability_index),
+                r.risk_flag,
+                num(&r.savings_per_delay_day),
+                num(&r.cost_share_pct),
+                r.primary_province,
+                num(&r.budget_utilization_pct),
+            ])?;
+        }
+        let mut stmt = tx.prepare(
+            "INSERT INTO report3_annual_trends VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for r in r3 {
+            stmt.execute(params![
+                r.funding_year,
+                r.type_of_work,
+                r.total_projects as i64,
+                num(&r.avg_savings),
+                num(&r.overrun_rate),
+                num(&r.yoy_change),
+                r.smoothed_avg_savings.as_deref().and_then(num),
+                num(&r.project_count_yoy),
+                r.count_collapse,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReportConfig;
+    use crate::reports;
+    use crate::types::CoordSource;
+
+    /// A path named `name` in `dir`, as the `&str` the writers take.
+    fn path_in(dir: &tempfile::TempDir, name: &str) -> String {
+        dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn sqlite_database_answers_aggregate_queries() {
+        let data: Vec<CleanRecord> = (0..6)
+            .map(|i| CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0 + i as f64, 10.0))
+            .chain((0..2).map(|_| CleanRecord::sample("NCR", "XYZ INC", 500.0, 450.0, 0.0)))
+            .collect();
+        let cfg = ReportConfig::default();
+        let r1 = reports::generate_report1(&data, &cfg);
+        let r2 = reports::generate_report2(&data, &cfg);
+        let r3 = reports::generate_report3(&data, &cfg);
+        let dir = tempfile::tempdir().unwrap();
+        let db = path_in(&dir, "reports.db");
+        write_sqlite(&db, &data, &r1, &r2, &r3).unwrap();
+
+        let conn = Connection::open(&db).unwrap();
+        let total_cost: f64 = conn
+            .query_row("SELECT SUM(contract_cost) FROM clean_records", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(total_cost, 6.0 * 900.0 + 15.0 + 2.0 * 450.0);
+        let (projects, cost): (i64, f64) = conn
+            .query_row(
+                "SELECT num_projects, total_cost FROM report2_contractor_ranking \
+                 WHERE contractor = 'ABC CORP'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((projects, cost), (6, 5415.0));
+        let regions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM report1_regional_summary", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(regions, 2);
+    }
+
+    #[test]
+    fn previews_markdown_contains_all_three_tables() {
+        let data: Vec<CleanRecord> = (0..5)
+            .map(|_| CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0, 10.0))
+            .collect();
+        let cfg = ReportConfig::default();
+        let layout = PreviewLayout::default();
+        let sections = vec![
+            (
+                "Report 1".to_string(),
+                render_table_rows(&reports::generate_report1(&data, &cfg), 2, &layout),
+            ),
+            (
+                "Report 2".to_string(),
+                render_table_rows(&reports::generate_report2(&data, &cfg), 2, &layout),
+            ),
+            (
+                "Report 3".to_string(),
+                render_table_rows(&reports::generate_report3(&data, &cfg), 3, &layout),
+            ),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "previews.md");
+        write_previews_markdown(
+            &path,
+            &sections,
+            &serde_json::json!({ "total_projects": 5 }),
+        )
+        .unwrap();
+        let doc = std::fs::read_to_string(&path).unwrap();
+        for heading in ["## Report 1", "## Report 2", "## Report 3", "## Summary"] {
+            assert!(doc.contains(heading), "missing {}", heading);
+        }
+        for header in ["| Region ", "| Rank ", "| FundingYear "] {
+            assert!(doc.contains(header), "missing table header {}", header);
+        }
+    }
+
+    #[test]
+    fn long_contractor_name_is_truncated_to_preview_width() {
+        let name = "VERY LONG CONTRACTOR NAME CONSTRUCTION AND DEVELOPMENT CORPORATION";
+        let data: Vec<CleanRecord> = (0..5)
+            .map(|_| CleanRecord::sample("Region I", name, 1000.0, 900.0, 10.0))
+            .collect();
+        let r2 = reports::generate_report2(&data, &ReportConfig::default());
+
+        let full = render_table_rows(&r2, 5, &PreviewLayout::default());
+        assert!(full.contains(name));
+
+        let layout = PreviewLayout {
+            max_text_width: Some(12),
+            right_align_numbers: true,
+        };
+        let narrow = render_table_rows(&r2, 5, &layout);
+        assert!(!narrow.contains(name));
+        let cell = narrow
+            .lines()
+            .nth(2)
+            .unwrap()
+            .split('|')
+            .map(str::trim)
+            .find(|c| c.starts_with("VERY"))
+            .unwrap();
+        assert!(cell.ends_with('…'), "{}", cell);
+        assert_eq!(cell.chars().count(), 12);
+        // Headers keep their full names.
+        assert!(narrow.lines().next().unwrap().contains("Contractor"));
+    }
+
+    #[test]
+    fn sampled_preview_spans_the_whole_report() {
+        #[derive(Clone, Tabled)]
+        struct Row {
+            name: String,
+        }
+        let rows: Vec<Row> = (0..20)
+            .map(|i| Row {
+                name: format!("Region {:02}", i),
+            })
+            .collect();
+        let layout = PreviewLayout::default();
+        let table = render_table_sampled(&rows, 3, &layout);
+        assert!(table.contains("Region 00"));
+        assert!(table.contains("Region 09"));
+        assert!(table.contains("Region 19"));
+        assert!(!table.contains("Region 01"));
+        // Exactly the sampled rows, in report order.
+        let shown: Vec<&str> = table
+            .lines()
+            .filter_map(|line| line.find("Region ").map(|i| &line[i..i + 9]))
+            .collect();
+        assert_eq!(shown, ["Region 00", "Region 09", "Region 19"]);
+    }
+
+    #[test]
+    fn embedded_line_breaks_are_flattened_in_previews() {
+        let data = vec![CleanRecord::sample(
+            "Region\r\nI",
+            "ABC CORP",
+            1000.0,
+            900.0,
+            10.0,
+        )];
+        let r1 = reports::generate_report1(&data, &ReportConfig::default());
+        let table = render_table_rows(&r1, 5, &PreviewLayout::default());
+        // Header, divider and one row.
+        assert_eq!(table.lines().count(), 3, "{}", table);
+        assert!(table.lines().nth(2).unwrap().contains("Region I"));
+        assert!(!table.contains('\r'));
+    }
+
+    #[test]
+    fn pipe_delimited_always_quoted_output() {
+        let data = vec![CleanRecord::sample(
+            "Region I", "ABC CORP", 1000.0, 900.0, 10.0,
+        )];
+        let r1 = reports::generate_report1(&data, &ReportConfig::default());
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "report1.csv");
+        let format = CsvFormat {
+            delimiter: b'|',
+            quote: CsvQuote::Always,
+        };
+        write_csv(&path, &r1, None, &format, false).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut lines = text.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with(r#""Region"|"MainIsland"|"TotalBudget"|"#));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with(r#""Region I"|"Luzon"|"1000.00"|"#));
+
+        let default = path_in(&dir, "default.csv");
+        write_csv(&default, &r1, None, &CsvFormat::default(), false).unwrap();
+        let text = std::fs::read_to_string(&default).unwrap();
+        assert!(text.starts_with("Region,MainIsland,TotalBudget,"));
+        assert!(!text.contains('"'));
+    }
+
+    #[test]
+    fn geojson_is_a_feature_collection_without_imputed_points_on_request() {
+        let data = vec![
+            CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0, 10.0),
+            CleanRecord {
+                coord_source: Some(CoordSource::ProvincialCapital),
+                ..CleanRecord::sample("Region II", "XYZ INC", 500.0, 450.0, 0.0)
+            },
+            CleanRecord {
+                lat: None,
+                lon: None,
+                coord_source: None,
+                ..CleanRecord::sample("NCR", "XYZ INC", 500.0, 450.0, 0.0)
+            },
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "projects.geojson");
+        let read = |path: &str| -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+
+        assert_eq!(write_geojson(&path, &data, false).unwrap(), 2);
+        let all = read(&path);
+        assert_eq!(all["type"], "FeatureCollection");
+        let features = all["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+        let first = &features[0];
+        assert_eq!(first["type"], "Feature");
+        assert_eq!(first["geometry"]["type"], "Point");
+        assert_eq!(first["geometry"]["coordinates"], json!([121.0, 14.6]));
+        assert_eq!(first["properties"]["region"], "Region I");
+        assert_eq!(first["properties"]["contractor"], "ABC CORP");
+        assert_eq!(first["properties"]["approved_budget"], 1000.0);
+        assert_eq!(first["properties"]["cost_savings"], 100.0);
+
+        assert_eq!(write_geojson(&path, &data, true).unwrap(), 1);
+        let real = read(&path);
+        let features = real["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["properties"]["region"], "Region I");
+    }
+
+    #[test]
+    fn fixed_width_columns_line_up_across_rows() {
+        let data: Vec<CleanRecord> = ["A", "MEDIUM NAME INC", "A MUCH LONGER CONTRACTOR NAME CORP"]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, name)| {
+                (0..5).map(move |_| {
+                    CleanRecord::sample("Region I", name, 1e3 * 10f64.powi(i as i32), 900.0, 1.0)
+                })
+            })
+            .collect();
+        let r2 = reports::generate_report2(&data, &ReportConfig::default());
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "report2.txt");
+        write_fixed_width(&path, &r2).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        assert_eq!(lines.len(), 1 + r2.len());
+
+        // Where each header name starts; no header contains a space.
+        let header = &lines[0];
+        let starts: Vec<usize> = (0..header.len())
+            .filter(|&i| header[i] != ' ' && (i == 0 || header[i - 1] == ' '))
+            .collect();
+        assert_eq!(starts.len(), ContractorRankingRow::LENGTH);
+        for line in &lines {
+            assert_eq!(line.len(), header.len());
+            for &start in &starts {
+                assert_ne!(line[start], ' ', "{}", line.iter().collect::<String>());
+                assert!(start == 0 || line[start - 1] == ' ');
+            }
+        }
+    }
+
+    #[test]
+    fn append_adds_rows_without_a_second_header() {
+        let cfg = ReportConfig::default();
+        let day1 = reports::generate_report1(
+            &[CleanRecord::sample(
+                "Region I", "ABC CORP", 1000.0, 900.0, 10.0,
+            )],
+            &cfg,
+        );
+        let day2 = reports::generate_report1(
+            &[CleanRecord::sample("NCR", "XYZ INC", 500.0, 450.0, 0.0)],
+            &cfg,
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "report1.csv");
+        let format = CsvFormat::default();
+        write_csv(
+            &path,
+            &day1,
+            Some("generated 2024-06-01, rows=1"),
+            &format,
+            true,
+        )
+        .unwrap();
+        write_csv(
+            &path,
+            &day2,
+            Some("generated 2024-06-02, rows=1"),
+            &format,
+            true,
+        )
+        .unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", text);
+        assert_eq!(lines[0], "# generated 2024-06-01, rows=1");
+        assert!(lines[1].starts_with("Region,MainIsland,"));
+        assert!(lines[2].starts_with("Region I,"));
+        assert!(lines[3].starts_with("NCR,"));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("Region,")).count(), 1);
+
+        // A report with other columns is refused rather than mixed in.
+        let r3 = reports::generate_report3(
+            &[CleanRecord::sample("NCR", "XYZ INC", 500.0, 450.0, 0.0)],
+            &cfg,
+        );
+        assert!(matches!(
+            write_csv(&path, &r3, None, &format, true),
+            Err(PipelineError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn prometheus_exposition_lines_for_a_known_summary() {
+        let data = vec![
+            CleanRecord::sample("Region I", "ABC CORP", 2_000_000.0, 1_000_000.0, 10.0),
+            CleanRecord::sample("NCR", "XYZ INC", 1_000_000.0, 500_000.5, 30.0),
+        ];
+        let today = chrono::NaiveDate::from_ymd_opt(2022, 7, 10).unwrap();
+        let mut summary = reports::generate_summary(&data, &[], today, Default::default());
+        summary.report1_regions = 2;
+        let load_report = LoadReport {
+            total_rows: 5,
+            parse_errors: 3,
+            ..LoadReport::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "metrics.prom");
+        write_prometheus(&path, &summary, &load_report).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        for expected in [
+            "# TYPE dpwh_total_projects gauge",
+            "dpwh_total_projects 2",
+            "dpwh_total_savings 1499999.5",
+            "dpwh_avg_delay_days 20",
+            "dpwh_parse_errors 3",
+            "dpwh_days_since_latest_completion 10",
+            "dpwh_report_rows{report=\"1\"} 2",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "missing {:?} in\n{}",
+                expected,
+                text
+            );
+        }
+        for line in lines.iter().filter(|l| !l.starts_with('#')) {
+            let (name, value) = line.rsplit_once(' ').unwrap();
+            assert!(name.starts_with("dpwh_"), "{}", line);
+            assert!(
+                value.parse::<f64>().is_ok() && !value.contains(','),
+                "{}",
+                line
+            );
+        }
+        // Every sample follows its own HELP and TYPE lines.
+        for (i, line) in lines.iter().enumerate() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let name = rest.split(' ').next().unwrap();
+                assert!(lines[i - 1].starts_with(&format!("# HELP {} ", name)));
+                assert!(lines[i + 1].starts_with(name));
+            }
+        }
+    }
+
+    #[test]
+    fn json_array_round_trips_a_report() {
+        let data = vec![
+            CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0, 10.0),
+            CleanRecord::sample("NCR", "XYZ INC", 2000.0, 2100.0, 30.0),
+        ];
+        let rows = reports::generate_report1(&data, &ReportConfig::default());
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "report1.json");
+        write_json_array(&path, &rows).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("[\n  {"));
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(
+            serde_json::Value::Array(parsed.clone()),
+            serde_json::to_value(&rows).unwrap()
+        );
+        assert_eq!(parsed[0]["Region"], rows[0].region.as_str());
+
+        let empty = path_in(&dir, "empty.json");
+        write_json_array::<RegionSummaryRow>(&empty, &[]).unwrap();
+        assert_eq!(std::fs::read_to_string(&empty).unwrap().trim(), "[]");
+    }
+
+    #[test]
+    fn markdown_report_escapes_pipes_and_respects_the_row_limit() {
+        let cfg = ReportConfig::default();
+        let data = vec![
+            CleanRecord::sample("Region I", "ABC | XYZ JV", 1000.0, 900.0, 10.0),
+            CleanRecord::sample("NCR", "XYZ INC", 2000.0, 1500.0, 20.0),
+            CleanRecord::sample("Region VII", "LMN CORP", 3000.0, 2900.0, 30.0),
+        ];
+        let r1 = reports::generate_report1(&data, &cfg);
+        let r2 = reports::generate_report2(
+            &data,
+            &ReportConfig {
+                min_contractor_projects: 1,
+                ..cfg
+            },
+        );
+        let summary = reports::generate_summary(
+            &data,
+            &r2,
+            chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            Default::default(),
+        );
+        let sections = [
+            ("Report 1".to_string(), render_markdown_table(&r1, 2)),
+            ("Report 2".to_string(), render_markdown_table(&r2, 5)),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "report.md");
+        write_markdown_report(&path, &summary, &sections).unwrap();
+        let doc = std::fs::read_to_string(&path).unwrap();
+
+        assert!(doc.starts_with("# DPWH Flood Control Projects Report\n\n## Summary\n"));
+        assert!(doc.contains("- Total savings: ₱700.00\n"));
+        // The pipe in the name is escaped, so the row keeps its columns.
+        assert!(doc.contains(r"ABC \| XYZ JV"));
+        let table_rows = |heading: &str| {
+            doc.split(&format!("## {}\n\n", heading))
+                .nth(1)
+                .unwrap()
+                .lines()
+                .take_while(|l| l.starts_with('|'))
+                .count()
+        };
+        // Header, separator, then at most the requested number of rows.
+        assert_eq!(table_rows("Report 1"), 2 + 2);
+        assert_eq!(table_rows("Report 2"), 2 + 3);
+    }
+
+    #[test]
+    fn combined_report1_keeps_each_source_distinguishable() {
+        let cfg = ReportConfig::default();
+        let agency_a = reports::generate_report1(
+            &[
+                CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0, 10.0),
+                CleanRecord::sample("NCR", "XYZ INC", 500.0, 450.0, 0.0),
+            ],
+            &cfg,
+        );
+        let agency_b = reports::generate_report1(
+            &[CleanRecord::sample(
+                "Region I", "ABC CORP", 3000.0, 2000.0, 40.0,
+            )],
+            &cfg,
+        );
+        let rows = reports::combine_report1_sources(&[
+            ("agency_a".to_string(), agency_a),
+            ("agency_b".to_string(), agency_b),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_in(&dir, "report1_combined.csv");
+        write_csv(&path, &rows, None, &CsvFormat::default(), false).unwrap();
+
+        let mut reader = ReaderBuilder::new().from_path(&path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(&headers[0], "Source");
+        let budget = headers.iter().position(|h| h == "TotalBudget").unwrap();
+        let cells: Vec<(String, String, String)> = reader
+            .records()
+            .map(|r| {
+                let r = r.unwrap();
+                (r[0].to_string(), r[1].to_string(), r[budget].to_string())
+            })
+            .collect();
+        let cell = |source: &str, region: &str, total: &str| {
+            (source.to_string(), region.to_string(), total.to_string())
+        };
+        assert_eq!(
+            cells,
+            [
+                cell("agency_a", "NCR", "500.00"),
+                cell("agency_a", "Region I", "1000.00"),
+                cell("agency_b", "Region I", "3000.00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn significance_preview_shows_exactly_the_high_risk_contractors() {
+        let cfg = ReportConfig {
+            min_contractor_projects: 1,
+            ..ReportConfig::default()
+        };
+        let data = vec![
+            CleanRecord::sample("Region I", "PROMPT CORP", 1000.0, 800.0, 0.0),
+            CleanRecord::sample("Region I", "LATE BUILDERS", 1000.0, 1500.0, 900.0),
+            CleanRecord::sample("NCR", "STEADY INC", 1000.0, 900.0, 5.0),
+            CleanRecord::sample("NCR", "STALLED JV", 1000.0, 1200.0, 700.0),
+        ];
+        let rows = reports::generate_report2(&data, &cfg);
+        let high_risk: Vec<&str> = rows
+            .iter()
+            .filter(|r| r.risk_flag == "High Risk")
+            .map(|r| r.contractor.as_str())
+            .collect();
+        assert_eq!(high_risk.len(), 2, "{:?}", high_risk);
+
+        let options = OutputOptions {
+            preview_significant: true,
+            ..OutputOptions::default()
+        };
+        let significant = reports::significant_report2_rows(&rows);
+        let table = render_preview(&rows, 2, &significant, &options);
+        for row in &rows {
+            assert_eq!(
+                table.contains(&row.contractor),
+                row.risk_flag == "High Risk",
+                "{}",
+                row.contractor
+            );
+        }
+        // Header, separator and one line per high-risk contractor.
+        assert_eq!(table.lines().count(), 2 + high_risk.len());
+
+        // The default preview is still the first `default_rows` rows.
+        let table = render_preview(&rows, 2, &significant, &OutputOptions::default());
+        assert_eq!(table.lines().count(), 2 + 2);
+        assert!(table.contains(&rows[0].contractor) && table.contains(&rows[1].contractor));
+    }
+
+    #[test]
+    fn one_region_json_per_region_with_its_record_count() {
+        let data = vec![
+            CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0, 10.0),
+            CleanRecord::sample("Region I", "XYZ INC", 2000.0, 1900.0, 20.0),
+            CleanRecord::sample(" region  i", "XYZ INC", 500.0, 400.0, 5.0),
+            CleanRecord::sample("NCR", "ABC CORP", 1000.0, 1100.0, 30.0),
+            CleanRecord::sample("Region IV-A/B", "LMN JV", 700.0, 600.0, 0.0),
+        ];
+        let regions = reports::region_drill_downs(&data, &ReportConfig::default(), false);
+        let dir = tempfile::tempdir().unwrap();
+        let out = path_in(&dir, "by_region");
+        let files = write_region_json(&out, &regions).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(std::fs::read_dir(&out).unwrap().count(), 3);
+
+        let mut counts: Vec<(String, usize)> = files
+            .iter()
+            .map(|file| {
+                assert!(file.ends_with(".json") && !file[out.len() + 1..].contains('/'));
+                let json: serde_json::Value =
+                    serde_json::from_str(&std::fs::read_to_string(file).unwrap()).unwrap();
+                let region = json["Region"].as_str().unwrap().to_string();
+                assert_eq!(json["Summary"]["Region"], region.as_str());
+                (region, json["Projects"].as_array().unwrap().len())
+            })
+            .collect();
+        counts.sort();
+        assert_eq!(
+            counts,
+            [
+                ("NCR".to_string(), 1),
+                ("Region I".to_string(), 3),
+                ("Region IV-A/B".to_string(), 1),
+            ]
+        );
+    }
 }
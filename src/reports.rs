@@ -6,12 +6,138 @@
 // 2. Contractors (Report 2)
 // 3. Funding year + type of work trends (Report 3)
 // 4. Overall summary statistics
+use crate::config::{
+    EfficiencyFormula, JvSplit, PlaceholderGroups, Placeholders, ReportConfig, RoundingMode,
+    YoyBaseline,
+};
 use crate::types::{
-    CleanRecord, ContractorRankingRow, RegionSummaryRow, SummaryStats, TypeTrendRow,
+    CleanRecord, CleanRecordRow, ContractorAgg, ContractorRankingRow, CostOutlierRow,
+    IslandSavings, MergeCandidateRow, RegionDrillDown, RegionRankChangeRow, RegionSummaryRow,
+    ReliabilityStats, SingleRegionContractorRow, SourcedRegionRow, SummaryStats, TypeTrendRow,
+    WorseningContractorRow, YearWeightedAvg,
+};
+use crate::util::{
+    average, canonical_name, date_span, days_diff, format_number, gini, joint_venture_members,
+    median, parse_f64_safe, percentile, round_currency, safe_ratio, std_dev,
 };
-use crate::util::{average, format_number, median};
+use chrono::NaiveDate;
+use csv::StringRecord;
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use tabled::Tabled;
+
+/// A peso amount as written in the reports: two decimals, rounded per
+/// `cfg.currency_rounding`.
+fn money(value: f64, cfg: &ReportConfig) -> String {
+    format!("{:.2}", round_currency(value, 2, cfg.currency_rounding))
+}
+
+/// Per-(Region, MainIsland) accumulator shared by `generate_report1` and
+/// `region_summary`.
+#[derive(Default)]
+struct RegionAcc {
+    budgets: Vec<f64>,
+    savings: Vec<f64>,
+    delays: Vec<f64>,
+    /// Projects with an imputed completion date or coordinates.
+    imputed: usize,
+    region: String,
+    island: String,
+}
+
+impl RegionAcc {
+    fn push(&mut self, r: &CleanRecord) {
+        self.budgets.push(r.approved_budget);
+        self.savings.push(r.cost_savings);
+        if !r.delay_excluded {
+            self.delays.push(r.completion_delay_days);
+        }
+        if r.is_imputed() {
+            self.imputed += 1;
+        }
+    }
+}
+
+/// Prepared row that holds both formatted strings and the efficiency
+/// values: `raw_efficiency` straight from the formula, and
+/// `weighted_efficiency` after any multipliers (used for min-max
+/// normalization later).
+struct RegionPrep {
+    region: String,
+    main_island: String,
+    total_budget: String,
+    median_savings: String,
+    avg_delay: String,
+    high_delay_pct: String,
+    budget_share_pct: String,
+    iqr: String,
+    raw_efficiency: f64,
+    weighted_efficiency: f64,
+}
+
+/// Compute one group's Report 1 aggregates and raw efficiency.
+/// `national_budget` is the BudgetSharePct denominator.
+fn prepare_region(acc: RegionAcc, cfg: &ReportConfig, national_budget: f64) -> RegionPrep {
+    let avg_delay = average(&acc.delays);
+    let high_delay_count = acc
+        .delays
+        .iter()
+        .filter(|d| **d > cfg.high_delay_threshold_days)
+        .count();
+    let high_delay_pct = safe_ratio(high_delay_count as f64, acc.delays.len() as f64) * 100.0;
+    let med_savings = median(acc.savings.clone());
+    // Spread of savings that ignores the extremes; a single project (or
+    // none) has no spread.
+    let iqr = if acc.savings.len() < 2 {
+        0.0
+    } else {
+        percentile(&acc.savings, 75.0) - percentile(&acc.savings, 25.0)
+    };
+    // Raw efficiency is `median_savings / avg_delay` by default, or
+    // `median_savings / (avg_delay + 1)` so zero-delay regions score
+    // high. Values are clamped to non-negative here (`safe_ratio` already
+    // rules out NaN); the normalization to [0,100] happens across groups
+    // in `generate_report1`.
+    let eff = match cfg.efficiency_formula {
+        // A negative average delay would flip the sign, so it scores 0
+        // like zero delay does.
+        EfficiencyFormula::ZeroWhenNoDelay if avg_delay <= 0.0 => 0.0,
+        EfficiencyFormula::ZeroWhenNoDelay => safe_ratio(med_savings, avg_delay),
+        // Early completion (negative delay) counts as zero delay so
+        // the denominator never drops below one day.
+        EfficiencyFormula::PlusOneDay => safe_ratio(med_savings, avg_delay.max(0.0) + 1.0),
+    }
+    .max(0.0);
+    let raw_efficiency = eff;
+    let total_budget: f64 = acc.budgets.iter().sum();
+    let budget_share_pct = safe_ratio(total_budget, national_budget) * 100.0;
+    // Weighting by budget share keeps a small region with an outsized
+    // ratio from pinning the top of the min-max scale.
+    let eff = if cfg.weighted_efficiency {
+        eff * budget_share_pct / 100.0
+    } else {
+        eff
+    };
+    let eff = if cfg.completeness_weighted {
+        let imputed_rate = safe_ratio(acc.imputed as f64, acc.budgets.len() as f64);
+        eff * (1.0 - imputed_rate)
+    } else {
+        eff
+    };
+    RegionPrep {
+        region: acc.region,
+        main_island: acc.island,
+        total_budget: money(total_budget, cfg),
+        median_savings: money(med_savings, cfg),
+        avg_delay: format!("{:.2}", avg_delay),
+        high_delay_pct: format!("{:.2}", high_delay_pct),
+        budget_share_pct: format!("{:.2}", budget_share_pct),
+        iqr: money(iqr, cfg),
+        raw_efficiency,
+        weighted_efficiency: eff,
+    }
+}
 
 /// Generate Report 1: Regional Flood Mitigation Efficiency Summary.
 ///
@@ -20,83 +146,36 @@ use std::collections::{HashMap, HashSet};
 /// - Compute:
 ///   * TotalBudget (sum of budgets)
 ///   * MedianSavings (median of savings)
+///   * IQR (Q3 - Q1 of savings)
 ///   * AvgDelay (mean of delays)
-///   * HighDelayPct (% of projects with delay > 30 days)
-///   * Raw efficiency = MedianSavings / AvgDelay (guarding against /0).
-/// - After computing raw efficiency for all regions, perform a min-max
-///   normalization so that EfficiencyScore lies in [0, 100] and preserves
-pub fn generate_report1(data: &[CleanRecord]) -> Vec<RegionSummaryRow> {
-    // Accumulator for each (Region, MainIsland) group.
-    #[derive(Default)]
-    struct Acc {
-        budgets: Vec<f64>,
-        savings: Vec<f64>,
-        delays: Vec<f64>,
-        region: String,
-        island: String,
-    }
-    // Prepared row that holds both formatted strings and the raw
-    // efficiency score (used for min-max normalization later).
-    #[derive(Clone)]
-    struct RowPrep {
-        region: String,
-        main_island: String,
-        total_budget: String,
-        median_savings: String,
-        avg_delay: String,
-        high_delay_pct: String,
-        raw_efficiency: f64,
-    }
+///   * HighDelayPct (% of projects with delay > `cfg.high_delay_threshold_days`)
+///   * Raw efficiency = MedianSavings / AvgDelay (guarding against /0), or
+///     MedianSavings / (AvgDelay + 1) under `EfficiencyFormula::PlusOneDay`
+///   * Weighted efficiency = raw efficiency times the group's budget share
+///     with `cfg.weighted_efficiency`, and times its share of projects with
+///     nothing imputed with `cfg.completeness_weighted`
+/// - After computing weighted efficiency for all regions, perform a
+///   min-max normalization so that EfficiencyScore lies in [0, 100] and
+///   preserves their order.
+pub fn generate_report1(data: &[CleanRecord], cfg: &ReportConfig) -> Vec<RegionSummaryRow> {
+    // National denominator for BudgetSharePct, computed once up front.
+    let national_budget: f64 = data.iter().map(|r| r.approved_budget).sum();
 
     // First pass: group all rows by (Region, MainIsland).
-    let mut map: HashMap<(String, String), Acc> = HashMap::new();
+    let mut map: HashMap<(String, String), RegionAcc> = HashMap::new();
     for r in data {
         let key = (r.region.clone(), r.main_island.clone());
-        let e = map.entry(key.clone()).or_insert_with(|| Acc {
-            budgets: vec![],
-            savings: vec![],
-            delays: vec![],
+        let e = map.entry(key.clone()).or_insert_with(|| RegionAcc {
             region: key.0.clone(),
             island: key.1.clone(),
+            ..RegionAcc::default()
         });
-        e.budgets.push(r.approved_budget);
-        e.savings.push(r.cost_savings);
-        e.delays.push(r.completion_delay_days);
+        e.push(r);
     }
     // Second pass: compute group-level aggregates and raw efficiency.
-    let prepared: Vec<RowPrep> = map
+    let prepared: Vec<RegionPrep> = map
         .into_values()
-        .map(|acc| {
-            let avg_delay = average(&acc.delays);
-            let delay_over_30 = if acc.delays.is_empty() {
-                0.0
-            } else {
-                (acc.delays.iter().filter(|d| **d > 30.0).count() as f64 / acc.delays.len() as f64)
-                    * 100.0
-            };
-            let med_savings = median(acc.savings.clone());
-            // Raw efficiency is defined as `median_savings / avg_delay`.
-            // Values are clamped to non-negative and non-NaN here; the
-            // normalization to [0,100] happens in a separate pass below.
-            let mut eff = if avg_delay <= 0.0 {
-                0.0
-            } else {
-                med_savings / avg_delay
-            };
-            if !eff.is_finite() || eff < 0.0 {
-                eff = 0.0;
-            }
-            let total_budget: f64 = acc.budgets.iter().sum();
-            RowPrep {
-                region: acc.region,
-                main_island: acc.island,
-                total_budget: format!("{:.2}", total_budget),
-                median_savings: format!("{:.2}", med_savings),
-                avg_delay: format!("{:.2}", avg_delay),
-                high_delay_pct: format!("{:.2}", delay_over_30),
-                raw_efficiency: eff,
-            }
-        })
+        .map(|acc| prepare_region(acc, cfg, national_budget))
         .collect();
     if prepared.is_empty() {
         return Vec::new();
@@ -105,8 +184,8 @@ pub fn generate_report1(data: &[CleanRecord]) -> Vec<RegionSummaryRow> {
     // Compute the min and max raw efficiency across all regions.
     let (mut min_eff, mut max_eff) = (f64::MAX, f64::MIN);
     for row in &prepared {
-        min_eff = min_eff.min(row.raw_efficiency);
-        max_eff = max_eff.max(row.raw_efficiency);
+        min_eff = min_eff.min(row.weighted_efficiency);
+        max_eff = max_eff.max(row.weighted_efficiency);
     }
     if !min_eff.is_finite() {
         min_eff = 0.0;
@@ -121,15 +200,12 @@ pub fn generate_report1(data: &[CleanRecord]) -> Vec<RegionSummaryRow> {
     let mut scored: Vec<(f64, RegionSummaryRow)> = prepared
         .into_iter()
         .map(|row| {
-            let mut scaled = if range.abs() < f64::EPSILON {
-                0.0
-            } else {
-                ((row.raw_efficiency - min_eff) / range) * 100.0
-            };
-            if !scaled.is_finite() {
-                scaled = 0.0;
-            }
-            scaled = scaled.clamp(0.0, 100.0);
+            // A zero range (every group equally efficient) scores 0.
+            let scaled =
+                (safe_ratio(row.weighted_efficiency - min_eff, range) * 100.0).clamp(0.0, 100.0);
+            let raw_efficiency = cfg
+                .raw_efficiency
+                .then(|| format!("{:.2}", row.raw_efficiency));
             let rendered = RegionSummaryRow {
                 region: row.region,
                 main_island: row.main_island,
@@ -140,6 +216,10 @@ pub fn generate_report1(data: &[CleanRecord]) -> Vec<RegionSummaryRow> {
                 // CSV cells should be "100.00" style, without
                 // thousands separators.
                 efficiency_score: format!("{:.2}", scaled),
+                budget_share_pct: row.budget_share_pct,
+                iqr: row.iqr,
+                percentile_rank: String::new(), // fill after sorting
+                raw_efficiency,
             };
             (scaled, rendered)
         })
@@ -148,78 +228,187 @@ pub fn generate_report1(data: &[CleanRecord]) -> Vec<RegionSummaryRow> {
     // Sort descending by scaled efficiency so the best-performing regions
     // appear first in both the preview and CSV.
     scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+    // PercentileRank: the group's average ascending rank among all groups,
+    // scaled so the lowest score is 0 and the highest 100. Groups whose
+    // EfficiencyScore matches to the shown two decimals are tied and share
+    // the average of their ranks.
+    let n = scored.len();
+    let shown = |score: f64| (score * 100.0).round();
+    let mut start = 0;
+    while start < n {
+        let mut end = start + 1;
+        while end < n && shown(scored[end].0) == shown(scored[start].0) {
+            end += 1;
+        }
+        let lower = (n - end) as f64;
+        let ties = (end - start) as f64;
+        let pct = safe_ratio(lower + (ties - 1.0) / 2.0, (n - 1) as f64) * 100.0;
+        for (_, row) in &mut scored[start..end] {
+            row.percentile_rank = format!("{:.2}", pct);
+        }
+        start = end;
+    }
     scored.into_iter().map(|(_, row)| row).collect()
 }
 
+/// Compute Report 1's metrics for a single region, or `None` if no record
+/// belongs to it. Names match after `canonical_name` (case and spacing are
+/// ignored), and all of the region's records form one group; if they span
+/// several main islands, `MainIsland` lists them joined by `/`.
+///
+/// Only this region's records are aggregated, so this is much cheaper than
+/// building the whole report. EfficiencyScore and PercentileRank compare
+/// against all regions in Report 1 and have no meaning for one region
+/// alone, so they are returned as the sentinel `N/A`; BudgetSharePct is
+/// still relative to the national total.
+pub fn region_summary(
+    data: &[CleanRecord],
+    region_name: &str,
+    cfg: &ReportConfig,
+) -> Option<RegionSummaryRow> {
+    let wanted = canonical_name(region_name);
+    let national_budget: f64 = data.iter().map(|r| r.approved_budget).sum();
+    summarize_region(
+        data.iter().filter(|r| r.in_region(&wanted)),
+        national_budget,
+        cfg,
+    )
+}
+
+/// `region_summary` over one region's `records`, with the national total
+/// budget already summed, so callers covering every region pass over the
+/// data only once.
+fn summarize_region<'a>(
+    records: impl IntoIterator<Item = &'a CleanRecord>,
+    national_budget: f64,
+    cfg: &ReportConfig,
+) -> Option<RegionSummaryRow> {
+    let mut islands: BTreeSet<&str> = BTreeSet::new();
+    let mut acc = RegionAcc::default();
+    for r in records {
+        if acc.budgets.is_empty() {
+            acc.region = r.region.clone();
+        }
+        islands.insert(r.main_island.as_str());
+        acc.push(r);
+    }
+    if acc.budgets.is_empty() {
+        return None;
+    }
+    acc.island = islands.into_iter().collect::<Vec<_>>().join("/");
+    let row = prepare_region(acc, cfg, national_budget);
+    Some(RegionSummaryRow {
+        region: row.region,
+        main_island: row.main_island,
+        total_budget: row.total_budget,
+        median_savings: row.median_savings,
+        avg_delay: row.avg_delay,
+        high_delay_pct: row.high_delay_pct,
+        efficiency_score: "N/A".to_string(),
+        budget_share_pct: row.budget_share_pct,
+        iqr: row.iqr,
+        percentile_rank: "N/A".to_string(),
+        // Unlike the score, the raw value needs no other regions.
+        raw_efficiency: cfg
+            .raw_efficiency
+            .then(|| format!("{:.2}", row.raw_efficiency)),
+    })
+}
+
+/// Upper bound of Report 2's BudgetUtilizationPct. Spending more than twice
+/// the approved budget only happens with bad data, so larger values are
+/// shown as this cap rather than as extreme outliers.
+const UTILIZATION_CAP_PCT: f64 = 200.0;
+
 /// Generate Report 2: Top Contractors Performance Ranking.
 ///
 /// Algorithm:
-/// - Group projects by contractor.
-/// - Filter out contractors with fewer than 5 projects.
+/// - Group projects by contractor via `aggregate_contractors`, splitting
+///   joint ventures into their member firms per `cfg.jv_split`.
+/// - Filter out contractors with fewer than `cfg.min_contractor_projects`
+///   projects (5 by default).
 /// - For each contractor, compute:
 ///   * TotalCost = sum of contract_cost
 ///   * NumProjects = project count
 ///   * AvgDelay = mean of completion delays
 ///   * TotalSavings = sum of cost_savings
-///   * ReliabilityIndex = (1 - AvgDelay/90) * (TotalSavings/TotalCost) * 100,
-///     clamped only on the upper bound (can be negative).
-/// - Sort contractors by TotalCost descending and take the top 15.
-pub fn generate_report2(data: &[CleanRecord]) -> Vec<ContractorRankingRow> {
-    #[derive(Default)]
-    struct Acc {
-        projects: usize,
-        delays: Vec<f64>,
-        total_savings: f64,
-        total_cost: f64,
-    }
-    let mut map: HashMap<String, Acc> = HashMap::new();
-    for r in data {
-        let e = map.entry(r.contractor.clone()).or_default();
-        e.projects += 1;
-        e.delays.push(r.completion_delay_days);
-        e.total_savings += r.cost_savings;
-        e.total_cost += r.contract_cost;
-    }
+///   * PrimaryProvince = the province with most of its projects
+///     (alphabetically first on a tie)
+///   * BudgetUtilizationPct = TotalCost / total approved budget * 100,
+///     clamped to [0, `UTILIZATION_CAP_PCT`]
+///   * ReliabilityIndex = (1 - AvgDelay/H) * (TotalSavings/TotalCost) * 100,
+///     where H is `cfg.reliability_horizon_days` (90 by default) or, with
+///     `cfg.type_horizons`, each project's own type's horizon,
+///     clamped on the upper bound `cfg.reliability_cap` (100 by default).
+///     Low and negative values are kept unless `cfg.reliability_floor` is
+///     set, in which case they are clamped to it.
+/// - Sort contractors by TotalCost descending and take the top
+///   `cfg.top_contractors` (15 by default).
+///
+/// RiskFlag is derived from the final (possibly clamped) index, so it is
+/// always consistent with the value shown in the ReliabilityIndex column.
+pub fn generate_report2(data: &[CleanRecord], cfg: &ReportConfig) -> Vec<ContractorRankingRow> {
+    // National denominator for CostSharePct, over every record rather
+    // than only the contractors that make the cut.
+    let national_cost: f64 = data.iter().map(|r| r.contract_cost).sum();
+    let map = aggregate_contractors(data, cfg.jv_split);
     // Turn the map into a flat list of tuples so we can sort by
     // total_cost while keeping all derived metrics together.
-    let mut tmp: Vec<(f64, String, usize, f64, f64, f64)> = map
+    let mut tmp: Vec<(String, ContractorAgg, f64, f64)> = map
         .into_iter()
-        .filter(|(_, v)| v.projects >= 5)
+        .filter(|(_, v)| v.projects >= cfg.min_contractor_projects)
         .map(|(k, v)| {
             let avg_delay = average(&v.delays);
-            let mut reliability =
-                (1.0 - (avg_delay / 90.0)) * (v.total_savings / v.total_cost) * 100.0;
-            if !reliability.is_finite() {
-                reliability = 0.0;
+            let mut reliability = raw_reliability(&v, cfg);
+            if reliability > cfg.reliability_cap {
+                reliability = cfg.reliability_cap;
             }
-            if reliability > 100.0 {
-                reliability = 100.0;
-            } // only cap upper bound
-            (
-                v.total_cost,
-                k,
-                v.projects,
-                avg_delay,
-                v.total_savings,
-                reliability,
-            )
+            if let Some(floor) = cfg.reliability_floor {
+                if reliability < floor {
+                    reliability = floor;
+                }
+            }
+            (k, v, avg_delay, reliability)
         })
         .collect();
-    // Sort descending by total contract cost and keep only the top 15.
-    tmp.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    // Sort descending by total contract cost and keep only the top N.
+    tmp.sort_by(|a, b| b.1.total_cost.partial_cmp(&a.1.total_cost).unwrap());
     let mut rows: Vec<ContractorRankingRow> = Vec::new();
-    for (idx, (total_cost, contractor, projects, avg_delay, total_savings, reliability)) in
-        tmp.into_iter().take(15).enumerate()
+    for (idx, (contractor, agg, avg_delay, reliability)) in
+        tmp.into_iter().take(cfg.top_contractors).enumerate()
     {
+        let (total_cost, projects, total_savings) =
+            (agg.total_cost, agg.projects, agg.total_savings);
+        // Savings per day of delay incurred across the contractor's projects
+        // that count towards delay averages. Undefined, and written as "N/A",
+        // when that total is zero or negative, since dividing by it would
+        // either blow up or flip the sign.
+        let total_delay: f64 = agg.delays.iter().sum();
+        let savings_per_delay_day = if total_delay > 0.0 {
+            money(safe_ratio(total_savings, total_delay), cfg)
+        } else {
+            "N/A".to_string()
+        };
+        let cost_share = safe_ratio(total_cost, national_cost) * 100.0;
+        // Each record's savings is its budget minus its cost, so the summed
+        // budget is cost + savings (also under a JV split, which scales
+        // both by the same share).
+        let utilization = (safe_ratio(total_cost, total_cost + total_savings) * 100.0)
+            .clamp(0.0, UTILIZATION_CAP_PCT);
         rows.push(ContractorRankingRow {
             rank: idx + 1,
             contractor,
-            total_cost: format!("{:.2}", total_cost),
+            total_cost: money(total_cost, cfg),
             num_projects: projects,
             avg_delay: format!("{:.2}", avg_delay),
-            total_savings: format!("{:.2}", total_savings),
+            total_savings: money(total_savings, cfg),
             reliability_index: format!("{:.2}", reliability),
-            risk_flag: if reliability < 50.0 {
+            savings_per_delay_day,
+            cost_share_pct: format!("{:.2}", cost_share),
+            primary_province: agg.primary_province().unwrap_or_default(),
+            budget_utilization_pct: format!("{:.2}", utilization),
+            risk_flag: if reliability < cfg.risk_threshold {
                 "High Risk".to_string()
             } else {
                 "OK".to_string()
@@ -229,6 +418,85 @@ pub fn generate_report2(data: &[CleanRecord]) -> Vec<ContractorRankingRow> {
     rows
 }
 
+/// A contractor's ReliabilityIndex before `reliability_cap` and
+/// `reliability_floor` are applied.
+///
+/// With `cfg.type_horizons` set, the delay term is the mean of each
+/// project's delay over its own type's horizon; with a single horizon
+/// that equals AvgDelay / H.
+fn raw_reliability(agg: &ContractorAgg, cfg: &ReportConfig) -> f64 {
+    let delay_term = if cfg.type_horizons.is_empty() {
+        safe_ratio(average(&agg.delays), cfg.reliability_horizon_days)
+    } else {
+        let ratios: Vec<f64> = agg
+            .delays
+            .iter()
+            .zip(&agg.delay_types)
+            .map(|(delay, t)| safe_ratio(*delay, cfg.horizon_for(t)))
+            .collect();
+        average(&ratios)
+    };
+    (1.0 - delay_term) * safe_ratio(agg.total_savings, agg.total_cost) * 100.0
+}
+
+/// Distribution of the raw (uncapped, unfloored) ReliabilityIndex over
+/// every contractor that qualifies for Report 2, i.e. before the top-N
+/// cut. `None` when no contractor has enough projects.
+pub fn reliability_stats(data: &[CleanRecord], cfg: &ReportConfig) -> Option<ReliabilityStats> {
+    let values: Vec<f64> = aggregate_contractors(data, cfg.jv_split)
+        .values()
+        .filter(|v| v.projects >= cfg.min_contractor_projects)
+        .map(|v| raw_reliability(v, cfg))
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    Some(ReliabilityStats {
+        qualifying: values.len(),
+        min: values.iter().copied().fold(f64::INFINITY, f64::min),
+        median: median(values.clone()),
+        max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        negative: values.iter().filter(|v| **v < 0.0).count(),
+    })
+}
+
+/// Group projects by contractor and accumulate the totals every
+/// contractor-level feature needs (counts, delays, savings, cost, and the
+/// provinces, regions and funding years they worked in).
+///
+/// With a `jv_split` other than `Single`, a joint-venture name (see
+/// `joint_venture_members`) is credited to each member firm instead.
+pub fn aggregate_contractors(
+    data: &[CleanRecord],
+    jv_split: JvSplit,
+) -> HashMap<String, ContractorAgg> {
+    let mut map: HashMap<String, ContractorAgg> = HashMap::new();
+    for r in data {
+        let members = match jv_split {
+            JvSplit::Single => vec![r.contractor.clone()],
+            JvSplit::FullCredit | JvSplit::SplitEvenly => joint_venture_members(&r.contractor),
+        };
+        let share = match jv_split {
+            JvSplit::SplitEvenly => 1.0 / members.len() as f64,
+            JvSplit::Single | JvSplit::FullCredit => 1.0,
+        };
+        for member in members {
+            let e = map.entry(member).or_default();
+            e.projects += 1;
+            if !r.delay_excluded {
+                e.delays.push(r.completion_delay_days);
+                e.delay_types.push(r.type_of_work.clone());
+            }
+            e.total_savings += r.cost_savings * share;
+            e.total_cost += r.contract_cost * share;
+            *e.provinces.entry(r.province.clone()).or_default() += 1;
+            *e.regions.entry(r.region.clone()).or_default() += 1;
+            *e.years.entry(r.funding_year).or_default() += 1;
+        }
+    }
+    map
+}
+
 /// Generate Report 3: Annual Project Type Cost Overrun Trends.
 ///
 /// Algorithm:
@@ -237,12 +505,14 @@ pub fn generate_report2(data: &[CleanRecord]) -> Vec<ContractorRankingRow> {
 ///   * TotalProjects
 ///   * AvgSavings (average of cost_savings)
 ///   * OverrunRate (% of projects with negative savings).
-/// - Separately maintain a per-year weighted average of savings across
-///   all types: (sum of savings) / (total project count).
-/// - Take 2021's weighted average as the baseline and compute a
-///   YoYChange for each year relative to that baseline.
+/// - Compute YoYChange against a baseline chosen by `cfg.yoy_baseline`:
+///   * `PerType` (default): the same TypeOfWork's AvgSavings in the
+///     baseline year (2021 by default); baseline-year rows get 0.00.
+///   * `GlobalPerYear`: the baseline year's weighted average of savings
+///     across all types, (sum of savings) / (total project count), as
+///     returned by `year_weighted_averages`.
 /// - Sort rows by FundingYear ascending, then AvgSavings descending.
-pub fn generate_report3(data: &[CleanRecord]) -> Vec<TypeTrendRow> {
+pub fn generate_report3(data: &[CleanRecord], cfg: &ReportConfig) -> Vec<TypeTrendRow> {
     #[derive(Default)]
     struct Acc {
         year: i32,
@@ -259,6 +529,21 @@ pub fn generate_report3(data: &[CleanRecord]) -> Vec<TypeTrendRow> {
         });
         e.savings.push(r.cost_savings);
     }
+    if cfg.dense_report3 {
+        // One row per observed year x observed type; combinations without
+        // projects get an empty accumulator (TotalProjects 0).
+        let years: BTreeSet<i32> = map.keys().map(|(y, _)| *y).collect();
+        let types: BTreeSet<String> = map.keys().map(|(_, t)| t.clone()).collect();
+        for year in &years {
+            for tow in &types {
+                map.entry((*year, tow.clone())).or_insert_with(|| Acc {
+                    year: *year,
+                    tow: tow.clone(),
+                    savings: vec![],
+                });
+            }
+        }
+    }
 
     // We track a numeric average per (year, type) plus formatted fields.
     // The numeric average is stored alongside the row for sorting and
@@ -267,53 +552,128 @@ pub fn generate_report3(data: &[CleanRecord]) -> Vec<TypeTrendRow> {
     for acc in map.into_values() {
         let avg = average(&acc.savings);
         let total_projects = acc.savings.len();
-        let overrun_rate = if acc.savings.is_empty() {
-            0.0
-        } else {
-            (acc.savings.iter().filter(|s| **s < 0.0).count() as f64 / acc.savings.len() as f64)
-                * 100.0
-        };
+        let overruns = acc.savings.iter().filter(|s| **s < 0.0).count();
+        let overrun_rate = safe_ratio(overruns as f64, total_projects as f64) * 100.0;
         let row = TypeTrendRow {
             funding_year: acc.year,
             type_of_work: acc.tow,
             total_projects,
-            avg_savings: format!("{:.2}", avg),
+            avg_savings: money(avg, cfg),
             overrun_rate: format!("{:.2}", overrun_rate),
             yoy_change: String::new(), // fill later
+            smoothed_avg_savings: None,
+            project_count_yoy: String::new(), // fill later
+            count_collapse: None,
         };
         rows_num.push((row.funding_year, avg, row));
     }
 
-    // Build a per-TypeOfWork baseline from 2021 averages, mirroring the
-    // JavaScript implementation's `baselineByType`.
+    // Build a per-TypeOfWork baseline from the baseline year's averages
+    // (2021 by default), mirroring the JavaScript implementation's
+    // `baselineByType`.
     let mut baseline_by_type: HashMap<String, f64> = HashMap::new();
     for (year, avg_val, row) in &rows_num {
-        if *year == 2021 {
+        if *year == cfg.baseline_year && row.total_projects > 0 {
             baseline_by_type
                 .entry(row.type_of_work.clone())
                 .or_insert(*avg_val);
         }
     }
 
-    // Compute YoY change per (year, type) using that type's 2021
-    // baseline. If there is no baseline or it is zero, YoYChange is 0.00.
+    // The all-types weighted average for the baseline year, used by
+    // `YoyBaseline::GlobalPerYear`.
+    let global_baseline = weighted_savings_by_year(data)
+        .get(&cfg.baseline_year)
+        .map(|(avg, _)| *avg)
+        .unwrap_or(0.0);
+
+    // Compute YoY change per (year, type) against the chosen baseline. If
+    // there is no baseline or it is zero, YoYChange is 0.00.
     let mut rows_with_avg: Vec<(i32, f64, TypeTrendRow)> = rows_num
         .into_iter()
         .map(|(year, avg_val, mut row)| {
-            let baseline = baseline_by_type
-                .get(&row.type_of_work)
-                .copied()
-                .unwrap_or(0.0);
-            let change = if year == 2021 || baseline.abs() < f64::EPSILON {
+            let (baseline, is_baseline_row) = match cfg.yoy_baseline {
+                YoyBaseline::PerType => (
+                    baseline_by_type
+                        .get(&row.type_of_work)
+                        .copied()
+                        .unwrap_or(0.0),
+                    year == cfg.baseline_year,
+                ),
+                YoyBaseline::GlobalPerYear => (global_baseline, false),
+            };
+            let change = if is_baseline_row || row.total_projects == 0 {
                 0.0
             } else {
-                ((avg_val - baseline) / baseline.abs()) * 100.0
+                safe_ratio(avg_val - baseline, baseline.abs()) * 100.0
             };
             row.yoy_change = format!("{:.2}", change);
             (year, avg_val, row)
         })
         .collect();
 
+    // ProjectCountYoY compares each row with the same type one funding
+    // year earlier.
+    let counts: HashMap<(i32, String), usize> = rows_with_avg
+        .iter()
+        .map(|(year, _, row)| ((*year, row.type_of_work.clone()), row.total_projects))
+        .collect();
+    for (year, _, row) in rows_with_avg.iter_mut() {
+        let previous = counts
+            .get(&(*year - 1, row.type_of_work.clone()))
+            .copied()
+            .unwrap_or(0);
+        // No projects the year before has no meaningful change: "N/A".
+        let change = (previous > 0).then(|| {
+            safe_ratio(row.total_projects as f64 - previous as f64, previous as f64) * 100.0
+        });
+        row.project_count_yoy = change
+            .map(|c| format!("{:.2}", c))
+            .unwrap_or_else(|| "N/A".to_string());
+        if let Some(drop_pct) = cfg.count_drop_pct {
+            let collapsed = change.is_some_and(|c| c < -drop_pct);
+            row.count_collapse = Some(if collapsed { "Yes" } else { "No" }.to_string());
+        }
+    }
+
+    if cfg.smooth_savings {
+        // Centered three-year moving average of each type's AvgSavings over
+        // the years present in the data; the first and last year average
+        // over the two years they have. Types missing from any year have
+        // no comparable series and get "N/A".
+        let years: Vec<i32> = rows_with_avg
+            .iter()
+            .map(|(year, _, _)| *year)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let mut series: HashMap<String, BTreeMap<i32, f64>> = HashMap::new();
+        for (year, avg_val, row) in rows_with_avg
+            .iter()
+            .filter(|(_, _, r)| r.total_projects > 0)
+        {
+            series
+                .entry(row.type_of_work.clone())
+                .or_default()
+                .insert(*year, *avg_val);
+        }
+        for (year, _, row) in rows_with_avg.iter_mut() {
+            let Some(by_year) = series.get(&row.type_of_work) else {
+                row.smoothed_avg_savings = Some("N/A".to_string());
+                continue;
+            };
+            let smoothed = if by_year.len() == years.len() {
+                let i = years.iter().position(|y| y == year).unwrap_or(0);
+                let window = &years[i.saturating_sub(1)..(i + 2).min(years.len())];
+                let values: Vec<f64> = window.iter().map(|y| by_year[y]).collect();
+                money(average(&values), cfg)
+            } else {
+                "N/A".to_string()
+            };
+            row.smoothed_avg_savings = Some(smoothed);
+        }
+    }
+
     // Sort by FundingYear ascending, then by AvgSavings (numeric) descending.
     // a.0 and b.0 are the funding years; a.1 and b.1 are the numeric
     // average savings used purely for sorting (the formatted string lives
@@ -327,10 +687,987 @@ pub fn generate_report3(data: &[CleanRecord]) -> Vec<TypeTrendRow> {
     rows_with_avg.into_iter().map(|(_, _, row)| row).collect()
 }
 
-/// Generate high-level summary statistics over all cleaned records.
+/// Partition the records by region (matched on `canonical_name`, named by
+/// the first spelling seen) and build each region's drill-down: its
+/// `region_summary` plus its records as `clean_record_rows`. Regions are
+/// in name order.
+pub fn region_drill_downs(
+    data: &[CleanRecord],
+    cfg: &ReportConfig,
+    flag_exact_budget: bool,
+) -> Vec<RegionDrillDown> {
+    let national_budget: f64 = data.iter().map(|r| r.approved_budget).sum();
+    let mut groups: BTreeMap<String, Vec<CleanRecord>> = BTreeMap::new();
+    for r in data {
+        groups
+            .entry(canonical_name(&r.region))
+            .or_default()
+            .push(r.clone());
+    }
+    groups
+        .into_values()
+        .filter_map(|records| {
+            let summary = summarize_region(&records, national_budget, cfg)?;
+            Some(RegionDrillDown {
+                region: records[0].region.clone(),
+                summary,
+                projects: clean_record_rows(&records, flag_exact_budget),
+            })
+        })
+        .collect()
+}
+
+/// The records as `clean_records.csv` rows, in a stable order (FundingYear,
+/// Region, Province, Contractor, then SourceLine) numbered from 1. With
+/// `flag_exact_budget`, each row also says whether its cost equals its
+/// budget exactly (see `is_exact_budget_match`).
+pub fn clean_record_rows(data: &[CleanRecord], flag_exact_budget: bool) -> Vec<CleanRecordRow> {
+    let mut sorted: Vec<&CleanRecord> = data.iter().collect();
+    sorted.sort_by(|a, b| {
+        (
+            a.funding_year,
+            &a.region,
+            &a.province,
+            &a.contractor,
+            a.source_line,
+        )
+            .cmp(&(
+                b.funding_year,
+                &b.region,
+                &b.province,
+                &b.contractor,
+                b.source_line,
+            ))
+    });
+    sorted
+        .into_iter()
+        .enumerate()
+        .map(|(i, r)| CleanRecordRow {
+            id: i + 1,
+            source_line: r.source_line,
+            funding_year: r.funding_year,
+            region: r.region.clone(),
+            main_island: r.main_island.clone(),
+            province: r.province.clone(),
+            type_of_work: r.type_of_work.clone(),
+            contractor: r.contractor.clone(),
+            approved_budget: format!("{:.2}", r.approved_budget),
+            contract_cost: format!("{:.2}", r.contract_cost),
+            cost_savings: format!("{:.2}", r.cost_savings),
+            completion_delay_days: format!("{}", r.completion_delay_days),
+            delay_basis: r.delay_basis.as_str(),
+            completion_date: r.completion_date.format("%Y-%m-%d").to_string(),
+            completion_date_imputed: r.completion_date_imputed,
+            lat: r.lat,
+            lon: r.lon,
+            coord_source: r.coord_source.map(|c| c.as_str()),
+            exact_budget_match: flag_exact_budget.then(|| is_exact_budget_match(r)),
+        })
+        .collect()
+}
+
+/// Whether a record's ContractCost equals its ApprovedBudgetForContract
+/// exactly (zero savings). Many of these suggest the cost column was
+/// filled from the budget rather than from a real bid.
+pub fn is_exact_budget_match(r: &CleanRecord) -> bool {
+    r.contract_cost == r.approved_budget
+}
+
+/// Flag records whose `contract_cost` lies outside the Tukey fences
+/// `[Q1 - multiplier*IQR, Q3 + multiplier*IQR]` (1.5 is the usual
+/// multiplier). Quartiles use `percentile`, over all records or, with
+/// `by_type`, over each TypeOfWork separately.
+///
+/// Rows are ordered by group, then by ContractCost descending.
+pub fn generate_cost_outliers(
+    data: &[CleanRecord],
+    multiplier: f64,
+    by_type: bool,
+) -> Vec<CostOutlierRow> {
+    let mut groups: BTreeMap<&str, Vec<&CleanRecord>> = BTreeMap::new();
+    for r in data {
+        let group = if by_type {
+            r.type_of_work.as_str()
+        } else {
+            "All"
+        };
+        groups.entry(group).or_default().push(r);
+    }
+    let mut rows = Vec::new();
+    for (group, records) in groups {
+        let costs: Vec<f64> = records.iter().map(|r| r.contract_cost).collect();
+        let q1 = percentile(&costs, 25.0);
+        let q3 = percentile(&costs, 75.0);
+        let lower = q1 - multiplier * (q3 - q1);
+        let upper = q3 + multiplier * (q3 - q1);
+        let mut outliers: Vec<&CleanRecord> = records
+            .into_iter()
+            .filter(|r| r.contract_cost < lower || r.contract_cost > upper)
+            .collect();
+        outliers.sort_by(|a, b| b.contract_cost.total_cmp(&a.contract_cost));
+        rows.extend(outliers.into_iter().map(|r| CostOutlierRow {
+            group: group.to_string(),
+            funding_year: r.funding_year,
+            region: r.region.clone(),
+            province: r.province.clone(),
+            type_of_work: r.type_of_work.clone(),
+            contractor: r.contractor.clone(),
+            contract_cost: format!("{:.2}", r.contract_cost),
+            lower_fence: format!("{:.2}", lower),
+            upper_fence: format!("{:.2}", upper),
+        }));
+    }
+    rows
+}
+
+/// Combine the Report 1 rows of several `(label, rows)` sources into one
+/// table with a `Source` column. Rows are ordered by Region and MainIsland,
+/// then by the order of `sources`, so each region's rows sit together.
+pub fn combine_report1_sources(
+    sources: &[(String, Vec<RegionSummaryRow>)],
+) -> Vec<SourcedRegionRow> {
+    let mut rows: Vec<(usize, SourcedRegionRow)> = sources
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (label, report))| {
+            report.iter().map(move |row| {
+                (
+                    i,
+                    SourcedRegionRow {
+                        source: label.clone(),
+                        region: row.region.clone(),
+                        main_island: row.main_island.clone(),
+                        total_budget: row.total_budget.clone(),
+                        median_savings: row.median_savings.clone(),
+                        avg_delay: row.avg_delay.clone(),
+                        high_delay_pct: row.high_delay_pct.clone(),
+                        efficiency_score: row.efficiency_score.clone(),
+                        budget_share_pct: row.budget_share_pct.clone(),
+                        iqr: row.iqr.clone(),
+                        percentile_rank: row.percentile_rank.clone(),
+                        raw_efficiency: row.raw_efficiency.clone(),
+                    },
+                )
+            })
+        })
+        .collect();
+    rows.sort_by(|(ia, a), (ib, b)| {
+        (&a.region, &a.main_island, ia).cmp(&(&b.region, &b.main_island, ib))
+    });
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+/// A what-if copy of `data`: every record of `contractor` (matched on
+/// `canonical_name`) has its ContractCost multiplied by `multiplier` and
+/// its CostSavings recomputed. Returns the copy and how many records were
+/// adjusted; `data` itself is left unchanged.
+pub fn adjust_contractor_costs(
+    data: &[CleanRecord],
+    contractor: &str,
+    multiplier: f64,
+) -> (Vec<CleanRecord>, usize) {
+    let target = canonical_name(contractor);
+    let mut adjusted = 0;
+    let records = data
+        .iter()
+        .cloned()
+        .map(|mut r| {
+            if canonical_name(&r.contractor) == target {
+                r.contract_cost *= multiplier;
+                r.cost_savings = r.approved_budget - r.contract_cost;
+                adjusted += 1;
+            }
+            r
+        })
+        .collect();
+    (records, adjusted)
+}
+
+/// List contractors whose average delay increased strictly from 2021 to
+/// 2022 and from 2022 to 2023. A contractor needs projects (with a counted
+/// delay) in all three years to qualify; names are taken as written.
+///
+/// Rows are ordered by DelayIncrease (2023 minus 2021) descending, then
+/// by contractor name.
+pub fn generate_worsening_contractors(data: &[CleanRecord]) -> Vec<WorseningContractorRow> {
+    const YEARS: [i32; 3] = [2021, 2022, 2023];
+    let mut delays: HashMap<&str, BTreeMap<i32, Vec<f64>>> = HashMap::new();
+    let mut projects: HashMap<&str, usize> = HashMap::new();
+    for r in data {
+        *projects.entry(r.contractor.as_str()).or_default() += 1;
+        if !r.delay_excluded {
+            delays
+                .entry(r.contractor.as_str())
+                .or_default()
+                .entry(r.funding_year)
+                .or_default()
+                .push(r.completion_delay_days);
+        }
+    }
+    let mut rows: Vec<(f64, WorseningContractorRow)> = delays
+        .into_iter()
+        .filter_map(|(contractor, by_year)| {
+            let avgs: Vec<f64> = YEARS
+                .iter()
+                .map(|y| by_year.get(y).map(|d| average(d)))
+                .collect::<Option<_>>()?;
+            if !avgs.windows(2).all(|w| w[1] > w[0]) {
+                return None;
+            }
+            let increase = avgs[2] - avgs[0];
+            Some((
+                increase,
+                WorseningContractorRow {
+                    contractor: contractor.to_string(),
+                    avg_delay_2021: format!("{:.2}", avgs[0]),
+                    avg_delay_2022: format!("{:.2}", avgs[1]),
+                    avg_delay_2023: format!("{:.2}", avgs[2]),
+                    delay_increase: format!("{:.2}", increase),
+                    num_projects: projects[contractor],
+                },
+            ))
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| a.1.contractor.cmp(&b.1.contractor))
+    });
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+/// List contractors whose every project lies in at most `max_regions`
+/// distinct regions (1: a single region), from `aggregate_contractors`
+/// with `cfg.jv_split`. Rows are ordered by TotalCost descending, then by
+/// contractor name.
+pub fn single_region_contractors(
+    data: &[CleanRecord],
+    cfg: &ReportConfig,
+    max_regions: usize,
+) -> Vec<SingleRegionContractorRow> {
+    let mut rows: Vec<(String, ContractorAgg)> = aggregate_contractors(data, cfg.jv_split)
+        .into_iter()
+        .filter(|(_, agg)| agg.regions.len() <= max_regions)
+        .collect();
+    rows.sort_by(|a, b| {
+        b.1.total_cost
+            .total_cmp(&a.1.total_cost)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    rows.into_iter()
+        .map(|(contractor, agg)| SingleRegionContractorRow {
+            contractor,
+            regions: agg.regions.keys().cloned().collect::<Vec<_>>().join("/"),
+            num_regions: agg.regions.len(),
+            num_projects: agg.projects,
+            total_cost: money(agg.total_cost, cfg),
+            total_savings: money(agg.total_savings, cfg),
+        })
+        .collect()
+}
+
+/// Find clusters of contractor names that are probably the same firm.
+///
+/// Two names are linked when their edit distance is at most
+/// `max_distance`, using optimal string alignment (Levenshtein plus
+/// adjacent transpositions, so "ABC Corp" / "ABC Crop" is distance 1).
+/// Linked names are merged transitively into clusters; only clusters with
+/// two or more names are returned.
+///
+/// Clusters are numbered from 1 in order of their alphabetically first
+/// member, and names within a cluster are listed alphabetically. Each row
+/// also lists the provinces and funding years the name appears in, which
+/// helps a reviewer judge whether two spellings really are one firm.
+pub fn generate_contractor_merge_candidates(
+    data: &[CleanRecord],
+    max_distance: usize,
+) -> Vec<MergeCandidateRow> {
+    // Spellings are compared as written, so joint ventures stay whole.
+    let aggs = aggregate_contractors(data, JvSplit::Single);
+    let mut names: Vec<&str> = aggs.keys().map(|k| k.as_str()).collect();
+    names.sort_unstable();
+
+    // Union-find over name indices.
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..names.len()).collect();
+
+    // Compare names in length order so we can stop as soon as the length
+    // gap alone exceeds `max_distance`.
+    let lens: Vec<usize> = names.iter().map(|n| n.chars().count()).collect();
+    let mut by_len: Vec<usize> = (0..names.len()).collect();
+    by_len.sort_by_key(|&i| lens[i]);
+    for (pos, &i) in by_len.iter().enumerate() {
+        for &j in &by_len[pos + 1..] {
+            if lens[j] - lens[i] > max_distance {
+                break;
+            }
+            if strsim::osa_distance(names[i], names[j]) <= max_distance {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                if a != b {
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..names.len() {
+        let root = find(&mut parent, i);
+        clusters.entry(root).or_default().push(i);
+    }
+    clusters
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .enumerate()
+        .flat_map(|(idx, members)| {
+            let names = &names;
+            let aggs = &aggs;
+            members.into_iter().map(move |i| {
+                let agg = &aggs[names[i]];
+                MergeCandidateRow {
+                    cluster: idx + 1,
+                    contractor: names[i].to_string(),
+                    num_projects: agg.projects,
+                    provinces: agg.provinces.keys().cloned().collect::<Vec<_>>().join("; "),
+                    funding_years: agg
+                        .years
+                        .keys()
+                        .map(|y| y.to_string())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Per-year weighted average of savings across all types of work:
+/// (sum of savings) / (project count) for each funding year, ascending.
+pub fn year_weighted_averages(
+    data: &[CleanRecord],
+    rounding: RoundingMode,
+) -> Vec<YearWeightedAvg> {
+    weighted_savings_by_year(data)
+        .into_iter()
+        .map(|(year, (avg, count))| YearWeightedAvg {
+            funding_year: year,
+            total_projects: count,
+            weighted_avg_savings: format!("{:.2}", round_currency(avg, 2, rounding)),
+        })
+        .collect()
+}
+
+/// Numeric form of `year_weighted_averages`: year -> (average, count).
+fn weighted_savings_by_year(data: &[CleanRecord]) -> BTreeMap<i32, (f64, usize)> {
+    let mut by_year: BTreeMap<i32, (f64, usize)> = BTreeMap::new();
+    for r in data {
+        let e = by_year.entry(r.funding_year).or_insert((0.0, 0));
+        e.0 += r.cost_savings;
+        e.1 += 1;
+    }
+    for (sum, count) in by_year.values_mut() {
+        *sum = safe_ratio(*sum, *count as f64);
+    }
+    by_year
+}
+
+/// The rows of a report that differ from a previous run of it, matched on
+/// the `keys` columns (e.g. Region and MainIsland for Report 1).
+///
+/// The result has the current report's columns after two more:
+/// `Change` (`new`, `changed` or `removed`) and `ChangedColumns` (the
+/// `;`-separated columns whose value differs, for `changed` rows). A
+/// column the previous file lacks counts as changed. Current rows come
+/// first in their own order, then removed rows with their previous values
+/// in the previous file's order. Unchanged rows are left out.
+pub fn report_changes(
+    previous: (&StringRecord, &[StringRecord]),
+    current: (&StringRecord, &[StringRecord]),
+    keys: &[&str],
+) -> (StringRecord, Vec<StringRecord>) {
+    let (prev_header, prev_rows) = previous;
+    let (header, rows) = current;
+    // Each column of the current report, as an index into a previous row.
+    let prev_index: Vec<Option<usize>> = header
+        .iter()
+        .map(|name| prev_header.iter().position(|h| h == name))
+        .collect();
+    let key_of = |row: &StringRecord, hdr: &StringRecord| -> Vec<String> {
+        keys.iter()
+            .map(|k| {
+                hdr.iter()
+                    .position(|h| h == *k)
+                    .and_then(|i| row.get(i))
+                    .unwrap_or("")
+                    .to_string()
+            })
+            .collect()
+    };
+    let mut unmatched: HashMap<Vec<String>, usize> = prev_rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (key_of(row, prev_header), i))
+        .collect();
+
+    let mut out_header = StringRecord::from(vec!["Change", "ChangedColumns"]);
+    out_header.extend(header.iter());
+    let tagged = |change: &str, changed: &str, values: Vec<&str>| {
+        let mut record = StringRecord::from(vec![change, changed]);
+        record.extend(values);
+        record
+    };
+    let mut out = Vec::new();
+    for row in rows {
+        let Some(p) = unmatched.remove(&key_of(row, header)) else {
+            out.push(tagged("new", "", row.iter().collect()));
+            continue;
+        };
+        let changed: Vec<&str> = header
+            .iter()
+            .zip(row.iter())
+            .zip(&prev_index)
+            .filter(|((_, value), idx)| idx.and_then(|i| prev_rows[p].get(i)) != Some(*value))
+            .map(|((name, _), _)| name)
+            .collect();
+        if !changed.is_empty() {
+            out.push(tagged("changed", &changed.join(";"), row.iter().collect()));
+        }
+    }
+    let mut removed: Vec<usize> = unmatched.into_values().collect();
+    removed.sort_unstable();
+    for p in removed {
+        let values = prev_index
+            .iter()
+            .map(|idx| idx.and_then(|i| prev_rows[p].get(i)).unwrap_or(""))
+            .collect();
+        out.push(tagged("removed", "", values));
+    }
+    (out_header, out)
+}
+
+/// Compare two periods' Report 1 outputs and track each region's rank.
+///
+/// Both inputs must already be sorted the way `generate_report1` returns
+/// them (EfficiencyScore descending), so a region's rank is its 1-based
+/// position. Regions are joined by name:
+/// - present in both: `RankChange` is `up N`, `down N`, or `unchanged`,
+/// - only in `current`: `new`,
+/// - only in `previous`: `dropped`.
+///
+/// Output follows the current period's order, with dropped regions last.
+pub fn compare_report1_ranks(
+    previous: &[RegionSummaryRow],
+    current: &[RegionSummaryRow],
+) -> Vec<RegionRankChangeRow> {
+    let prev_rank: HashMap<&str, (usize, &RegionSummaryRow)> = previous
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| (row.region.as_str(), (idx + 1, row)))
+        .collect();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut rows: Vec<RegionRankChangeRow> = Vec::new();
+    for (idx, row) in current.iter().enumerate() {
+        let rank = idx + 1;
+        seen.insert(row.region.as_str());
+        let (previous_rank, previous_score, rank_change) = match prev_rank.get(row.region.as_str())
+        {
+            Some((prev, prev_row)) => {
+                let change = match (*prev as i64) - (rank as i64) {
+                    0 => "unchanged".to_string(),
+                    d if d > 0 => format!("up {}", d),
+                    d => format!("down {}", -d),
+                };
+                (prev.to_string(), prev_row.efficiency_score.clone(), change)
+            }
+            None => ("-".to_string(), "-".to_string(), "new".to_string()),
+        };
+        rows.push(RegionRankChangeRow {
+            region: row.region.clone(),
+            previous_rank,
+            current_rank: rank.to_string(),
+            rank_change,
+            previous_score,
+            current_score: row.efficiency_score.clone(),
+        });
+    }
+    for (idx, row) in previous.iter().enumerate() {
+        if !seen.contains(row.region.as_str()) {
+            rows.push(RegionRankChangeRow {
+                region: row.region.clone(),
+                previous_rank: (idx + 1).to_string(),
+                current_rank: "-".to_string(),
+                rank_change: "dropped".to_string(),
+                previous_score: row.efficiency_score.clone(),
+                current_score: "-".to_string(),
+            });
+        }
+    }
+    rows
+}
+
+/// The records `report` (1, 2 or 3) should be built from under
+/// `cfg.placeholder_groups`. Only the fields that report groups by are
+/// considered: Region and MainIsland for Report 1, Contractor for Report 2
+/// and TypeOfWork for Report 3.
+///
+/// `Keep` borrows `data` unchanged. `Exclude` drops records whose grouping
+/// field equals its placeholder, and `Suffix` relabels those values as e.g.
+/// `Unknown (imputed)`.
+pub fn apply_placeholder_groups<'a>(
+    data: &'a [CleanRecord],
+    placeholders: &Placeholders,
+    cfg: &ReportConfig,
+    report: usize,
+) -> Cow<'a, [CleanRecord]> {
+    // Report 1 only drops records whose *region* is a placeholder; an
+    // unknown island alone still leaves a usable region group.
+    let is_placeholder = |r: &CleanRecord| match report {
+        1 => r.region == placeholders.region,
+        2 => r.contractor == placeholders.contractor,
+        3 => r.type_of_work == placeholders.type_of_work,
+        _ => false,
+    };
+    let mark = |value: &mut String, placeholder: &str| {
+        if value == placeholder {
+            value.push_str(" (imputed)");
+        }
+    };
+    match cfg.placeholder_groups {
+        PlaceholderGroups::Keep => Cow::Borrowed(data),
+        PlaceholderGroups::Exclude => Cow::Owned(
+            data.iter()
+                .filter(|r| !is_placeholder(r))
+                .cloned()
+                .collect(),
+        ),
+        PlaceholderGroups::Suffix => Cow::Owned(
+            data.iter()
+                .cloned()
+                .map(|mut r| {
+                    match report {
+                        1 => {
+                            mark(&mut r.region, &placeholders.region);
+                            mark(&mut r.main_island, &placeholders.main_island);
+                        }
+                        2 => mark(&mut r.contractor, &placeholders.contractor),
+                        3 => mark(&mut r.type_of_work, &placeholders.type_of_work),
+                        _ => {}
+                    }
+                    r
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Compare report row counts against `--expect` values. `expected` maps a
+/// report number (1-3) to its expected row count and `actual` holds the
+/// row counts of Reports 1-3. Returns one message per report that differs;
+/// empty means every expectation held.
+pub fn check_row_counts(expected: &BTreeMap<usize, usize>, actual: [usize; 3]) -> Vec<String> {
+    expected
+        .iter()
+        .filter_map(|(&report, &want)| {
+            let got = actual[report - 1];
+            (got != want).then(|| {
+                format!(
+                    "Report {} has {} rows, expected {} (--expect report{}={})",
+                    report, got, want, report, want
+                )
+            })
+        })
+        .collect()
+}
+
+/// Indices of Report 1 rows whose EfficiencyScore is more than one
+/// standard deviation from the mean (`--preview-significant`).
+pub fn significant_report1_rows(rows: &[RegionSummaryRow]) -> Vec<usize> {
+    beyond_one_std_dev(
+        rows.iter()
+            .map(|r| parse_f64_safe(Some(&r.efficiency_score))),
+    )
+}
+
+/// Indices of Report 2 rows flagged `High Risk` (`--preview-significant`).
+pub fn significant_report2_rows(rows: &[ContractorRankingRow]) -> Vec<usize> {
+    rows.iter()
+        .enumerate()
+        .filter(|(_, r)| r.risk_flag == "High Risk")
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Indices of Report 3 rows whose YoYChange is more than one standard
+/// deviation from the mean (`--preview-significant`). `N/A` rows never
+/// qualify.
+pub fn significant_report3_rows(rows: &[TypeTrendRow]) -> Vec<usize> {
+    beyond_one_std_dev(rows.iter().map(|r| parse_f64_safe(Some(&r.yoy_change))))
+}
+
+/// Positions of the values more than one population standard deviation
+/// from the mean of the present (`Some`) values.
+fn beyond_one_std_dev(values: impl Iterator<Item = Option<f64>>) -> Vec<usize> {
+    let values: Vec<Option<f64>> = values.collect();
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    let (mean, sd) = (average(&present), std_dev(&present));
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.is_some_and(|v| (v - mean).abs() > sd))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Check that every numeric string cell of the reports parses back to an
+/// `f64` after stripping commas, the way `main`'s preview formatting reads
+/// them. `N/A` is the one accepted non-number. Returns one message per
+/// offending cell; empty means all good.
+///
+/// A malformed cell would otherwise pass through the previews unformatted
+/// without any error, so this guards the generators' formatting.
+pub fn check_numeric_cells(
+    r1: &[RegionSummaryRow],
+    r2: &[ContractorRankingRow],
+    r3: &[TypeTrendRow],
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut check = |report: &str, row: &str, cells: &[(&str, &str)]| {
+        for (column, cell) in cells {
+            if *cell != "N/A" && cell.replace(',', "").parse::<f64>().is_err() {
+                problems.push(format!(
+                    "{} {}: {} = {:?} is not a number",
+                    report, row, column, cell
+                ));
+            }
+        }
+    };
+    for r in r1 {
+        check(
+            "Report 1",
+            &format!("{} ({})", r.region, r.main_island),
+            &[
+                ("TotalBudget", &r.total_budget),
+                ("MedianSavings", &r.median_savings),
+                ("AvgDelay", &r.avg_delay),
+                ("HighDelayPct", &r.high_delay_pct),
+                ("EfficiencyScore", &r.efficiency_score),
+                ("BudgetSharePct", &r.budget_share_pct),
+                ("IQR", &r.iqr),
+                ("PercentileRank", &r.percentile_rank),
+            ],
+        );
+        if let Some(raw) = &r.raw_efficiency {
+            check(
+                "Report 1",
+                &format!("{} ({})", r.region, r.main_island),
+                &[("RawEfficiency", raw)],
+            );
+        }
+    }
+    for r in r2 {
+        check(
+            "Report 2",
+            &r.contractor,
+            &[
+                ("TotalCost", &r.total_cost),
+                ("AvgDelay", &r.avg_delay),
+                ("TotalSavings", &r.total_savings),
+                ("ReliabilityIndex", &r.reliability_index),
+                ("SavingsPerDelayDay", &r.savings_per_delay_day),
+                ("CostSharePct", &r.cost_share_pct),
+                ("BudgetUtilizationPct", &r.budget_utilization_pct),
+            ],
+        );
+    }
+    for r in r3 {
+        let mut cells = vec![
+            ("AvgSavings", r.avg_savings.as_str()),
+            ("OverrunRate", &r.overrun_rate),
+            ("YoYChange", &r.yoy_change),
+            ("ProjectCountYoY", &r.project_count_yoy),
+        ];
+        if let Some(smoothed) = &r.smoothed_avg_savings {
+            cells.push(("SmoothedAvgSavings", smoothed));
+        }
+        check(
+            "Report 3",
+            &format!("{} {}", r.funding_year, r.type_of_work),
+            &cells,
+        );
+    }
+    problems
+}
+
+/// Check that every percentage column of the reports (HighDelayPct,
+/// EfficiencyScore, BudgetSharePct, PercentileRank, CostSharePct and
+/// OverrunRate) parses and lies within [0, 100]. Returns one message per
+/// offending cell; empty means all good.
+///
+/// The generators are meant to guarantee this already, so a non-empty
+/// result points at a bug in their normalization rather than at the data.
+pub fn check_percentage_bounds(
+    r1: &[RegionSummaryRow],
+    r2: &[ContractorRankingRow],
+    r3: &[TypeTrendRow],
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut check =
+        |report: &str, row: String, column: &str, cell: &str| match parse_f64_safe(Some(cell)) {
+            Some(v) if (0.0..=100.0).contains(&v) => {}
+            _ => problems.push(format!(
+                "{} {}: {} = {} is outside [0, 100]",
+                report, row, column, cell
+            )),
+        };
+    for r in r1 {
+        let row = format!("{} ({})", r.region, r.main_island);
+        check("Report 1", row.clone(), "HighDelayPct", &r.high_delay_pct);
+        check(
+            "Report 1",
+            row.clone(),
+            "EfficiencyScore",
+            &r.efficiency_score,
+        );
+        check(
+            "Report 1",
+            row.clone(),
+            "BudgetSharePct",
+            &r.budget_share_pct,
+        );
+        check("Report 1", row, "PercentileRank", &r.percentile_rank);
+    }
+    for r in r2 {
+        check(
+            "Report 2",
+            r.contractor.clone(),
+            "CostSharePct",
+            &r.cost_share_pct,
+        );
+    }
+    for r in r3 {
+        let row = format!("{} {}", r.funding_year, r.type_of_work);
+        check("Report 3", row, "OverrunRate", &r.overrun_rate);
+    }
+    problems
+}
+
+/// The column headers of Report `report` (1, 2 or 3), or `None` for any
+/// other number. Used to validate `--sort` before any data is loaded.
+pub fn report_columns(report: usize) -> Option<Vec<String>> {
+    let headers = match report {
+        1 => RegionSummaryRow::headers(),
+        2 => ContractorRankingRow::headers(),
+        3 => TypeTrendRow::headers(),
+        _ => return None,
+    };
+    Some(headers.into_iter().map(|h| h.into_owned()).collect())
+}
+
+/// `--sort-report1` keys for Report 1's numeric columns, paired with the
+/// column header each one sorts by.
+pub const REPORT1_SORT_KEYS: [(&str, &str); 8] = [
+    ("total_budget", "TotalBudget"),
+    ("median_savings", "MedianSavings"),
+    ("avg_delay", "AvgDelay"),
+    ("high_delay_pct", "HighDelayPct"),
+    ("efficiency_score", "EfficiencyScore"),
+    ("budget_share_pct", "BudgetSharePct"),
+    ("iqr", "IQR"),
+    ("percentile_rank", "PercentileRank"),
+];
+
+/// Stable-sort report `rows` by the column headed `column`.
+///
+/// Cells are the formatted strings the report writes, so they are parsed
+/// back: numeric cells compare as numbers, and non-numeric cells (e.g.
+/// `N/A`) compare as text and always sort after the numbers, in either
+/// direction. Errors, leaving `rows` untouched, when the report has no such
+/// column.
+pub fn sort_by_column<T: Tabled>(
+    rows: &mut Vec<T>,
+    column: &str,
+    descending: bool,
+) -> Result<(), String> {
+    let headers = T::headers();
+    let idx = headers.iter().position(|h| h == column).ok_or_else(|| {
+        format!(
+            "Unknown column {} (expected one of: {})",
+            column,
+            headers.join(", ")
+        )
+    })?;
+    let mut keyed: Vec<(Option<f64>, String, T)> = std::mem::take(rows)
+        .into_iter()
+        .map(|row| {
+            let cell = row.fields()[idx].to_string();
+            (parse_f64_safe(Some(&cell)), cell, row)
+        })
+        .collect();
+    let order = |o: Ordering| if descending { o.reverse() } else { o };
+    keyed.sort_by(|a, b| match (a.0, b.0) {
+        (Some(x), Some(y)) => order(x.total_cmp(&y)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => order(a.1.cmp(&b.1)),
+    });
+    rows.extend(keyed.into_iter().map(|(_, _, row)| row));
+    Ok(())
+}
+
+/// Describe how Report 1's derived columns are computed with `cfg`.
+///
+/// Used by `--explain`; the text is built from the live parameters so it
+/// never drifts from what the generator actually does.
+pub fn explain_report1(cfg: &ReportConfig) -> String {
+    let raw = match cfg.efficiency_formula {
+        EfficiencyFormula::ZeroWhenNoDelay => {
+            "MedianSavings / AvgDelay (0 when AvgDelay <= 0 or the result is negative)"
+        }
+        EfficiencyFormula::PlusOneDay => {
+            "MedianSavings / (max(AvgDelay, 0) + 1) (0 when the result is negative)"
+        }
+    };
+    // The score is scaled from RawEfficiency itself unless a multiplier
+    // applies, in which case from the weighted value.
+    let mut weight = String::new();
+    if cfg.weighted_efficiency {
+        weight.push_str(" * BudgetSharePct / 100");
+    }
+    if cfg.completeness_weighted {
+        weight.push_str(" * share of projects with no imputed coordinates or completion date");
+    }
+    let (scaled, weighted) = if weight.is_empty() {
+        ("RawEfficiency", String::new())
+    } else {
+        (
+            "WeightedEfficiency",
+            format!("- WeightedEfficiency = RawEfficiency{}\n", weight),
+        )
+    };
+    format!(
+        "How Report 1 is computed (per Region + MainIsland):\n\
+         - HighDelayPct = % of projects with CompletionDelay > {} days\n\
+         - RawEfficiency = {}\n\
+         {}\
+         - EfficiencyScore = ({scaled} - min) / (max - min) * 100 across all groups \
+         (0 when every group has the same {scaled})\n\
+         - BudgetSharePct = TotalBudget / national TotalBudget * 100\n\
+         - IQR = Q3 - Q1 of cost savings (linear interpolation; 0 for a single project)\n\
+         - PercentileRank = (average rank by EfficiencyScore - 1) / (groups - 1) * 100; \
+         equal scores share a rank\n{}",
+        cfg.high_delay_threshold_days,
+        raw,
+        weighted,
+        if cfg.raw_efficiency {
+            "- RawEfficiency column = the RawEfficiency above, before normalization\n"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Describe how Report 2's derived columns are computed with `cfg`.
+pub fn explain_report2(cfg: &ReportConfig) -> String {
+    let floor = match cfg.reliability_floor {
+        Some(f) => format!("floored at {}", f),
+        None => "no lower bound".to_string(),
+    };
+    let delay_term = if cfg.type_horizons.is_empty() {
+        format!("AvgDelay / {}", cfg.reliability_horizon_days)
+    } else {
+        format!(
+            "mean of delay / horizon of the project's type ({}; {} otherwise)",
+            cfg.type_horizons
+                .iter()
+                .map(|(t, h)| format!("{} = {}", t, h))
+                .collect::<Vec<_>>()
+                .join(", "),
+            cfg.reliability_horizon_days
+        )
+    };
+    format!(
+        "How Report 2 is computed (per Contractor):\n\
+         - Only contractors with >= {} projects; top {} by TotalCost\n\
+         - ReliabilityIndex = (1 - {}) * (TotalSavings / TotalCost) * 100, capped at {}, {}\n\
+         - RiskFlag = \"High Risk\" when ReliabilityIndex < {}, otherwise \"OK\"\n\
+         - SavingsPerDelayDay = TotalSavings / total delay days across projects \
+         (N/A when total delay <= 0)\n\
+         - CostSharePct = TotalCost / national ContractCost of all projects * 100\n\
+         - PrimaryProvince = province with the most projects (alphabetically first on a tie)\n\
+         - BudgetUtilizationPct = TotalCost / total ApprovedBudgetForContract * 100, \
+         clamped to [0, {}]\n\
+         - Joint ventures: {}\n",
+        cfg.min_contractor_projects,
+        cfg.top_contractors,
+        delay_term,
+        cfg.reliability_cap,
+        floor,
+        cfg.risk_threshold,
+        UTILIZATION_CAP_PCT,
+        match cfg.jv_split {
+            JvSplit::Single => "one contractor under the full name",
+            JvSplit::FullCredit => "each member firm credited with the whole project",
+            JvSplit::SplitEvenly => {
+                "each member firm credited with the project and an equal share of its money"
+            }
+        }
+    )
+}
+
+/// Describe how Report 3's derived columns are computed with `cfg`.
+pub fn explain_report3(cfg: &ReportConfig) -> String {
+    let baseline = match cfg.yoy_baseline {
+        YoyBaseline::PerType => format!(
+            "the same TypeOfWork's AvgSavings in {} (0 for {} rows or when the baseline is \
+             missing or zero)",
+            cfg.baseline_year, cfg.baseline_year
+        ),
+        YoyBaseline::GlobalPerYear => format!(
+            "the {} weighted average of savings across all types (0 when it is zero)",
+            cfg.baseline_year
+        ),
+    };
+    format!(
+        "How Report 3 is computed (per FundingYear + TypeOfWork):\n\
+         - OverrunRate = % of projects with negative CostSavings\n\
+         - YoYChange = (AvgSavings - baseline) / |baseline| * 100, where baseline is {}\n\
+         - ProjectCountYoY = % change in TotalProjects from the type's previous FundingYear \
+         (N/A when that year has none)\n{}{}{}",
+        baseline,
+        match cfg.count_drop_pct {
+            Some(pct) => format!(
+                "- CountCollapse = Yes when ProjectCountYoY is below -{}\n",
+                pct
+            ),
+            None => String::new(),
+        },
+        if cfg.smooth_savings {
+            "- SmoothedAvgSavings = mean of the type's AvgSavings in the previous, same and \
+             next year (two years at either end; N/A unless the type appears every year)\n"
+        } else {
+            ""
+        },
+        if cfg.dense_report3 {
+            "- Pairs without projects are listed with TotalProjects 0 and AvgSavings, \
+             OverrunRate and YoYChange of 0.00\n"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Generate high-level summary statistics over all cleaned records. Peso
+/// amounts are rounded per `rounding`.
 pub fn generate_summary(
     data: &[CleanRecord],
     contractors: &[ContractorRankingRow],
+    today: NaiveDate,
+    rounding: RoundingMode,
 ) -> SummaryStats {
     let total_projects = data.len();
     let total_contractors = contractors.len();
@@ -338,18 +1675,1283 @@ pub fn generate_summary(
     let avg_global_delay = average(
         &data
             .iter()
+            .filter(|r| !r.delay_excluded)
             .map(|r| r.completion_delay_days)
             .collect::<Vec<_>>(),
     );
     let total_savings: f64 = data.iter().map(|r| r.cost_savings).sum();
+    let mut cost_by_contractor: HashMap<&str, f64> = HashMap::new();
+    for r in data {
+        *cost_by_contractor.entry(r.contractor.as_str()).or_default() += r.contract_cost;
+    }
+    let contractor_costs: Vec<f64> = cost_by_contractor.into_values().collect();
+    let mut savings_by_island: BTreeMap<&str, f64> = BTreeMap::new();
+    for r in data {
+        *savings_by_island.entry(r.main_island.as_str()).or_default() += r.cost_savings;
+    }
+    // Imputed completion dates are just start dates, so they would make
+    // the dataset look older (or newer) than it is.
+    let completion_span = date_span(
+        data.iter()
+            .filter(|r| !r.completion_date_imputed)
+            .map(|r| r.completion_date),
+    );
     SummaryStats {
         total_projects,
         total_contractors,
         total_provinces: provinces.len(),
         global_avg_delay_days: format_number(avg_global_delay, 2),
-        total_savings: format!("{:.2}", total_savings),
-        report1_regions: 0,      // filled by caller if needed
-        report2_contractors: 0,  // filled by caller if needed
-        report3_entries: 0,      // filled by caller if needed
+        total_savings: format!("{:.2}", round_currency(total_savings, 2, rounding)),
+        year_weighted_avg_savings: year_weighted_averages(data, rounding),
+        island_savings: savings_by_island
+            .into_iter()
+            .map(|(island, savings)| IslandSavings {
+                main_island: island.to_string(),
+                total_savings: format!("{:.2}", round_currency(savings, 2, rounding)),
+            })
+            .collect(),
+        contractor_cost_gini: format!("{:.4}", gini(&contractor_costs)),
+        earliest_completion_date: completion_span.map(|(lo, _)| lo.format("%Y-%m-%d").to_string()),
+        latest_completion_date: completion_span.map(|(_, hi)| hi.format("%Y-%m-%d").to_string()),
+        days_since_latest_completion: completion_span.map(|(_, hi)| days_diff(hi, today) as i64),
+        input_sha256: None,         // filled by caller if needed
+        exact_budget_matches: None, // filled by caller if needed
+        report1_regions: 0,         // filled by caller if needed
+        report2_contractors: 0,     // filled by caller if needed
+        report3_entries: 0,         // filled by caller if needed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CoordSource;
+
+    /// `n` identical projects of `contractor` in Region I.
+    fn projects(
+        contractor: &str,
+        n: usize,
+        budget: f64,
+        cost: f64,
+        delay: f64,
+    ) -> Vec<CleanRecord> {
+        (0..n)
+            .map(|_| CleanRecord::sample("Region I", contractor, budget, cost, delay))
+            .collect()
+    }
+
+    /// Three projects in `region`, each saving `savings` of a 1,000 budget
+    /// and delayed `delay` days.
+    fn region_projects(region: &str, savings: f64, delay: f64) -> Vec<CleanRecord> {
+        (0..3)
+            .map(|_| CleanRecord::sample(region, "ABC CORP", 1000.0, 1000.0 - savings, delay))
+            .collect()
+    }
+
+    #[test]
+    fn region_climbing_the_ranking_reports_its_rank_delta() {
+        let cfg = ReportConfig::default();
+        let previous: Vec<CleanRecord> = [
+            region_projects("Region A", 100.0, 10.0),
+            region_projects("Region B", 100.0, 50.0),
+            region_projects("Region C", 100.0, 20.0),
+        ]
+        .concat();
+        let current: Vec<CleanRecord> = [
+            region_projects("Region A", 100.0, 10.0),
+            region_projects("Region B", 100.0, 5.0),
+            region_projects("Region C", 100.0, 20.0),
+        ]
+        .concat();
+        let changes = compare_report1_ranks(
+            &generate_report1(&previous, &cfg),
+            &generate_report1(&current, &cfg),
+        );
+        let change = |region: &str| {
+            let row = changes.iter().find(|r| r.region == region).unwrap();
+            (
+                row.previous_rank.as_str(),
+                row.current_rank.as_str(),
+                row.rank_change.as_str(),
+            )
+        };
+        assert_eq!(change("Region B"), ("3", "1", "up 2"));
+        assert_eq!(change("Region A"), ("1", "2", "down 1"));
+        assert_eq!(change("Region C"), ("2", "3", "down 1"));
+    }
+
+    #[test]
+    fn reliability_floor_clamps_negative_index() {
+        // Delay 180 days over a 90-day horizon, savings 10% of cost:
+        // (1 - 180/90) * 10 = -10.
+        let data = projects("ABC CORP", 5, 1100.0, 1000.0, 180.0);
+        let unbounded = generate_report2(&data, &ReportConfig::default());
+        assert_eq!(unbounded[0].reliability_index, "-10.00");
+        assert_eq!(unbounded[0].risk_flag, "High Risk");
+
+        let cfg = ReportConfig {
+            reliability_floor: Some(0.0),
+            ..ReportConfig::default()
+        };
+        let floored = generate_report2(&data, &cfg);
+        assert_eq!(floored[0].reliability_index, "0.00");
+        assert_eq!(floored[0].risk_flag, "High Risk");
+    }
+
+    #[test]
+    fn explanations_reflect_the_current_parameters() {
+        let defaults = ReportConfig::default();
+        assert!(explain_report1(&defaults)
+            .contains("HighDelayPct = % of projects with CompletionDelay > 30 days"));
+        assert!(explain_report2(&defaults).contains("(1 - AvgDelay / 90) *"));
+        assert!(explain_report3(&defaults).contains("AvgSavings in 2021"));
+
+        let cfg = ReportConfig {
+            high_delay_threshold_days: 45.0,
+            reliability_horizon_days: 120.0,
+            risk_threshold: 60.0,
+            reliability_floor: Some(-10.0),
+            baseline_year: 2022,
+            weighted_efficiency: true,
+            ..ReportConfig::default()
+        };
+        let r1 = explain_report1(&cfg);
+        assert!(r1.contains("CompletionDelay > 45 days"), "{}", r1);
+        assert!(
+            r1.contains("WeightedEfficiency = RawEfficiency * BudgetSharePct / 100"),
+            "{}",
+            r1
+        );
+        assert!(r1.contains("(WeightedEfficiency - min)"), "{}", r1);
+        let r2 = explain_report2(&cfg);
+        assert!(r2.contains("(1 - AvgDelay / 120) *"), "{}", r2);
+        assert!(r2.contains("floored at -10"), "{}", r2);
+        assert!(r2.contains("ReliabilityIndex < 60"), "{}", r2);
+        let r3 = explain_report3(&cfg);
+        assert!(r3.contains("AvgSavings in 2022 (0 for 2022 rows"), "{}", r3);
+    }
+
+    #[test]
+    fn zero_delay_regions_score_per_efficiency_formula() {
+        let data: Vec<CleanRecord> = [
+            region_projects("Region A", 100.0, 0.0),
+            region_projects("Region B", 100.0, 10.0),
+        ]
+        .concat();
+        let score = |rows: &[RegionSummaryRow], region: &str| {
+            let row = rows.iter().find(|r| r.region == region).unwrap();
+            (
+                row.efficiency_score.clone(),
+                row.raw_efficiency.clone().unwrap(),
+            )
+        };
+        let cfg = ReportConfig {
+            raw_efficiency: true,
+            ..ReportConfig::default()
+        };
+        let default = generate_report1(&data, &cfg);
+        assert_eq!(score(&default, "Region A"), ("0.00".into(), "0.00".into()));
+        assert_eq!(
+            score(&default, "Region B"),
+            ("100.00".into(), "10.00".into())
+        );
+
+        let cfg = ReportConfig {
+            efficiency_formula: EfficiencyFormula::PlusOneDay,
+            ..cfg
+        };
+        let plus_one = generate_report1(&data, &cfg);
+        assert_eq!(
+            score(&plus_one, "Region A"),
+            ("100.00".into(), "100.00".into())
+        );
+        assert_eq!(score(&plus_one, "Region B"), ("0.00".into(), "9.09".into()));
+    }
+
+    #[test]
+    fn all_zero_delays_give_finite_scores() {
+        let data: Vec<CleanRecord> = [
+            region_projects("Region A", 100.0, 0.0),
+            region_projects("Region B", 50.0, 0.0),
+        ]
+        .concat();
+        for formula in [
+            EfficiencyFormula::ZeroWhenNoDelay,
+            EfficiencyFormula::PlusOneDay,
+        ] {
+            let cfg = ReportConfig {
+                efficiency_formula: formula,
+                ..ReportConfig::default()
+            };
+            for row in generate_report1(&data, &cfg) {
+                let score: f64 = row.efficiency_score.parse().unwrap();
+                assert!(score.is_finite(), "{:?}: {}", formula, row.efficiency_score);
+                assert_eq!(row.avg_delay, "0.00");
+            }
+        }
+    }
+
+    /// One project of `type_of_work` funded in `year` that saved `savings`.
+    fn typed_project(year: i32, type_of_work: &str, savings: f64) -> CleanRecord {
+        CleanRecord {
+            funding_year: year,
+            type_of_work: type_of_work.to_string(),
+            ..CleanRecord::sample("Region I", "ABC CORP", 1000.0, 1000.0 - savings, 10.0)
+        }
+    }
+
+    /// Report 3's YoYChange per (year, type).
+    fn yoy_changes(rows: &[TypeTrendRow]) -> Vec<(i32, &str, &str)> {
+        rows.iter()
+            .map(|r| {
+                (
+                    r.funding_year,
+                    r.type_of_work.as_str(),
+                    r.yoy_change.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn yoy_change_against_each_baseline() {
+        let data = vec![
+            typed_project(2021, "Dike", 100.0),
+            typed_project(2021, "Revetment", 300.0),
+            typed_project(2022, "Dike", 150.0),
+        ];
+        let per_type = generate_report3(&data, &ReportConfig::default());
+        assert_eq!(
+            yoy_changes(&per_type),
+            vec![
+                (2021, "Revetment", "0.00"),
+                (2021, "Dike", "0.00"),
+                (2022, "Dike", "50.00"),
+            ]
+        );
+        // The 2021 average over all types is (100 + 300) / 2 = 200.
+        let cfg = ReportConfig {
+            yoy_baseline: YoyBaseline::GlobalPerYear,
+            ..ReportConfig::default()
+        };
+        let global = generate_report3(&data, &cfg);
+        assert_eq!(
+            yoy_changes(&global),
+            vec![
+                (2021, "Revetment", "50.00"),
+                (2021, "Dike", "-50.00"),
+                (2022, "Dike", "-25.00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn transposed_letters_cluster_at_distance_one() {
+        let data: Vec<CleanRecord> = ["ABC Corp", "ABC Crop", "XYZ Builders"]
+            .iter()
+            .map(|name| CleanRecord::sample("Region I", name, 1000.0, 900.0, 10.0))
+            .collect();
+        let rows = generate_contractor_merge_candidates(&data, 1);
+        let clustered: Vec<(usize, &str)> = rows
+            .iter()
+            .map(|r| (r.cluster, r.contractor.as_str()))
+            .collect();
+        assert_eq!(clustered, vec![(1, "ABC Corp"), (1, "ABC Crop")]);
+        assert!(generate_contractor_merge_candidates(&data, 0).is_empty());
+    }
+
+    #[test]
+    fn report2_matches_the_shared_contractor_aggregate() {
+        let data: Vec<CleanRecord> = [
+            projects("ABC CORP", 5, 1000.0, 900.0, 30.0),
+            projects("XYZ INC", 6, 2000.0, 1900.0, 45.0),
+            projects("SMALL CO", 2, 1000.0, 900.0, 0.0),
+        ]
+        .concat();
+        let aggs = aggregate_contractors(&data, JvSplit::Single);
+        assert_eq!(aggs["ABC CORP"].projects, 5);
+        assert_eq!(aggs["XYZ INC"].total_cost, 11400.0);
+        assert_eq!(aggs["XYZ INC"].total_savings, 600.0);
+        assert_eq!(aggs["SMALL CO"].delays, vec![0.0, 0.0]);
+
+        let rows = generate_report2(&data, &ReportConfig::default());
+        let summary: Vec<(usize, &str, &str, usize, &str, &str, &str)> = rows
+            .iter()
+            .map(|r| {
+                (
+                    r.rank,
+                    r.contractor.as_str(),
+                    r.total_cost.as_str(),
+                    r.num_projects,
+                    r.avg_delay.as_str(),
+                    r.total_savings.as_str(),
+                    r.reliability_index.as_str(),
+                )
+            })
+            .collect();
+        // SMALL CO has fewer than 5 projects and is left out.
+        assert_eq!(
+            summary,
+            vec![
+                (1, "XYZ INC", "11400.00", 6, "45.00", "600.00", "2.63"),
+                (2, "ABC CORP", "4500.00", 5, "30.00", "500.00", "7.41"),
+            ]
+        );
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn summary_reports_completion_span_and_staleness() {
+        let completed = |on: NaiveDate, imputed: bool| CleanRecord {
+            completion_date: on,
+            completion_date_imputed: imputed,
+            ..CleanRecord::sample("Region I", "ABC CORP", 1000.0, 900.0, 10.0)
+        };
+        let data = vec![
+            completed(date(2022, 3, 15), false),
+            completed(date(2021, 7, 1), false),
+            completed(date(2023, 5, 31), false),
+            // An imputed date is only a start date; it is ignored.
+            completed(date(2024, 1, 1), true),
+        ];
+        let summary = generate_summary(&data, &[], date(2023, 6, 30), RoundingMode::default());
+        assert_eq!(
+            summary.earliest_completion_date.as_deref(),
+            Some("2021-07-01")
+        );
+        assert_eq!(
+            summary.latest_completion_date.as_deref(),
+            Some("2023-05-31")
+        );
+        assert_eq!(summary.days_since_latest_completion, Some(30));
+
+        let none = generate_summary(&data[3..], &[], date(2023, 6, 30), RoundingMode::default());
+        assert_eq!(none.latest_completion_date, None);
+        assert_eq!(none.days_since_latest_completion, None);
+    }
+
+    #[test]
+    fn budget_shares_sum_to_one_hundred() {
+        let data: Vec<CleanRecord> = [
+            ("Region I", 1_000_000.0),
+            ("Region II", 2_500_000.0),
+            ("Region III", 333_333.33),
+            ("NCR", 7_777_777.77),
+        ]
+        .iter()
+        .map(|(region, budget)| {
+            CleanRecord::sample(region, "ABC CORP", *budget, budget * 0.9, 10.0)
+        })
+        .collect();
+        let rows = generate_report1(&data, &ReportConfig::default());
+        let share = |region: &str| -> f64 {
+            let row = rows.iter().find(|r| r.region == region).unwrap();
+            row.budget_share_pct.parse().unwrap()
+        };
+        let total: f64 = rows
+            .iter()
+            .map(|r| r.budget_share_pct.parse::<f64>().unwrap())
+            .sum();
+        assert!((total - 100.0).abs() < 0.05, "{}", total);
+        assert_eq!(share("Region I"), 8.61);
+    }
+
+    #[test]
+    fn savings_per_delay_day_divides_by_total_delay() {
+        let data: Vec<CleanRecord> = [
+            projects("ABC CORP", 5, 1000.0, 900.0, 20.0),
+            projects("ON TIME INC", 5, 1000.0, 900.0, 0.0),
+        ]
+        .concat();
+        let rows = generate_report2(&data, &ReportConfig::default());
+        let metric = |contractor: &str| {
+            let row = rows.iter().find(|r| r.contractor == contractor).unwrap();
+            row.savings_per_delay_day.clone()
+        };
+        // 500 saved over 5 x 20 = 100 delay days.
+        assert_eq!(metric("ABC CORP"), "5.00");
+        assert_eq!(metric("ON TIME INC"), "N/A");
+    }
+
+    #[test]
+    fn savings_per_delay_day_ignores_excluded_long_projects() {
+        let mut data = projects("ABC CORP", 5, 1000.0, 900.0, 20.0);
+        data[0].completion_delay_days = 400.0;
+        data[0].delay_excluded = true;
+        let rows = generate_report2(&data, &ReportConfig::default());
+        // 500 saved over the 4 x 20 = 80 delay days that still count; the
+        // excluded project's delay must not inflate the denominator.
+        assert_eq!(rows[0].savings_per_delay_day, "6.25");
+    }
+
+    #[test]
+    fn unfinished_project_leaves_savings_per_delay_day_unchanged() {
+        let finished = projects("ABC CORP", 5, 1000.0, 900.0, 20.0);
+        let cfg = ReportConfig::default();
+        let before = generate_report2(&finished, &cfg);
+        // A Not Yet Started project under `--incomplete-status
+        // exclude-delay`: it saves nothing and its delay does not count.
+        let mut unfinished = CleanRecord::sample("Region I", "ABC CORP", 1000.0, 1000.0, 120.0);
+        unfinished.delay_excluded = true;
+        let after = generate_report2(&[finished, vec![unfinished]].concat(), &cfg);
+        assert_eq!(after[0].num_projects, 6);
+        assert_eq!(after[0].savings_per_delay_day, "5.00");
+        assert_eq!(
+            after[0].savings_per_delay_day,
+            before[0].savings_per_delay_day
+        );
+    }
+
+    #[test]
+    fn report1_sorts_by_any_column_in_either_direction() {
+        let data: Vec<CleanRecord> = [
+            ("Region A", [31.0, 31.0, 31.0]),
+            ("Region B", [0.0, 0.0, 100.0]),
+            ("Region C", [5.0, 5.0, 5.0]),
+        ]
+        .iter()
+        .flat_map(|(region, delays)| {
+            delays
+                .iter()
+                .map(|d| CleanRecord::sample(region, "ABC CORP", 1000.0, 900.0, *d))
+        })
+        .collect();
+        let mut rows = generate_report1(&data, &ReportConfig::default());
+        let order = |rows: &[RegionSummaryRow]| -> Vec<String> {
+            rows.iter().map(|r| r.region.clone()).collect()
+        };
+
+        sort_by_column(&mut rows, "AvgDelay", false).unwrap();
+        assert_eq!(order(&rows), ["Region C", "Region A", "Region B"]);
+
+        sort_by_column(&mut rows, "HighDelayPct", true).unwrap();
+        assert_eq!(order(&rows), ["Region A", "Region B", "Region C"]);
+
+        let err = sort_by_column(&mut rows, "Delay", false).unwrap_err();
+        assert!(err.starts_with("Unknown column Delay"), "{}", err);
+        assert_eq!(order(&rows), ["Region A", "Region B", "Region C"]);
+    }
+
+    #[test]
+    fn reliability_of_minus_500_is_kept_or_floored() {
+        // Savings are 50% of cost and the delay is 11 horizons:
+        // (1 - 990/90) * 50 = -500.
+        let data = projects("ABC CORP", 5, 1500.0, 1000.0, 990.0);
+        let unbounded = generate_report2(&data, &ReportConfig::default());
+        assert_eq!(unbounded[0].reliability_index, "-500.00");
+
+        let cfg = ReportConfig {
+            reliability_floor: Some(-100.0),
+            ..ReportConfig::default()
+        };
+        let floored = generate_report2(&data, &cfg);
+        assert_eq!(floored[0].reliability_index, "-100.00");
+        assert_eq!(floored[0].risk_flag, "High Risk");
+
+        // A punctual contractor is held at the configurable cap.
+        let punctual = projects("ABC CORP", 5, 3000.0, 1000.0, 0.0);
+        let cfg = ReportConfig {
+            reliability_cap: 80.0,
+            ..ReportConfig::default()
+        };
+        assert_eq!(
+            generate_report2(&punctual, &cfg)[0].reliability_index,
+            "80.00"
+        );
+        assert_eq!(
+            generate_report2(&punctual, &ReportConfig::default())[0].reliability_index,
+            "100.00"
+        );
+    }
+
+    #[test]
+    fn percentages_stay_in_bounds_under_extreme_data() {
+        let data: Vec<CleanRecord> = [
+            // Every project over budget and years late.
+            projects("OVERRUN CO", 5, 1000.0, 5000.0, 5000.0),
+            // Huge savings, early completion.
+            projects("EARLY INC", 5, 1e12, 1.0, -400.0),
+            // Nothing spent at all.
+            projects("ZERO LTD", 5, 0.0, 0.0, 0.0),
+            region_projects("Region Z", -1e9, 1e6),
+        ]
+        .concat();
+        let cfg = ReportConfig::default();
+        let mut r1 = generate_report1(&data, &cfg);
+        let mut r2 = generate_report2(&data, &cfg);
+        let mut r3 = generate_report3(&data, &cfg);
+        let problems = check_percentage_bounds(&r1, &r2, &r3);
+        assert!(problems.is_empty(), "{:?}", problems);
+
+        r1[0].high_delay_pct = "100.01".to_string();
+        r2[0].cost_share_pct = "-0.50".to_string();
+        r3[0].overrun_rate = "NaN".to_string();
+        let problems = check_percentage_bounds(&r1, &r2, &r3);
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].contains("HighDelayPct = 100.01 is outside [0, 100]"));
+        assert!(problems[1].contains("CostSharePct = -0.50"));
+        assert!(problems[2].contains("OverrunRate = NaN"));
+    }
+
+    #[test]
+    fn region_summary_for_present_and_absent_region() {
+        let data: Vec<CleanRecord> = [
+            region_projects("Region IV-A", 100.0, 10.0),
+            region_projects("NCR", 200.0, 40.0),
+        ]
+        .concat();
+        let cfg = ReportConfig::default();
+        let row = region_summary(&data, "  region iv-a ", &cfg).unwrap();
+        let full = generate_report1(&data, &cfg);
+        let expected = full.iter().find(|r| r.region == "Region IV-A").unwrap();
+        assert_eq!(row.region, "Region IV-A");
+        assert_eq!(row.total_budget, expected.total_budget);
+        assert_eq!(row.median_savings, expected.median_savings);
+        assert_eq!(row.avg_delay, expected.avg_delay);
+        assert_eq!(row.budget_share_pct, "50.00");
+        assert_eq!(row.efficiency_score, "N/A");
+        assert_eq!(row.percentile_rank, "N/A");
+
+        assert!(region_summary(&data, "Region XIII", &cfg).is_none());
+    }
+
+    #[test]
+    fn iqr_measures_the_middle_half_of_savings() {
+        let savings = [10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 10_000.0];
+        let mut data: Vec<CleanRecord> = savings
+            .iter()
+            .map(|s| CleanRecord::sample("Region I", "ABC CORP", 20_000.0, 20_000.0 - s, 10.0))
+            .collect();
+        data.push(CleanRecord::sample("NCR", "ABC CORP", 1000.0, 900.0, 10.0));
+        let rows = generate_report1(&data, &ReportConfig::default());
+        let iqr = |region: &str| {
+            let row = rows.iter().find(|r| r.region == region).unwrap();
+            row.iqr.clone()
+        };
+        // Q1 = 30 and Q3 = 70; the 10,000 outlier does not move them.
+        assert_eq!(iqr("Region I"), "40.00");
+        // One project has no spread.
+        assert_eq!(iqr("NCR"), "0.00");
+    }
+
+    #[test]
+    fn unknown_region_is_absent_from_report1_when_excluded() {
+        let data: Vec<CleanRecord> = [
+            region_projects("Region I", 100.0, 10.0),
+            region_projects("Unknown", 100.0, 10.0),
+        ]
+        .concat();
+        let placeholders = Placeholders::default();
+        let regions = |groups: PlaceholderGroups| -> Vec<String> {
+            let cfg = ReportConfig {
+                placeholder_groups: groups,
+                ..ReportConfig::default()
+            };
+            let scoped = apply_placeholder_groups(&data, &placeholders, &cfg, 1);
+            let mut regions: Vec<String> = generate_report1(&scoped, &cfg)
+                .into_iter()
+                .map(|r| r.region)
+                .collect();
+            regions.sort();
+            regions
+        };
+        assert_eq!(regions(PlaceholderGroups::Keep), ["Region I", "Unknown"]);
+        assert_eq!(regions(PlaceholderGroups::Exclude), ["Region I"]);
+        assert_eq!(
+            regions(PlaceholderGroups::Suffix),
+            ["Region I", "Unknown (imputed)"]
+        );
+    }
+
+    #[test]
+    fn smoothing_averages_neighbouring_years() {
+        let data = vec![
+            typed_project(2021, "Dike", 100.0),
+            typed_project(2022, "Dike", 200.0),
+            typed_project(2023, "Dike", 600.0),
+            typed_project(2022, "Seawall", 50.0),
+        ];
+        let cfg = ReportConfig {
+            smooth_savings: true,
+            ..ReportConfig::default()
+        };
+        let rows = generate_report3(&data, &cfg);
+        let smoothed = |year: i32, tow: &str| {
+            let row = rows
+                .iter()
+                .find(|r| r.funding_year == year && r.type_of_work == tow)
+                .unwrap();
+            row.smoothed_avg_savings.clone().unwrap()
+        };
+        // Edge years average over two years, the middle one over three.
+        assert_eq!(smoothed(2021, "Dike"), "150.00");
+        assert_eq!(smoothed(2022, "Dike"), "300.00");
+        assert_eq!(smoothed(2023, "Dike"), "400.00");
+        // Not present in every year: no comparable series.
+        assert_eq!(smoothed(2022, "Seawall"), "N/A");
+
+        let plain = generate_report3(&data, &ReportConfig::default());
+        assert!(plain.iter().all(|r| r.smoothed_avg_savings.is_none()));
+    }
+
+    #[test]
+    fn dense_report3_has_one_row_per_year_and_type() {
+        let data = vec![
+            typed_project(2021, "Dike", 100.0),
+            typed_project(2022, "Seawall", 50.0),
+            typed_project(2023, "Dike", 80.0),
+            typed_project(2023, "Drainage", 20.0),
+        ];
+        assert_eq!(generate_report3(&data, &ReportConfig::default()).len(), 4);
+
+        let cfg = ReportConfig {
+            dense_report3: true,
+            ..ReportConfig::default()
+        };
+        let rows = generate_report3(&data, &cfg);
+        let cells: BTreeSet<(i32, &str)> = rows
+            .iter()
+            .map(|r| (r.funding_year, r.type_of_work.as_str()))
+            .collect();
+        assert_eq!(rows.len(), 3 * 3);
+        assert_eq!(cells.len(), rows.len());
+        let empty = rows
+            .iter()
+            .find(|r| r.funding_year == 2021 && r.type_of_work == "Seawall")
+            .unwrap();
+        assert_eq!(empty.total_projects, 0);
+        assert_eq!(empty.avg_savings, "0.00");
+    }
+
+    #[test]
+    fn cost_share_is_against_the_national_total() {
+        let data: Vec<CleanRecord> = [
+            projects("BIG CORP", 5, 1000.0, 600.0, 10.0),
+            projects("SMALL INC", 5, 1000.0, 300.0, 10.0),
+            // Below the project minimum, but still national spend.
+            projects("TINY CO", 2, 1000.0, 500.0, 10.0),
+        ]
+        .concat();
+        let rows = generate_report2(&data, &ReportConfig::default());
+        let share = |contractor: &str| {
+            let row = rows.iter().find(|r| r.contractor == contractor).unwrap();
+            row.cost_share_pct.clone()
+        };
+        // 3,000 of 5,500 and 1,500 of 5,500.
+        assert_eq!(rows.len(), 2);
+        assert_eq!(share("BIG CORP"), "54.55");
+        assert_eq!(share("SMALL INC"), "27.27");
+    }
+
+    #[test]
+    fn reliability_stats_cover_every_qualifying_contractor() {
+        let contractors = [
+            ("ALPHA", 800.0),
+            ("BRAVO", 900.0),
+            ("CHARLIE", 1000.0),
+            ("DELTA", 1250.0),
+        ];
+        let mut data: Vec<CleanRecord> = contractors
+            .iter()
+            .flat_map(|&(name, cost)| {
+                (0..5).map(move |_| CleanRecord::sample("Region I", name, 1000.0, cost, 0.0))
+            })
+            .collect();
+        // Too few projects to qualify.
+        data.extend((0..2).map(|_| CleanRecord::sample("Region I", "ECHO", 1000.0, 100.0, 0.0)));
+        let cfg = ReportConfig {
+            top_contractors: 2,
+            ..ReportConfig::default()
+        };
+        let before = serde_json::to_string(&generate_report2(&data, &cfg)).unwrap();
+
+        let stats = reliability_stats(&data, &cfg).unwrap();
+        assert_eq!(stats.qualifying, 4);
+        assert!((stats.min - -20.0).abs() < 1e-9);
+        assert!((stats.max - 25.0).abs() < 1e-9);
+        assert!((stats.median - (0.0 + 100.0 / 9.0) / 2.0).abs() < 1e-9);
+        assert_eq!(stats.negative, 1);
+
+        let report = generate_report2(&data, &cfg);
+        assert_eq!(report.len(), 2);
+        assert_eq!(serde_json::to_string(&report).unwrap(), before);
+
+        let strict = ReportConfig {
+            min_contractor_projects: 10,
+            ..cfg
+        };
+        assert!(reliability_stats(&data, &strict).is_none());
+    }
+
+    #[test]
+    fn primary_province_is_the_modal_one_with_alphabetical_ties() {
+        let in_province = |contractor: &str, province: &str| CleanRecord {
+            province: province.to_string(),
+            ..CleanRecord::sample("Region I", contractor, 1000.0, 900.0, 10.0)
+        };
+        let data: Vec<CleanRecord> = [
+            "Pangasinan",
+            "Ilocos Norte",
+            "Pangasinan",
+            "La Union",
+            "Pangasinan",
+        ]
+        .iter()
+        .map(|p| in_province("ABC CORP", p))
+        .chain(
+            ["Zambales", "Bataan", "Bataan", "Zambales", "Aurora"]
+                .iter()
+                .map(|p| in_province("XYZ INC", p)),
+        )
+        .collect();
+        let rows = generate_report2(&data, &ReportConfig::default());
+        let province = |contractor: &str| {
+            let row = rows.iter().find(|r| r.contractor == contractor).unwrap();
+            row.primary_province.clone()
+        };
+        assert_eq!(province("ABC CORP"), "Pangasinan");
+        // Two projects each in Bataan and Zambales.
+        assert_eq!(province("XYZ INC"), "Bataan");
+    }
+
+    #[test]
+    fn joint_venture_single_entity_or_split_evenly() {
+        let jv = "ABC CORP / XYZ BUILDERS JV";
+        let data = vec![
+            CleanRecord::sample("Region I", jv, 1000.0, 800.0, 10.0),
+            CleanRecord::sample("Region I", "ABC CORP", 500.0, 400.0, 20.0),
+        ];
+        let totals = |split: JvSplit| -> Vec<(String, usize, f64, f64)> {
+            let mut rows: Vec<(String, usize, f64, f64)> = aggregate_contractors(&data, split)
+                .into_iter()
+                .map(|(name, agg)| (name, agg.projects, agg.total_cost, agg.total_savings))
+                .collect();
+            rows.sort_by(|a, b| a.0.cmp(&b.0));
+            rows
+        };
+        assert_eq!(
+            totals(JvSplit::Single),
+            vec![
+                ("ABC CORP".to_string(), 1, 400.0, 100.0),
+                (jv.to_string(), 1, 800.0, 200.0),
+            ]
+        );
+        assert_eq!(
+            totals(JvSplit::SplitEvenly),
+            vec![
+                ("ABC CORP".to_string(), 2, 800.0, 200.0),
+                ("XYZ BUILDERS".to_string(), 1, 400.0, 100.0),
+            ]
+        );
+        assert_eq!(
+            totals(JvSplit::FullCredit),
+            vec![
+                ("ABC CORP".to_string(), 2, 1200.0, 300.0),
+                ("XYZ BUILDERS".to_string(), 1, 800.0, 200.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn percentile_rank_puts_the_top_region_at_100_and_ties_together() {
+        // Raw efficiency 100 / delay: 10, 5, 5, 2.5 and 2.
+        let data: Vec<CleanRecord> = [
+            region_projects("Region A", 100.0, 10.0),
+            region_projects("Region B", 100.0, 20.0),
+            region_projects("Region C", 100.0, 20.0),
+            region_projects("Region D", 100.0, 40.0),
+            region_projects("Region E", 100.0, 50.0),
+        ]
+        .concat();
+        let rows = generate_report1(&data, &ReportConfig::default());
+        let rank = |region: &str| {
+            let row = rows.iter().find(|r| r.region == region).unwrap();
+            row.percentile_rank.clone()
+        };
+        assert_eq!(rank("Region A"), "100.00");
+        // B and C share the average of ranks 3 and 4 (of 5).
+        assert_eq!(rank("Region B"), "62.50");
+        assert_eq!(rank("Region C"), "62.50");
+        assert_eq!(rank("Region D"), "25.00");
+        assert_eq!(rank("Region E"), "0.00");
+    }
+
+    #[test]
+    fn row_count_expectations_match_or_report_the_drift() {
+        let expected = BTreeMap::from([(1, 17), (2, 15)]);
+        assert!(check_row_counts(&expected, [17, 15, 40]).is_empty());
+        assert_eq!(
+            check_row_counts(&expected, [16, 15, 40]),
+            vec!["Report 1 has 16 rows, expected 17 (--expect report1=17)"]
+        );
+        assert_eq!(check_row_counts(&expected, [17, 14, 0]).len(), 1);
+        assert!(check_row_counts(&BTreeMap::new(), [1, 2, 3]).is_empty());
+    }
+
+    #[test]
+    fn only_the_obvious_cost_outlier_is_flagged() {
+        let mut data: Vec<CleanRecord> = [100.0, 110.0, 95.0, 105.0, 98.0, 102.0, 5000.0, 101.0]
+            .iter()
+            .map(|cost| CleanRecord::sample("Region I", "ABC CORP", 6000.0, *cost, 10.0))
+            .collect();
+        data[6].contractor = "OUTLIER INC".to_string();
+        let rows = generate_cost_outliers(&data, 1.5, false);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].contractor, "OUTLIER INC");
+        assert_eq!(rows[0].contract_cost, "5000.00");
+        assert_eq!(rows[0].group, "All");
+
+        // A wide enough fence flags nothing.
+        assert!(generate_cost_outliers(&data, 1000.0, false).is_empty());
+    }
+
+    #[test]
+    fn island_savings_sum_to_total_savings() {
+        let on = |island: &str, budget: f64, cost: f64| CleanRecord {
+            main_island: island.to_string(),
+            ..CleanRecord::sample("Region I", "ABC CORP", budget, cost, 10.0)
+        };
+        let data = vec![
+            on("Luzon", 1_000_000.0, 900_000.25),
+            on("Luzon", 500_000.0, 499_999.5),
+            on("Visayas", 2_000_000.0, 1_234_567.89),
+            on("Mindanao", 750_000.0, 800_000.0),
+            on("Unknown", 300_000.0, 100_000.0),
+            on("Unknown", 200_000.0, 150_000.0),
+        ];
+        let summary = generate_summary(&data, &[], date(2023, 6, 30), RoundingMode::default());
+        let islands: Vec<&str> = summary
+            .island_savings
+            .iter()
+            .map(|i| i.main_island.as_str())
+            .collect();
+        assert_eq!(islands, ["Luzon", "Mindanao", "Unknown", "Visayas"]);
+        let unknown = &summary.island_savings[2];
+        assert_eq!(unknown.total_savings, "250000.00");
+
+        let sum: f64 = summary
+            .island_savings
+            .iter()
+            .map(|i| i.total_savings.parse::<f64>().unwrap())
+            .sum();
+        let total: f64 = summary.total_savings.parse().unwrap();
+        assert!((sum - total).abs() < 0.005, "{} vs {}", sum, total);
+    }
+
+    #[test]
+    fn clean_record_rows_have_the_same_order_on_every_run() {
+        let record =
+            |year: i32, region: &str, province: &str, contractor: &str, line: u64| CleanRecord {
+                funding_year: year,
+                province: province.to_string(),
+                source_line: line,
+                ..CleanRecord::sample(region, contractor, 1000.0, 900.0, 10.0)
+            };
+        let data = vec![
+            record(2023, "NCR", "Manila", "ABC CORP", 2),
+            record(2021, "Region I", "Ilocos Norte", "XYZ INC", 3),
+            record(2021, "Region I", "Ilocos Norte", "ABC CORP", 9),
+            record(2021, "Region I", "Ilocos Norte", "ABC CORP", 4),
+            record(2021, "NCR", "Manila", "XYZ INC", 5),
+            record(2022, "Region I", "Pangasinan", "ABC CORP", 6),
+        ];
+        let lines = |data: &[CleanRecord]| -> Vec<u64> {
+            clean_record_rows(data, false)
+                .iter()
+                .map(|row| row.source_line)
+                .collect()
+        };
+        // A second run sees the same records in a different order, as
+        // HashMap-based grouping may hand them over.
+        let mut second = data.clone();
+        second.reverse();
+        second.swap(1, 4);
+
+        assert_eq!(lines(&data), [5, 4, 9, 3, 6, 2]);
+        assert_eq!(lines(&data), lines(&second));
+        let ids: Vec<usize> = clean_record_rows(&second, false)
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn halved_project_count_trips_the_collapse_flag() {
+        let mut data = Vec::new();
+        for (year, dikes, seawalls) in [(2021, 4, 2), (2022, 2, 2), (2023, 2, 3)] {
+            data.extend((0..dikes).map(|_| typed_project(year, "Dike", 100.0)));
+            data.extend((0..seawalls).map(|_| typed_project(year, "Seawall", 100.0)));
+        }
+        let cfg = ReportConfig {
+            count_drop_pct: Some(40.0),
+            ..ReportConfig::default()
+        };
+        let rows = generate_report3(&data, &cfg);
+        let cell = |year: i32, type_of_work: &str| {
+            let row = rows
+                .iter()
+                .find(|r| r.funding_year == year && r.type_of_work == type_of_work)
+                .unwrap();
+            (
+                row.project_count_yoy.as_str(),
+                row.count_collapse.as_deref(),
+            )
+        };
+        assert_eq!(cell(2021, "Dike"), ("N/A", Some("No")));
+        assert_eq!(cell(2022, "Dike"), ("-50.00", Some("Yes")));
+        assert_eq!(cell(2023, "Dike"), ("0.00", Some("No")));
+        assert_eq!(cell(2023, "Seawall"), ("50.00", Some("No")));
+
+        // Without a threshold the column is still computed but not flagged.
+        let rows = generate_report3(&data, &ReportConfig::default());
+        assert!(rows.iter().all(|r| r.count_collapse.is_none()));
+        assert!(rows.iter().any(|r| r.project_count_yoy == "-50.00"));
+    }
+
+    #[test]
+    fn monotonically_worsening_contractor_is_listed() {
+        let delayed = |contractor: &str, year: i32, delay: f64| CleanRecord {
+            funding_year: year,
+            ..CleanRecord::sample("Region I", contractor, 1000.0, 900.0, delay)
+        };
+        let data = vec![
+            // Worse every year: 10 -> 20 (avg of 15, 25) -> 40.
+            delayed("SLOW BUILDERS", 2021, 10.0),
+            delayed("SLOW BUILDERS", 2022, 15.0),
+            delayed("SLOW BUILDERS", 2022, 25.0),
+            delayed("SLOW BUILDERS", 2023, 40.0),
+            // Worse, then better.
+            delayed("UP DOWN INC", 2021, 10.0),
+            delayed("UP DOWN INC", 2022, 30.0),
+            delayed("UP DOWN INC", 2023, 20.0),
+            // Worse each year it appears, but missing 2022.
+            delayed("GAP CORP", 2021, 10.0),
+            delayed("GAP CORP", 2023, 50.0),
+        ];
+        let rows = generate_worsening_contractors(&data);
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.contractor, "SLOW BUILDERS");
+        assert_eq!(
+            [
+                row.avg_delay_2021.as_str(),
+                row.avg_delay_2022.as_str(),
+                row.avg_delay_2023.as_str(),
+                row.delay_increase.as_str(),
+            ],
+            ["10.00", "20.00", "40.00", "30.00"]
+        );
+        assert_eq!(row.num_projects, 4);
+    }
+
+    #[test]
+    fn every_numeric_report_cell_parses_back() {
+        // Large amounts (thousands separators), overruns, zero delays and a
+        // type only present in 2021, under every optional column.
+        let data: Vec<CleanRecord> = (0..36)
+            .map(|i| CleanRecord {
+                funding_year: 2021 + (i % 3) as i32,
+                type_of_work: match i {
+                    0 => "Drainage",
+                    _ => ["Dike", "Seawall"][(i / 3) % 2],
+                }
+                .to_string(),
+                ..CleanRecord::sample(
+                    ["Region I", "NCR", "Region VII", "BARMM"][i % 4],
+                    ["ABC CORP", "XYZ INC", "LMN BUILDERS"][i % 3],
+                    1_234_567.0 * (1 + i) as f64,
+                    1_234_567.0 * (1 + i) as f64 * [0.8, 1.25, 1.0][i % 3],
+                    [0.0, 45.5, 400.0, 12.0][i % 4],
+                )
+            })
+            .collect();
+        let cfg = ReportConfig {
+            raw_efficiency: true,
+            smooth_savings: true,
+            dense_report3: true,
+            count_drop_pct: Some(25.0),
+            min_contractor_projects: 1,
+            ..ReportConfig::default()
+        };
+        let (r1, r2, r3) = (
+            generate_report1(&data, &cfg),
+            generate_report2(&data, &cfg),
+            generate_report3(&data, &cfg),
+        );
+        assert!(!r1.is_empty() && !r2.is_empty() && !r3.is_empty());
+        assert_eq!(check_numeric_cells(&r1, &r2, &r3), Vec::<String>::new());
+
+        let mut broken = r2.clone();
+        broken[0].total_cost = "1.234.567,00".to_string();
+        let problems = check_numeric_cells(&r1, &broken, &r3);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Report 2") && problems[0].contains("TotalCost"));
+    }
+
+    #[test]
+    fn budget_utilization_is_cost_over_budget_with_a_cap() {
+        let cfg = ReportConfig {
+            min_contractor_projects: 1,
+            ..ReportConfig::default()
+        };
+        let data = vec![
+            CleanRecord::sample("Region I", "UNDER CORP", 1000.0, 600.0, 10.0),
+            CleanRecord::sample("Region I", "UNDER CORP", 1000.0, 1200.0, 10.0),
+            CleanRecord::sample("NCR", "OVER INC", 1000.0, 1500.0, 10.0),
+            CleanRecord::sample("NCR", "RUNAWAY JV", 1000.0, 5000.0, 10.0),
+        ];
+        let rows = generate_report2(&data, &cfg);
+        let utilization = |name: &str| {
+            rows.iter()
+                .find(|r| r.contractor == name)
+                .map(|r| r.budget_utilization_pct.as_str())
+                .unwrap()
+        };
+        // Summed cost over summed budget, not an average of ratios.
+        assert_eq!(utilization("UNDER CORP"), "90.00");
+        assert_eq!(utilization("OVER INC"), "150.00");
+        assert_eq!(utilization("RUNAWAY JV"), "200.00");
+    }
+
+    #[test]
+    fn raw_efficiency_is_consistent_with_the_normalized_score() {
+        let data: Vec<CleanRecord> = [
+            region_projects("Region A", 100.0, 5.0),
+            region_projects("Region B", 300.0, 10.0),
+            region_projects("Region C", 50.0, 20.0),
+            region_projects("Region D", 400.0, 4.0),
+        ]
+        .concat();
+        let cfg = ReportConfig {
+            raw_efficiency: true,
+            ..ReportConfig::default()
+        };
+        let rows = generate_report1(&data, &cfg);
+        let num = |s: &str| s.replace(',', "").parse::<f64>().unwrap();
+        let raws: Vec<f64> = rows
+            .iter()
+            .map(|r| num(r.raw_efficiency.as_deref().unwrap()))
+            .collect();
+        let (min, max) = raws
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        for (row, raw) in rows.iter().zip(&raws) {
+            // Raw is MedianSavings / AvgDelay; the score min-max scales it.
+            assert!((raw - num(&row.median_savings) / num(&row.avg_delay)).abs() < 0.01);
+            let expected = (raw - min) / (max - min) * 100.0;
+            assert!(
+                (num(&row.efficiency_score) - expected).abs() < 0.01,
+                "{}: {} vs {}",
+                row.region,
+                row.efficiency_score,
+                expected
+            );
+        }
+        let json = serde_json::to_value(&rows[0]).unwrap();
+        assert!(json.get("EfficiencyScore").is_some() && json.get("RawEfficiency").is_some());
+
+        // Off by default: the column is left out, the score is unchanged.
+        let plain = generate_report1(&data, &ReportConfig::default());
+        assert!(plain.iter().all(|r| r.raw_efficiency.is_none()));
+        let scores = |rows: &[RegionSummaryRow]| -> Vec<String> {
+            rows.iter().map(|r| r.efficiency_score.clone()).collect()
+        };
+        assert_eq!(scores(&plain), scores(&rows));
+    }
+
+    #[test]
+    fn type_horizons_change_the_reliability_index() {
+        let project = |type_of_work: &str| CleanRecord {
+            type_of_work: type_of_work.to_string(),
+            ..CleanRecord::sample("Region I", "ABC CORP", 1000.0, 800.0, 90.0)
+        };
+        let data = vec![project("Dike"), project("Seawall")];
+        let index = |horizons: &[(&str, f64)]| {
+            let cfg = ReportConfig {
+                min_contractor_projects: 1,
+                type_horizons: horizons
+                    .iter()
+                    .map(|(t, days)| (t.to_string(), *days))
+                    .collect(),
+                ..ReportConfig::default()
+            };
+            generate_report2(&data, &cfg)[0].reliability_index.clone()
+        };
+        // Savings are 25% of cost; both 90-day delays use up the default
+        // 90-day horizon.
+        assert_eq!(index(&[]), "0.00");
+        // Dike at 180 days: delay terms 0.5 and 1 (Seawall falls back to 90).
+        assert_eq!(index(&[("Dike", 180.0)]), "6.25");
+        // Seawall at 45 days as well: delay terms 0.5 and 2.
+        assert_eq!(index(&[("Dike", 180.0), ("Seawall", 45.0)]), "-6.25");
+        // A horizon for a type with no projects changes nothing.
+        assert_eq!(index(&[("Drainage", 10.0)]), "0.00");
+    }
+
+    #[test]
+    fn budget_weighting_lifts_the_big_spending_region() {
+        let projects = |region: &str, budget: f64, savings: f64, delay: f64| {
+            (0..3)
+                .map(|_| CleanRecord::sample(region, "ABC CORP", budget, budget - savings, delay))
+                .collect::<Vec<_>>()
+        };
+        // Raw efficiency (median savings / avg delay): 50, 30 and 20; budget
+        // shares 1/12, 1/12 and 10/12.
+        let data = [
+            projects("Small", 1000.0, 500.0, 10.0),
+            projects("Mid", 1000.0, 300.0, 10.0),
+            projects("Big", 10_000.0, 1000.0, 50.0),
+        ]
+        .concat();
+        let scores = |weighted_efficiency: bool| {
+            let cfg = ReportConfig {
+                weighted_efficiency,
+                ..ReportConfig::default()
+            };
+            let rows = generate_report1(&data, &cfg);
+            ["Small", "Mid", "Big"].map(|region| {
+                rows.iter()
+                    .find(|r| r.region == region)
+                    .unwrap()
+                    .efficiency_score
+                    .clone()
+            })
+        };
+        assert_eq!(scores(false), ["100.00", "33.33", "0.00"]);
+        // Weighted: 4.17, 2.50 and 16.67 before scaling.
+        assert_eq!(scores(true), ["11.76", "0.00", "100.00"]);
+
+        // RawEfficiency is the formula value, taken before either multiplier.
+        let cfg = ReportConfig {
+            raw_efficiency: true,
+            weighted_efficiency: true,
+            completeness_weighted: true,
+            ..ReportConfig::default()
+        };
+        let rows = generate_report1(&data, &cfg);
+        let raw = |region: &str| {
+            let row = rows.iter().find(|r| r.region == region).unwrap();
+            row.raw_efficiency.clone().unwrap()
+        };
+        assert_eq!(
+            [raw("Small"), raw("Mid"), raw("Big")],
+            ["50.00", "30.00", "20.00"]
+        );
+    }
+
+    #[test]
+    fn heavily_imputed_region_scores_below_an_identical_clean_one() {
+        let mut imputed = region_projects("Imputed", 500.0, 10.0);
+        imputed[0].coord_source = Some(CoordSource::ProvinceAverage);
+        imputed[1].completion_date_imputed = true;
+        // Same raw efficiency (500 / 10) in both; the anchor sits at 10.
+        let data = [
+            region_projects("Clean", 500.0, 10.0),
+            imputed,
+            region_projects("Anchor", 100.0, 10.0),
+        ]
+        .concat();
+        let scores = |completeness_weighted: bool| {
+            let cfg = ReportConfig {
+                completeness_weighted,
+                ..ReportConfig::default()
+            };
+            let rows = generate_report1(&data, &cfg);
+            ["Clean", "Imputed", "Anchor"].map(|region| {
+                rows.iter()
+                    .find(|r| r.region == region)
+                    .unwrap()
+                    .efficiency_score
+                    .clone()
+            })
+        };
+        assert_eq!(scores(false), ["100.00", "100.00", "0.00"]);
+        // Two of three projects imputed: 50 becomes 50 / 3.
+        assert_eq!(scores(true), ["100.00", "16.67", "0.00"]);
+    }
+
+    #[test]
+    fn one_changed_value_gives_exactly_one_change_row() {
+        let header = StringRecord::from(vec!["Contractor", "TotalCost", "AvgDelay"]);
+        let previous = vec![
+            StringRecord::from(vec!["ABC CORP", "1000.00", "10.00"]),
+            StringRecord::from(vec!["XYZ INC", "2000.00", "20.00"]),
+            StringRecord::from(vec!["LMN JV", "3000.00", "30.00"]),
+        ];
+        let mut current = previous.clone();
+        current[1] = StringRecord::from(vec!["XYZ INC", "2000.00", "25.00"]);
+
+        let (out_header, rows) =
+            report_changes((&header, &previous), (&header, &current), &["Contractor"]);
+        assert_eq!(
+            out_header,
+            StringRecord::from(vec![
+                "Change",
+                "ChangedColumns",
+                "Contractor",
+                "TotalCost",
+                "AvgDelay"
+            ])
+        );
+        assert_eq!(
+            rows,
+            [StringRecord::from(vec![
+                "changed", "AvgDelay", "XYZ INC", "2000.00", "25.00"
+            ])]
+        );
+
+        // New and removed rows are labeled; removed ones keep old values.
+        current.remove(0);
+        current.push(StringRecord::from(vec!["NEW BUILDERS", "500.00", "0.00"]));
+        let (_, rows) = report_changes((&header, &previous), (&header, &current), &["Contractor"]);
+        let labels: Vec<(&str, &str)> = rows.iter().map(|r| (&r[0], &r[2])).collect();
+        assert_eq!(
+            labels,
+            [
+                ("changed", "XYZ INC"),
+                ("new", "NEW BUILDERS"),
+                ("removed", "ABC CORP")
+            ]
+        );
+        assert_eq!(&rows[2][3], "1000.00");
+    }
+
+    #[test]
+    fn single_region_contractor_is_listed_but_a_three_region_one_is_not() {
+        let data = vec![
+            CleanRecord::sample("Region I", "LOCAL CORP", 1000.0, 900.0, 10.0),
+            CleanRecord::sample("Region I", "LOCAL CORP", 3000.0, 2500.0, 10.0),
+            CleanRecord::sample("Region I", "NATIONAL INC", 1000.0, 900.0, 10.0),
+            CleanRecord::sample("NCR", "NATIONAL INC", 1000.0, 900.0, 10.0),
+            CleanRecord::sample("Region VII", "NATIONAL INC", 1000.0, 900.0, 10.0),
+        ];
+        let cfg = ReportConfig::default();
+        let rows = single_region_contractors(&data, &cfg, 1);
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(
+            (
+                row.contractor.as_str(),
+                row.regions.as_str(),
+                row.num_regions
+            ),
+            ("LOCAL CORP", "Region I", 1)
+        );
+        assert_eq!(row.num_projects, 2);
+        assert_eq!(row.total_cost, "3400.00");
+
+        // A three-region contractor only appears once three are allowed.
+        let names = |max_regions: usize| -> Vec<String> {
+            single_region_contractors(&data, &cfg, max_regions)
+                .into_iter()
+                .map(|r| r.contractor)
+                .collect()
+        };
+        assert_eq!(names(2), ["LOCAL CORP"]);
+        assert_eq!(names(3), ["LOCAL CORP", "NATIONAL INC"]);
     }
 }
@@ -0,0 +1,120 @@
+// Minimal HTTP server for `serve` mode.
+//
+// Built on `tiny_http`, which handles the HTTP parsing and connection
+// management. Every response body is computed once at startup; the server
+// only matches the request path against them, one request at a time.
+use std::collections::BTreeMap;
+use std::io;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Paths `serve` answers, in the order they are listed in errors.
+pub const ENDPOINTS: [&str; 4] = ["/report1", "/report2", "/report3", "/summary"];
+
+/// A JSON error body: `{"error": message}`.
+pub fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Listen on `127.0.0.1:port` and answer each `GET` of an endpoint with
+/// its body from `bodies`. `None` means no data was loaded, so endpoints
+/// answer 503. Only returns if the port cannot be bound.
+pub fn serve(port: u16, bodies: Option<&BTreeMap<&str, String>>) -> io::Result<()> {
+    let server = Server::http(("127.0.0.1", port)).map_err(io::Error::other)?;
+    println!(
+        "Serving {} on http://127.0.0.1:{} (Ctrl+C to stop)",
+        ENDPOINTS.join(", "),
+        port
+    );
+    for request in server.incoming_requests() {
+        handle(request, bodies);
+    }
+    Ok(())
+}
+
+/// Answer one request. A client that disconnects only loses its own
+/// response.
+fn handle(request: Request, bodies: Option<&BTreeMap<&str, String>>) {
+    let (status, body) = route(request.method(), request.url(), bodies);
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        eprintln!("Request error: {}", e);
+    }
+}
+
+/// The status code and JSON body for a `method` request of `url`.
+fn route(method: &Method, url: &str, bodies: Option<&BTreeMap<&str, String>>) -> (u16, String) {
+    let path = url.split('?').next().unwrap_or("");
+    match (method, bodies.and_then(|b| b.get(path))) {
+        (Method::Get, Some(body)) => (200, body.clone()),
+        (Method::Get, None) if ENDPOINTS.contains(&path) => (
+            503,
+            error_body("No data loaded; check the server log for the load error"),
+        ),
+        (Method::Get, None) => (
+            404,
+            error_body(&format!(
+                "Unknown path {} (expected one of: {})",
+                path,
+                ENDPOINTS.join(", ")
+            )),
+        ),
+        _ => (405, error_body("Only GET is supported")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// Send a raw HTTP/1.0 request to `addr` and return the status line
+    /// and body of the response.
+    fn request(addr: std::net::SocketAddr, method: &str, path: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nHost: localhost\r\n\r\n",
+            method, path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    #[test]
+    fn endpoints_answer_over_http() {
+        let bodies = BTreeMap::from([("/summary", r#"{"total_projects":3}"#.to_string())]);
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..3 {
+                    handle(server.recv().unwrap(), Some(&bodies));
+                }
+            });
+            let (status, body) = request(addr, "GET", "/summary?pretty=1");
+            assert_eq!(status, "HTTP/1.0 200 OK");
+            assert_eq!(body, r#"{"total_projects":3}"#);
+            let (status, body) = request(addr, "GET", "/report9");
+            assert_eq!(status, "HTTP/1.0 404 Not Found");
+            assert!(body.contains("Unknown path /report9"));
+            let (status, _) = request(addr, "POST", "/summary");
+            assert_eq!(status, "HTTP/1.0 405 Method Not Allowed");
+        });
+    }
+
+    #[test]
+    fn endpoints_answer_503_without_data() {
+        let (status, body) = route(&Method::Get, "/report1", None);
+        assert_eq!(status, 503);
+        assert!(body.contains("No data loaded"));
+        assert_eq!(route(&Method::Get, "/other", None).0, 404);
+    }
+}
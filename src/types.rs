@@ -4,7 +4,10 @@
 // `serde` is used for both CSV deserialization (input) and JSON/CSV
 // serialization (output). `tabled` is used to pretty-print Markdown tables
 // in the terminal previews.
+use crate::util::{canonical_name, format_number};
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tabled::Tabled;
 
 /// Direct mapping of the input CSV schema.
@@ -31,6 +34,9 @@ pub struct RawRow {
     pub contract_cost: Option<String>,
     #[serde(rename = "ActualCompletionDate")]
     pub actual_completion_date: Option<String>,
+    /// Optional; older DPWH exports do not carry this column.
+    #[serde(rename = "PlannedCompletionDate")]
+    pub planned_completion_date: Option<String>,
     #[serde(rename = "Contractor")]
     pub contractor: Option<String>,
     #[serde(rename = "StartDate")]
@@ -43,6 +49,51 @@ pub struct RawRow {
     pub provincial_capital_latitude: Option<String>,
     #[serde(rename = "ProvincialCapitalLongitude")]
     pub provincial_capital_longitude: Option<String>,
+    /// Optional project status (e.g. `Completed`, `Not Yet Started`); a
+    /// `Remarks` column can be read as it via `--column-map`.
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+}
+
+/// Which definition of "delay" a record's `completion_delay_days` uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayBasis {
+    /// `ActualCompletionDate - PlannedCompletionDate`: true delay versus plan.
+    Planned,
+    /// `ActualCompletionDate - StartDate`: project duration, used when no
+    /// planned date (or no actual completion date) is available.
+    Duration,
+}
+
+impl DelayBasis {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DelayBasis::Planned => "Planned",
+            DelayBasis::Duration => "Duration",
+        }
+    }
+}
+
+/// Where a record's `lat`/`lon` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordSource {
+    /// `ProjectLatitude`/`ProjectLongitude`: the project's own location.
+    Project,
+    /// The provincial capital's coordinates, used when the project's own
+    /// are missing.
+    ProvincialCapital,
+    /// Imputed from the average of other projects in the same province.
+    ProvinceAverage,
+}
+
+impl CoordSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoordSource::Project => "Project",
+            CoordSource::ProvincialCapital => "ProvincialCapital",
+            CoordSource::ProvinceAverage => "ProvinceAverage",
+        }
+    }
 }
 
 /// Fully validated and normalized project record.
@@ -55,6 +106,9 @@ pub struct RawRow {
 /// - possibly imputed latitude/longitude values.
 #[derive(Debug, Clone)]
 pub struct CleanRecord {
+    /// 1-based line of the row in the input CSV (its row number in a
+    /// Parquet file).
+    pub source_line: u64,
     pub funding_year: i32,
     pub region: String,
     pub main_island: String,
@@ -65,8 +119,184 @@ pub struct CleanRecord {
     pub contract_cost: f64,
     pub cost_savings: f64,
     pub completion_delay_days: f64,
+    pub delay_basis: DelayBasis,
+    /// `ActualCompletionDate`, or the start date when it was missing.
+    pub completion_date: NaiveDate,
+    /// `true` when `completion_date` was imputed from the start date.
+    pub completion_date_imputed: bool,
     pub lat: Option<f64>,
     pub lon: Option<f64>,
+    /// Where `lat`/`lon` came from; `None` when they are still missing.
+    pub coord_source: Option<CoordSource>,
+    /// `true` when the project ran longer than
+    /// `LoadOptions::max_duration_days` and `exclude_long_duration` is set,
+    /// or is unfinished under `IncompleteStatus::ExcludeDelay`: its
+    /// `completion_delay_days` is left out of every delay average.
+    pub delay_excluded: bool,
+}
+
+impl CleanRecord {
+    /// Whether the record's Region is `region`, which must already be a
+    /// `canonical_name`. Spelling and spacing differences in the data do
+    /// not split a region.
+    pub fn in_region(&self, region: &str) -> bool {
+        canonical_name(&self.region) == region
+    }
+
+    /// Whether the record is from FundingYear `year`.
+    pub fn in_funding_year(&self, year: i32) -> bool {
+        self.funding_year == year
+    }
+
+    /// Whether the record's coordinates were filled in rather than read
+    /// from its own project columns: from the provincial capital or a
+    /// province average.
+    pub fn coords_imputed(&self) -> bool {
+        matches!(
+            self.coord_source,
+            Some(CoordSource::ProvincialCapital | CoordSource::ProvinceAverage)
+        )
+    }
+
+    /// Whether anything about the record was imputed: its coordinates
+    /// (see `coords_imputed`) or its completion date.
+    pub fn is_imputed(&self) -> bool {
+        self.coords_imputed() || self.completion_date_imputed
+    }
+}
+
+#[cfg(test)]
+impl CleanRecord {
+    /// A completed 2022 project with its own coordinates, for tests:
+    /// `budget` approved, `cost` spent and `delay` days of delay. Fields a
+    /// test cares about are overridden with struct update syntax.
+    pub(crate) fn sample(
+        region: &str,
+        contractor: &str,
+        budget: f64,
+        cost: f64,
+        delay: f64,
+    ) -> CleanRecord {
+        CleanRecord {
+            source_line: 2,
+            funding_year: 2022,
+            region: region.to_string(),
+            main_island: "Luzon".to_string(),
+            province: "Province".to_string(),
+            type_of_work: "Construction of Flood Mitigation Structure".to_string(),
+            contractor: contractor.to_string(),
+            approved_budget: budget,
+            contract_cost: cost,
+            cost_savings: budget - cost,
+            completion_delay_days: delay,
+            delay_basis: DelayBasis::Duration,
+            completion_date: NaiveDate::from_ymd_opt(2022, 6, 30).unwrap(),
+            completion_date_imputed: false,
+            lat: Some(14.6),
+            lon: Some(121.0),
+            coord_source: Some(CoordSource::Project),
+            delay_excluded: false,
+        }
+    }
+}
+
+/// Per-contractor totals built by `reports::aggregate_contractors`.
+///
+/// Report 2 and the other contractor-level features all derive their
+/// metrics from this one aggregation so they never disagree.
+#[derive(Debug, Clone, Default)]
+pub struct ContractorAgg {
+    pub projects: usize,
+    pub delays: Vec<f64>,
+    /// TypeOfWork of the project behind each entry of `delays`.
+    pub delay_types: Vec<String>,
+    pub total_savings: f64,
+    pub total_cost: f64,
+    /// Project count per province.
+    pub provinces: BTreeMap<String, usize>,
+    /// Project count per region.
+    pub regions: BTreeMap<String, usize>,
+    /// Project count per funding year.
+    pub years: BTreeMap<i32, usize>,
+}
+
+impl ContractorAgg {
+    /// The province with the most projects; ties go to the alphabetically
+    /// first name. `None` when no projects were added.
+    pub fn primary_province(&self) -> Option<String> {
+        let mut best: Option<(&String, usize)> = None;
+        // `provinces` iterates alphabetically, so keeping only strictly
+        // larger counts leaves the first name among equals.
+        for (province, count) in &self.provinces {
+            if best.is_none_or(|(_, c)| *count > c) {
+                best = Some((province, *count));
+            }
+        }
+        best.map(|(p, _)| p.clone())
+    }
+}
+
+/// Filters built with menu option [6], applied to the loaded records
+/// before reports are generated. Each field is one predicate (`None` means
+/// no filter on that field); a record must pass all of them.
+#[derive(Debug, Clone, Default)]
+pub struct RecordFilter {
+    /// Region name, matched on `canonical_name`.
+    pub region: Option<String>,
+    /// TypeOfWork, matched on `canonical_name`.
+    pub type_of_work: Option<String>,
+    pub funding_year: Option<i32>,
+    /// Lowest ApprovedBudgetForContract kept (inclusive).
+    pub min_budget: Option<f64>,
+}
+
+impl RecordFilter {
+    /// Whether no field is set, so every record passes.
+    pub fn is_empty(&self) -> bool {
+        self.region.is_none()
+            && self.type_of_work.is_none()
+            && self.funding_year.is_none()
+            && self.min_budget.is_none()
+    }
+
+    /// Whether `r` passes every predicate that is set.
+    pub fn matches(&self, r: &CleanRecord) -> bool {
+        self.region
+            .as_ref()
+            .is_none_or(|region| r.in_region(&canonical_name(region)))
+            && self
+                .type_of_work
+                .as_ref()
+                .is_none_or(|tow| canonical_name(tow) == canonical_name(&r.type_of_work))
+            && self.funding_year.is_none_or(|year| r.in_funding_year(year))
+            && self.min_budget.is_none_or(|min| r.approved_budget >= min)
+    }
+
+    /// The active predicates for display, e.g.
+    /// `Region = Region X, FundingYear = 2022`, or `none`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(region) = &self.region {
+            parts.push(format!("Region = {}", region));
+        }
+        if let Some(tow) = &self.type_of_work {
+            parts.push(format!("TypeOfWork = {}", tow));
+        }
+        if let Some(year) = self.funding_year {
+            parts.push(format!("FundingYear = {}", year));
+        }
+        if let Some(min) = self.min_budget {
+            parts.push(format!(
+                "ApprovedBudgetForContract >= {}",
+                format_number(min, 2)
+            ));
+        }
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
 }
 
 /// Row for Report 1: Regional Flood Mitigation Efficiency Summary.
@@ -93,6 +323,69 @@ pub struct RegionSummaryRow {
     #[serde(rename = "EfficiencyScore")]
     #[tabled(rename = "EfficiencyScore")]
     pub efficiency_score: String,
+    /// This group's share of the national total budget, in percent.
+    #[serde(rename = "BudgetSharePct")]
+    #[tabled(rename = "BudgetSharePct")]
+    pub budget_share_pct: String,
+    /// Interquartile range (Q3 - Q1) of the group's cost savings.
+    #[serde(rename = "IQR")]
+    #[tabled(rename = "IQR")]
+    pub iqr: String,
+    /// Standing among all groups by EfficiencyScore, from 0 (lowest) to
+    /// 100 (highest).
+    #[serde(rename = "PercentileRank")]
+    #[tabled(rename = "PercentileRank")]
+    pub percentile_rank: String,
+    /// MedianSavings and AvgDelay combined by `--efficiency-formula`,
+    /// before any `--weighted-efficiency` or `--completeness-weight`
+    /// multiplier; without those, EfficiencyScore is normalized from it.
+    /// Only filled (and only written) with `--raw-efficiency`.
+    #[serde(rename = "RawEfficiency", skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    pub raw_efficiency: Option<String>,
+}
+
+/// A Report 1 row tagged with the dataset it came from, for the combined
+/// `report1_by_source.csv` (see `--side-by-side`). Same columns as
+/// `RegionSummaryRow`, with `Source` first.
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct SourcedRegionRow {
+    #[serde(rename = "Source")]
+    #[tabled(rename = "Source")]
+    pub source: String,
+    #[serde(rename = "Region")]
+    #[tabled(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "MainIsland")]
+    #[tabled(rename = "MainIsland")]
+    pub main_island: String,
+    #[serde(rename = "TotalBudget")]
+    #[tabled(rename = "TotalBudget")]
+    pub total_budget: String,
+    #[serde(rename = "MedianSavings")]
+    #[tabled(rename = "MedianSavings")]
+    pub median_savings: String,
+    #[serde(rename = "AvgDelay")]
+    #[tabled(rename = "AvgDelay")]
+    pub avg_delay: String,
+    #[serde(rename = "HighDelayPct")]
+    #[tabled(rename = "HighDelayPct")]
+    pub high_delay_pct: String,
+    #[serde(rename = "EfficiencyScore")]
+    #[tabled(rename = "EfficiencyScore")]
+    pub efficiency_score: String,
+    #[serde(rename = "BudgetSharePct")]
+    #[tabled(rename = "BudgetSharePct")]
+    pub budget_share_pct: String,
+    #[serde(rename = "IQR")]
+    #[tabled(rename = "IQR")]
+    pub iqr: String,
+    #[serde(rename = "PercentileRank")]
+    #[tabled(rename = "PercentileRank")]
+    pub percentile_rank: String,
+    #[serde(rename = "RawEfficiency", skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    pub raw_efficiency: Option<String>,
 }
 
 /// Preview-only variant of `RegionSummaryRow` with prettier number formatting
@@ -113,6 +406,12 @@ pub struct RegionSummaryRowPreview {
     pub high_delay_pct: String,
     #[tabled(rename = "EfficiencyScore")]
     pub efficiency_score: String,
+    #[tabled(rename = "BudgetSharePct")]
+    pub budget_share_pct: String,
+    #[tabled(rename = "IQR")]
+    pub iqr: String,
+    #[tabled(rename = "PercentileRank")]
+    pub percentile_rank: String,
 }
 
 /// Row for Report 2: Top Contractors Performance Ranking.
@@ -145,6 +444,23 @@ pub struct ContractorRankingRow {
     #[serde(rename = "RiskFlag")]
     #[tabled(rename = "RiskFlag")]
     pub risk_flag: String,
+    /// TotalSavings per day of delay, or `N/A` when total delay is <= 0.
+    #[serde(rename = "SavingsPerDelayDay")]
+    #[tabled(rename = "SavingsPerDelayDay")]
+    pub savings_per_delay_day: String,
+    /// TotalCost as a percentage of the national total contract cost.
+    #[serde(rename = "CostSharePct")]
+    #[tabled(rename = "CostSharePct")]
+    pub cost_share_pct: String,
+    /// The province where the contractor has the most projects.
+    #[serde(rename = "PrimaryProvince")]
+    #[tabled(rename = "PrimaryProvince")]
+    pub primary_province: String,
+    /// TotalCost as a percentage of the contractor's total approved budget,
+    /// clamped to [0, 200].
+    #[serde(rename = "BudgetUtilizationPct")]
+    #[tabled(rename = "BudgetUtilizationPct")]
+    pub budget_utilization_pct: String,
 }
 
 /// Preview-only variant of `ContractorRankingRow` with comma formatting for
@@ -167,6 +483,14 @@ pub struct ContractorRankingRowPreview {
     pub reliability_index: String,
     #[tabled(rename = "RiskFlag")]
     pub risk_flag: String,
+    #[tabled(rename = "SavingsPerDelayDay")]
+    pub savings_per_delay_day: String,
+    #[tabled(rename = "CostSharePct")]
+    pub cost_share_pct: String,
+    #[tabled(rename = "PrimaryProvince")]
+    pub primary_province: String,
+    #[tabled(rename = "BudgetUtilizationPct")]
+    pub budget_utilization_pct: String,
 }
 
 /// Row for Report 3: Annual Project Type Cost Overrun Trends.
@@ -193,6 +517,20 @@ pub struct TypeTrendRow {
     #[serde(rename = "YoYChange")]
     #[tabled(rename = "YoYChange")]
     pub yoy_change: String,
+    /// Only filled (and only written) with `--smooth-savings`.
+    #[serde(rename = "SmoothedAvgSavings", skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    pub smoothed_avg_savings: Option<String>,
+    /// % change in TotalProjects from the same type's previous FundingYear;
+    /// `N/A` when that year has no projects of the type.
+    #[serde(rename = "ProjectCountYoY")]
+    #[tabled(rename = "ProjectCountYoY")]
+    pub project_count_yoy: String,
+    /// Only filled (and only written) with `--count-drop-pct`: `Yes` when
+    /// ProjectCountYoY fell by more than that percentage.
+    #[serde(rename = "CountCollapse", skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    pub count_collapse: Option<String>,
 }
 
 /// Preview-only variant of `TypeTrendRow` where all numeric columns except
@@ -211,6 +549,267 @@ pub struct TypeTrendRowPreview {
     pub overrun_rate: String,
     #[tabled(rename = "YoYChange")]
     pub yoy_change: String,
+    #[tabled(rename = "ProjectCountYoY")]
+    pub project_count_yoy: String,
+}
+
+/// One contractor name in a cluster of likely-duplicate spellings.
+///
+/// Rows sharing a `Cluster` id are names within the configured edit
+/// distance of each other (directly or through another member). This is a
+/// review aid only; the reports still treat each spelling separately.
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct MergeCandidateRow {
+    #[serde(rename = "Cluster")]
+    #[tabled(rename = "Cluster")]
+    pub cluster: usize,
+    #[serde(rename = "Contractor")]
+    #[tabled(rename = "Contractor")]
+    pub contractor: String,
+    #[serde(rename = "NumProjects")]
+    #[tabled(rename = "NumProjects")]
+    pub num_projects: usize,
+    #[serde(rename = "Provinces")]
+    #[tabled(rename = "Provinces")]
+    pub provinces: String,
+    #[serde(rename = "FundingYears")]
+    #[tabled(rename = "FundingYears")]
+    pub funding_years: String,
+}
+
+/// One record whose `ContractCost` falls outside the IQR fences of its
+/// group: `[Q1 - k*IQR, Q3 + k*IQR]` with k from `--cost-outliers`.
+///
+/// `Group` is `All` when fences are computed over every record, or the
+/// record's TypeOfWork with `--cost-outliers-by-type`.
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct CostOutlierRow {
+    #[serde(rename = "Group")]
+    #[tabled(rename = "Group")]
+    pub group: String,
+    #[serde(rename = "FundingYear")]
+    #[tabled(rename = "FundingYear")]
+    pub funding_year: i32,
+    #[serde(rename = "Region")]
+    #[tabled(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "Province")]
+    #[tabled(rename = "Province")]
+    pub province: String,
+    #[serde(rename = "TypeOfWork")]
+    #[tabled(rename = "TypeOfWork")]
+    pub type_of_work: String,
+    #[serde(rename = "Contractor")]
+    #[tabled(rename = "Contractor")]
+    pub contractor: String,
+    #[serde(rename = "ContractCost")]
+    #[tabled(rename = "ContractCost")]
+    pub contract_cost: String,
+    #[serde(rename = "LowerFence")]
+    #[tabled(rename = "LowerFence")]
+    pub lower_fence: String,
+    #[serde(rename = "UpperFence")]
+    #[tabled(rename = "UpperFence")]
+    pub upper_fence: String,
+}
+
+/// A contractor whose average delay rose every year from 2021 to 2023
+/// (`--worsening-contractors`).
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct WorseningContractorRow {
+    #[serde(rename = "Contractor")]
+    #[tabled(rename = "Contractor")]
+    pub contractor: String,
+    #[serde(rename = "AvgDelay2021")]
+    #[tabled(rename = "AvgDelay2021")]
+    pub avg_delay_2021: String,
+    #[serde(rename = "AvgDelay2022")]
+    #[tabled(rename = "AvgDelay2022")]
+    pub avg_delay_2022: String,
+    #[serde(rename = "AvgDelay2023")]
+    #[tabled(rename = "AvgDelay2023")]
+    pub avg_delay_2023: String,
+    /// AvgDelay2023 - AvgDelay2021.
+    #[serde(rename = "DelayIncrease")]
+    #[tabled(rename = "DelayIncrease")]
+    pub delay_increase: String,
+    #[serde(rename = "NumProjects")]
+    #[tabled(rename = "NumProjects")]
+    pub num_projects: usize,
+}
+
+/// A contractor whose projects all lie in a few regions, by default one
+/// (`--single-region-contractors`).
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct SingleRegionContractorRow {
+    #[serde(rename = "Contractor")]
+    #[tabled(rename = "Contractor")]
+    pub contractor: String,
+    /// The regions, `/`-separated in name order.
+    #[serde(rename = "Regions")]
+    #[tabled(rename = "Regions")]
+    pub regions: String,
+    #[serde(rename = "NumRegions")]
+    #[tabled(rename = "NumRegions")]
+    pub num_regions: usize,
+    #[serde(rename = "NumProjects")]
+    #[tabled(rename = "NumProjects")]
+    pub num_projects: usize,
+    #[serde(rename = "TotalCost")]
+    #[tabled(rename = "TotalCost")]
+    pub total_cost: String,
+    #[serde(rename = "TotalSavings")]
+    #[tabled(rename = "TotalSavings")]
+    pub total_savings: String,
+}
+
+/// A numeric cell text the loader could not parse, with how often it
+/// occurred, from `--log-rejected`.
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct RejectedFormatRow {
+    #[serde(rename = "Column")]
+    #[tabled(rename = "Column")]
+    pub column: String,
+    #[serde(rename = "Value")]
+    #[tabled(rename = "Value")]
+    pub value: String,
+    #[serde(rename = "Count")]
+    #[tabled(rename = "Count")]
+    pub count: usize,
+}
+
+/// One cleaned record as written to `clean_records.csv` (`--export-clean`).
+///
+/// Rows are sorted by FundingYear, Region, Province, Contractor and
+/// SourceLine, and `Id` numbers them in that order, so re-runs over the
+/// same input produce identical files.
+#[derive(Debug, Serialize, Clone)]
+pub struct CleanRecordRow {
+    #[serde(rename = "Id")]
+    pub id: usize,
+    #[serde(rename = "SourceLine")]
+    pub source_line: u64,
+    #[serde(rename = "FundingYear")]
+    pub funding_year: i32,
+    #[serde(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "MainIsland")]
+    pub main_island: String,
+    #[serde(rename = "Province")]
+    pub province: String,
+    #[serde(rename = "TypeOfWork")]
+    pub type_of_work: String,
+    #[serde(rename = "Contractor")]
+    pub contractor: String,
+    #[serde(rename = "ApprovedBudgetForContract")]
+    pub approved_budget: String,
+    #[serde(rename = "ContractCost")]
+    pub contract_cost: String,
+    #[serde(rename = "CostSavings")]
+    pub cost_savings: String,
+    #[serde(rename = "CompletionDelayDays")]
+    pub completion_delay_days: String,
+    #[serde(rename = "DelayBasis")]
+    pub delay_basis: &'static str,
+    #[serde(rename = "CompletionDate")]
+    pub completion_date: String,
+    #[serde(rename = "CompletionDateImputed")]
+    pub completion_date_imputed: bool,
+    #[serde(rename = "Latitude")]
+    pub lat: Option<f64>,
+    #[serde(rename = "Longitude")]
+    pub lon: Option<f64>,
+    #[serde(rename = "CoordSource")]
+    pub coord_source: Option<&'static str>,
+    /// Whether ContractCost equals ApprovedBudgetForContract exactly. Only
+    /// filled (and only written) with `--flag-exact-budget`.
+    #[serde(rename = "ExactBudgetMatch", skip_serializing_if = "Option::is_none")]
+    pub exact_budget_match: Option<bool>,
+}
+
+/// One region's drill-down as written to `by_region/<region>.json`
+/// (`--by-region`): its Report 1-style summary plus every cleaned project
+/// in it, in `clean_records.csv` order.
+#[derive(Debug, Serialize, Clone)]
+pub struct RegionDrillDown {
+    #[serde(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "Summary")]
+    pub summary: RegionSummaryRow,
+    #[serde(rename = "Projects")]
+    pub projects: Vec<CleanRecordRow>,
+}
+
+/// Console-only row pairing the N-th best and N-th worst regions by
+/// EfficiencyScore for the top/bottom menu view.
+#[derive(Debug, Tabled, Clone)]
+pub struct RegionExtremesRow {
+    #[tabled(rename = "#")]
+    pub position: usize,
+    #[tabled(rename = "TopRegion")]
+    pub top_region: String,
+    #[tabled(rename = "TopScore")]
+    pub top_score: String,
+    #[tabled(rename = "BottomRegion")]
+    pub bottom_region: String,
+    #[tabled(rename = "BottomScore")]
+    pub bottom_score: String,
+}
+
+/// One region's Report 1 standing in two periods being compared.
+///
+/// Ranks are 1-based positions in each period's efficiency-sorted Report 1.
+/// They are strings so a region present in only one period can show `-`
+/// for the missing side; `RankChange` then reads `new` or `dropped`.
+#[derive(Debug, Serialize, Tabled, Clone)]
+pub struct RegionRankChangeRow {
+    #[serde(rename = "Region")]
+    #[tabled(rename = "Region")]
+    pub region: String,
+    #[serde(rename = "PreviousRank")]
+    #[tabled(rename = "PreviousRank")]
+    pub previous_rank: String,
+    #[serde(rename = "CurrentRank")]
+    #[tabled(rename = "CurrentRank")]
+    pub current_rank: String,
+    #[serde(rename = "RankChange")]
+    #[tabled(rename = "RankChange")]
+    pub rank_change: String,
+    #[serde(rename = "PreviousScore")]
+    #[tabled(rename = "PreviousScore")]
+    pub previous_score: String,
+    #[serde(rename = "CurrentScore")]
+    #[tabled(rename = "CurrentScore")]
+    pub current_score: String,
+}
+
+/// Spread of Report 2's raw ReliabilityIndex across qualifying
+/// contractors, printed by `--stats`.
+#[derive(Debug, Clone)]
+pub struct ReliabilityStats {
+    pub qualifying: usize,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    /// How many contractors score below zero.
+    pub negative: usize,
+}
+
+/// Weighted average savings across all types of work for one funding
+/// year: (sum of savings) / (project count).
+#[derive(Debug, Serialize, Clone)]
+pub struct YearWeightedAvg {
+    pub funding_year: i32,
+    pub total_projects: usize,
+    pub weighted_avg_savings: String,
+}
+
+/// Total savings of one main island. Records with a missing island share
+/// the loader's placeholder bucket (`Unknown` by default).
+#[derive(Debug, Serialize, Clone)]
+pub struct IslandSavings {
+    pub main_island: String,
+    pub total_savings: String,
 }
 
 /// High-level summary statistics exported as `summary.json`.
@@ -222,7 +821,74 @@ pub struct SummaryStats {
     #[serde(rename = "global_avg_delay_days")]
     pub global_avg_delay_days: String,
     pub total_savings: String,
+    pub year_weighted_avg_savings: Vec<YearWeightedAvg>,
+    /// `total_savings` broken down by main island, alphabetically.
+    pub island_savings: Vec<IslandSavings>,
+    /// Gini coefficient of total `ContractCost` per contractor across all
+    /// records (0 = spread evenly, toward 1 = concentrated in a few).
+    pub contractor_cost_gini: String,
+    /// Earliest and latest reported (non-imputed) `ActualCompletionDate`,
+    /// as `YYYY-MM-DD`; `None` when no record has one.
+    pub earliest_completion_date: Option<String>,
+    pub latest_completion_date: Option<String>,
+    /// Days from `latest_completion_date` to the day the summary was built.
+    pub days_since_latest_completion: Option<i64>,
+    /// Hex SHA-256 of the input file the records were loaded from.
+    pub input_sha256: Option<String>,
+    /// Records whose ContractCost equals their approved budget exactly, a
+    /// hint of placeholder or copied data. Only with `--flag-exact-budget`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exact_budget_matches: Option<usize>,
     pub report1_regions: usize,
     pub report2_contractors: usize,
     pub report3_entries: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_filter_applies_every_predicate_together() {
+        let record = |region: &str, year: i32, type_of_work: &str, budget: f64| CleanRecord {
+            funding_year: year,
+            type_of_work: type_of_work.to_string(),
+            ..CleanRecord::sample(region, "ABC CORP", budget, budget * 0.9, 10.0)
+        };
+        let data = [
+            record("Region IV-A", 2022, "Dike", 5_000_000.0),
+            // Same region spelled differently, as `region_summary` allows.
+            record(" region  iv-a", 2022, "dike", 7_000_000.0),
+            record("Region IV-A", 2021, "Dike", 5_000_000.0),
+            record("Region IV-A", 2022, "Seawall", 5_000_000.0),
+            record("Region IV-A", 2022, "Dike", 500_000.0),
+            record("NCR", 2022, "Dike", 5_000_000.0),
+        ];
+        let filter = RecordFilter {
+            region: Some("REGION IV-A".to_string()),
+            type_of_work: Some("Dike".to_string()),
+            funding_year: Some(2022),
+            min_budget: Some(1_000_000.0),
+        };
+        let kept: Vec<f64> = data
+            .iter()
+            .filter(|r| filter.matches(r))
+            .map(|r| r.approved_budget)
+            .collect();
+        assert_eq!(kept, [5_000_000.0, 7_000_000.0]);
+        assert_eq!(
+            filter.describe(),
+            "Region = REGION IV-A, TypeOfWork = Dike, FundingYear = 2022, \
+             ApprovedBudgetForContract >= 1,000,000.00"
+        );
+
+        // Dropping the year filter lets the 2021 project through as well.
+        let filter = RecordFilter {
+            funding_year: None,
+            ..filter
+        };
+        assert_eq!(data.iter().filter(|r| filter.matches(r)).count(), 3);
+        assert!(RecordFilter::default().is_empty());
+        assert!(data.iter().all(|r| RecordFilter::default().matches(r)));
+    }
+}
@@ -2,8 +2,10 @@
 //
 // This module centralizes all the "dirty" CSV/number/date handling so the
 // rest of the code can assume clean, typed values.
-use chrono::NaiveDate;
+use crate::config::RoundingMode;
+use chrono::{Datelike, Duration, NaiveDate};
 use num_format::{Locale, ToFormattedString};
+use sha2::{Digest, Sha256};
 
 /// Parse a string-like value into `f64` while being forgiving about
 /// formatting issues that are common in CSV exports (commas, spaces, text).
@@ -25,6 +27,51 @@ pub fn parse_f64_safe(s: Option<&str>) -> Option<f64> {
     s.parse::<f64>().ok()
 }
 
+/// Like `parse_f64_safe`, but first strips one recognized leading currency
+/// prefix and one recognized trailing unit token (both matched
+/// case-insensitively, with the whitespace next to them), e.g.
+/// `"₱1,234.56"` with `prefixes = ["₱"]` or `"1234.56 PHP"` with
+/// `units = ["PHP"]`.
+///
+/// Only the listed affixes are removed; any other letters still cause the
+/// value to be rejected. The returned flag is `true` when an affix was
+/// stripped and the remainder parsed successfully.
+pub fn parse_f64_with_units(
+    s: Option<&str>,
+    prefixes: &[String],
+    units: &[String],
+) -> (Option<f64>, bool) {
+    let Some(s) = s else {
+        return (None, false);
+    };
+    let mut s = s.trim();
+    let mut stripped = false;
+    for prefix in prefixes {
+        let matches = s
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix));
+        if !prefix.is_empty() && matches {
+            s = s[prefix.len()..].trim_start();
+            stripped = true;
+            break;
+        }
+    }
+    for unit in units {
+        let Some(split) = s.len().checked_sub(unit.len()) else {
+            continue;
+        };
+        if unit.is_empty() || !s.is_char_boundary(split) {
+            continue;
+        }
+        if s[split..].eq_ignore_ascii_case(unit) {
+            let value = parse_f64_safe(Some(&s[..split]));
+            return (value, value.is_some());
+        }
+    }
+    let value = parse_f64_safe(Some(s));
+    (value, stripped && value.is_some())
+}
+
 pub fn parse_i32_safe(s: Option<&str>) -> Option<i32> {
     // `?` propagates `None` early if the option is missing.
     let s = s?.trim();
@@ -34,6 +81,23 @@ pub fn parse_i32_safe(s: Option<&str>) -> Option<i32> {
     s.parse::<i32>().ok()
 }
 
+/// Parse a `FundingYear` cell. Like `parse_i32_safe`, but a year written
+/// as a float with only zeros after the point (`2021.0`, `2021.00`, as
+/// some spreadsheet exports store it) is accepted as that year.
+///
+/// A year with a non-zero fraction (`2021.7`) is not rounded: it is
+/// rejected, so the row is dropped as having an invalid FundingYear rather
+/// than being counted in a year nobody wrote.
+pub fn parse_year_safe(s: Option<&str>) -> Option<i32> {
+    let s = s?.trim();
+    let whole = match s.split_once('.') {
+        Some((whole, frac)) if !frac.is_empty() && frac.bytes().all(|b| b == b'0') => whole,
+        Some(_) => return None,
+        None => s,
+    };
+    parse_i32_safe(Some(whole))
+}
+
 pub fn parse_date_safe(s: Option<&str>) -> Option<NaiveDate> {
     // CSV dates are expected in `YYYY-MM-DD` format.
     let s = s?.trim();
@@ -48,6 +112,57 @@ pub fn days_diff(start: NaiveDate, end: NaiveDate) -> f64 {
     (end - start).num_days() as f64
 }
 
+/// Like `days_diff`, but counting only weekdays: the Mondays to Fridays in
+/// `(start, end]`, negated when `end` is before `start`. Holidays are not
+/// excluded.
+pub fn working_days_diff(start: NaiveDate, end: NaiveDate) -> f64 {
+    if end < start {
+        return -working_days_diff(end, start);
+    }
+    let weeks = (end - start).num_days() / 7;
+    // Every full week holds exactly five weekdays; walk the rest.
+    let mut count = weeks * 5;
+    let mut day = start + Duration::days(weeks * 7);
+    while day < end {
+        day += Duration::days(1);
+        if day.weekday().num_days_from_monday() < 5 {
+            count += 1;
+        }
+    }
+    count as f64
+}
+
+/// Earliest and latest of `dates`, or `None` when there are none.
+pub fn date_span<I>(dates: I) -> Option<(NaiveDate, NaiveDate)>
+where
+    I: IntoIterator<Item = NaiveDate>,
+{
+    dates.into_iter().fold(None, |span, d| match span {
+        None => Some((d, d)),
+        Some((lo, hi)) => Some((lo.min(d), hi.max(d))),
+    })
+}
+
+/// `num / den`, or 0.0 when `den` is zero or the quotient is not finite
+/// (a NaN or infinite operand, or an overflow).
+///
+/// This is the single zero-division policy for every ratio in the reports:
+/// an undefined ratio reads as "nothing to report" rather than poisoning
+/// the output with `NaN`/`inf`. Sites that need a different answer for a
+/// degenerate denominator (e.g. `N/A`, or 0 for a negative delay) check
+/// that case themselves before calling this.
+pub fn safe_ratio(num: f64, den: f64) -> f64 {
+    if den == 0.0 {
+        return 0.0;
+    }
+    let ratio = num / den;
+    if ratio.is_finite() {
+        ratio
+    } else {
+        0.0
+    }
+}
+
 pub fn average(v: &[f64]) -> f64 {
     // Standard arithmetic mean; returns 0 for an empty slice to avoid NaNs.
     if v.is_empty() {
@@ -57,15 +172,29 @@ pub fn average(v: &[f64]) -> f64 {
     sum / v.len() as f64
 }
 
+pub fn std_dev(v: &[f64]) -> f64 {
+    // Population standard deviation (divides by n); 0 for an empty slice.
+    if v.is_empty() {
+        return 0.0;
+    }
+    let mean = average(v);
+    let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / v.len() as f64;
+    var.sqrt()
+}
+
 pub fn median(mut v: Vec<f64>) -> f64 {
     // Median of a list of numbers. We accept `Vec<f64>` by value so the
     // function can sort in-place without cloning at the call site.
+    //
+    // Non-finite values (NaN, ±inf) are dropped first: NaN has no place in
+    // the sort order and would land anywhere, corrupting the middle. An
+    // input with no finite values yields 0, like an empty one.
+    v.retain(|x| x.is_finite());
     if v.is_empty() {
         return 0.0;
     }
-    // Use `partial_cmp` to handle floating-point comparisons and fall back to
-    // equality if either side is NaN.
-    v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    // With NaN gone, `total_cmp` agrees with the numeric order.
+    v.sort_by(|a, b| a.total_cmp(b));
     let mid = v.len() / 2;
     if v.len() % 2 == 1 {
         v[mid]
@@ -74,6 +203,84 @@ pub fn median(mut v: Vec<f64>) -> f64 {
     }
 }
 
+/// The `p`-th percentile (0–100) of `v`, linearly interpolating between
+/// the two nearest ranks (the same method as Excel's `PERCENTILE.INC`).
+/// Non-finite values are ignored, as in `median`; returns 0 when nothing
+/// is left.
+pub fn percentile(v: &[f64], p: f64) -> f64 {
+    let mut sorted: Vec<f64> = v.iter().copied().filter(|x| x.is_finite()).collect();
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Gini coefficient of `values`: 0 when every value is equal, approaching
+/// 1 as the total concentrates in a single value ((n-1)/n for n values).
+/// Negative and non-finite values are ignored; returns 0 when nothing
+/// (or only zeros) is left.
+pub fn gini(values: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = values
+        .iter()
+        .copied()
+        .filter(|x| x.is_finite() && *x >= 0.0)
+        .collect();
+    let total: f64 = sorted.iter().sum();
+    if sorted.is_empty() || total == 0.0 {
+        return 0.0;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let n = sorted.len() as f64;
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i + 1) as f64 * x)
+        .sum();
+    (2.0 * weighted / (n * total) - (n + 1.0) / n).max(0.0)
+}
+
+/// Round a peso amount `n` to `decimals` places under `mode`.
+///
+/// `HalfEven` gives the value `format!("{:.*}", decimals, n)` prints.
+/// `HalfUp` rounds the shortest decimal form of `n` (what a spreadsheet
+/// shows) rather than its binary expansion, in which 2.675 is stored as
+/// 2.67499...; ties go away from zero.
+pub fn round_currency(n: f64, decimals: usize, mode: RoundingMode) -> f64 {
+    if !n.is_finite() {
+        return n;
+    }
+    match mode {
+        RoundingMode::HalfEven => format!("{:.*}", decimals, n).parse().unwrap_or(n),
+        RoundingMode::HalfUp => {
+            // `Display` for f64 never uses an exponent.
+            let repr = n.abs().to_string();
+            let (int, frac) = repr.split_once('.').unwrap_or((&repr, ""));
+            if frac.len() <= decimals {
+                return n;
+            }
+            let kept: f64 = format!("{}.{}", int, &frac[..decimals])
+                .parse()
+                .unwrap_or(0.0);
+            let rounded = if frac.as_bytes()[decimals] >= b'5' {
+                // Reprint the sum so 2.34 + 0.01 is 2.35, not 2.3499999999999996.
+                let up = kept + 10f64.powi(-(decimals as i32));
+                format!("{:.*}", decimals, up).parse().unwrap_or(up)
+            } else {
+                kept
+            };
+            if rounded == 0.0 {
+                0.0
+            } else {
+                rounded.copysign(n)
+            }
+        }
+    }
+}
+
 pub fn format_number(n: f64, decimals: usize) -> String {
     // Format a floating-point value with:
     // - a fixed number of decimal places, and
@@ -104,6 +311,52 @@ pub fn format_number(n: f64, decimals: usize) -> String {
     }
 }
 
+/// Parse a numeric string and format it with commas and two decimals.
+/// Anything that does not parse to a number `format_number` can print
+/// (text, or a value too large for `f64` or for its `i64` integer part) is
+/// returned unchanged rather than shown as 0 or `inf`.
+pub fn parse_and_format(s: &str) -> String {
+    match s.replace(',', "").parse::<f64>() {
+        Ok(v) if v.is_finite() && v.abs() < i64::MAX as f64 => format_number(v, 2),
+        _ => s.to_string(),
+    }
+}
+
+/// Format `n` in a short human-readable form with a magnitude suffix,
+/// e.g. `1,234,567,890.0` becomes `1.23B` and `-4,500,000` becomes `-4.50M`.
+///
+/// Values below one thousand are printed with `decimals` places and no
+/// suffix. This is only meant for headlines; CSV and JSON outputs keep the
+/// full figures.
+pub fn format_number_compact(n: f64, decimals: usize) -> String {
+    const UNITS: [(f64, &str); 5] = [(1.0, ""), (1e3, "K"), (1e6, "M"), (1e9, "B"), (1e12, "T")];
+    let abs_n = n.abs();
+    let mut unit = UNITS
+        .iter()
+        .rposition(|(scale, _)| abs_n >= *scale)
+        .unwrap_or(0);
+    // The suffix follows the value as printed, so rounding 999,999 up at
+    // two decimals gives `1.00M` rather than `1000.00K`.
+    loop {
+        let (scale, suffix) = UNITS[unit];
+        let text = format!("{:.*}", decimals, n / scale);
+        let rounds_up = text
+            .trim_start_matches('-')
+            .parse::<f64>()
+            .is_ok_and(|v| v >= 1000.0);
+        if !rounds_up || unit + 1 == UNITS.len() {
+            return format!("{}{}", text, suffix);
+        }
+        unit += 1;
+    }
+}
+
+/// `count` items per second over `secs` seconds, or `None` when the
+/// duration is zero, negative or not finite (too short to measure).
+pub fn rate_per_sec(count: usize, secs: f64) -> Option<f64> {
+    (secs.is_finite() && secs > 0.0).then(|| count as f64 / secs)
+}
+
 pub fn format_int<T>(n: T) -> String
 where
     T: ToFormattedString,
@@ -112,3 +365,404 @@ where
     // for counts in console messages (e.g., `9,855 rows loaded`).
     n.to_formatted_string(&Locale::en)
 }
+
+/// The number of pages of `page_size` items needed for `len` items (the
+/// last page may be partial). A zero `page_size` counts as 1.
+pub fn page_count(len: usize, page_size: usize) -> usize {
+    len.div_ceil(page_size.max(1))
+}
+
+/// The items on 0-based page `page` when `items` is split into pages of
+/// `page_size`; empty past the last page. A zero `page_size` counts as 1.
+pub fn page<T>(items: &[T], page_size: usize, page: usize) -> &[T] {
+    let size = page_size.max(1);
+    let start = page.saturating_mul(size).min(items.len());
+    let end = start.saturating_add(size).min(items.len());
+    &items[start..end]
+}
+
+/// Split a sorted slice into its first `n` and last `n` items.
+///
+/// The two halves never overlap: when the slice holds fewer than `2 * n`
+/// items, the tail only gets what is left after the head.
+pub fn head_tail<T>(items: &[T], n: usize) -> (&[T], &[T]) {
+    let head_end = n.min(items.len());
+    let tail_start = items.len().saturating_sub(n).max(head_end);
+    (&items[..head_end], &items[tail_start..])
+}
+
+/// `s` as a file name stem that is safe on every platform: ASCII letters,
+/// digits, `-` and `_` are kept, every run of anything else becomes one
+/// `_`, and leading/trailing `_` are trimmed. `"Region IV-A"` becomes
+/// `Region_IV-A`; a name with nothing usable becomes `_`.
+pub fn safe_file_name(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            out.push(c);
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    let trimmed = out.trim_matches('_');
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// `n` evenly spaced indices into a slice of length `len`, from the first
+/// item to the last. Returns every index when `n >= len`.
+pub fn sample_indices(len: usize, n: usize) -> Vec<usize> {
+    if n >= len {
+        return (0..len).collect();
+    }
+    match n {
+        0 => Vec::new(),
+        1 => vec![0],
+        _ => (0..n).map(|i| i * (len - 1) / (n - 1)).collect(),
+    }
+}
+
+/// Normalize a name for matching: trimmed, inner whitespace collapsed to
+/// single spaces, and upper-cased (`" region  iv-a"` -> `"REGION IV-A"`).
+pub fn canonical_name(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// The main island group of a DPWH region, matched on `canonical_name`
+/// against the region names in the dataset and their common short forms
+/// (`NCR`, `CAR`, `MIMAROPA`, ...). `None` for a region not in the table.
+pub fn island_for_region(region: &str) -> Option<&'static str> {
+    let island = match canonical_name(region).as_str() {
+        "NATIONAL CAPITAL REGION"
+        | "NCR"
+        | "CORDILLERA ADMINISTRATIVE REGION"
+        | "CAR"
+        | "REGION I"
+        | "REGION II"
+        | "REGION III"
+        | "REGION IV-A"
+        | "CALABARZON"
+        | "REGION IV-B"
+        | "MIMAROPA"
+        | "REGION V" => "Luzon",
+        "REGION VI" | "REGION VII" | "REGION VIII" | "NEGROS ISLAND REGION" | "NIR" => "Visayas",
+        "REGION IX" | "REGION X" | "REGION XI" | "REGION XII" | "REGION XIII" | "CARAGA"
+        | "BARMM" | "ARMM" => "Mindanao",
+        _ => return None,
+    };
+    Some(island)
+}
+
+/// The member firms of a joint-venture contractor name, split on `/`
+/// (`"ABC CORP / XYZ BUILDERS JV"` -> `["ABC CORP", "XYZ BUILDERS"]`). A
+/// trailing `JV` marker is dropped. `&` is not a separator: it appears
+/// inside many single firms' names (e.g. `CONSTRUCTION & SUPPLY`). A name
+/// without `/` comes back as its only member.
+pub fn joint_venture_members(name: &str) -> Vec<String> {
+    let parts: Vec<String> = name
+        .split('/')
+        .map(|part| {
+            let part = part.trim();
+            let cut = part.len().saturating_sub(3);
+            match part.get(cut..) {
+                Some(tail) if tail.eq_ignore_ascii_case(" jv") => {
+                    part[..cut].trim_end().to_string()
+                }
+                _ => part.to_string(),
+            }
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        vec![name.to_string()]
+    } else {
+        parts
+    }
+}
+
+/// Hex SHA-256 digest of the file at `path`, streamed through the hasher
+/// so the whole file is never held in memory.
+pub fn sha256_file(path: &str) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn known_trailing_units_are_stripped() {
+        let units = strings(&["PHP", "days"]);
+        assert_eq!(
+            parse_f64_with_units(Some("1234.56 PHP"), &[], &units),
+            (Some(1234.56), true)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some("500 days"), &[], &units),
+            (Some(500.0), true)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some("1,234.56"), &[], &units),
+            (Some(1234.56), false)
+        );
+    }
+
+    #[test]
+    fn unrecognized_unit_is_still_rejected() {
+        let units = strings(&["PHP", "days"]);
+        assert_eq!(
+            parse_f64_with_units(Some("500 xyz"), &[], &units),
+            (None, false)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some("500 days"), &[], &[]),
+            (None, false)
+        );
+    }
+
+    #[test]
+    fn head_tail_selects_both_ends_without_overlap() {
+        let regions = ["A", "B", "C", "D", "E", "F", "G"];
+        assert_eq!(
+            head_tail(&regions, 3),
+            (&["A", "B", "C"][..], &["E", "F", "G"][..])
+        );
+        // Fewer than 2n items: the tail only gets what the head left.
+        assert_eq!(
+            head_tail(&regions[..4], 3),
+            (&["A", "B", "C"][..], &["D"][..])
+        );
+        assert_eq!(head_tail(&regions[..2], 3), (&["A", "B"][..], &[][..]));
+        assert_eq!(head_tail(&regions, 0), (&[][..], &[][..]));
+    }
+
+    #[test]
+    fn compact_numbers_use_magnitude_suffixes() {
+        assert_eq!(format_number_compact(1_234_567_890.0, 2), "1.23B");
+        assert_eq!(format_number_compact(-4_500_000.0, 2), "-4.50M");
+        assert_eq!(format_number_compact(2_500_000_000_000.0, 1), "2.5T");
+        assert_eq!(format_number_compact(1_000.0, 2), "1.00K");
+        assert_eq!(format_number_compact(999.5, 2), "999.50");
+        assert_eq!(format_number_compact(-12.0, 0), "-12");
+    }
+
+    #[test]
+    fn compact_numbers_pick_the_suffix_after_rounding() {
+        assert_eq!(format_number_compact(999.999, 2), "1.00K");
+        assert_eq!(format_number_compact(999_999.0, 2), "1.00M");
+        assert_eq!(format_number_compact(-999_999.0, 2), "-1.00M");
+        assert_eq!(format_number_compact(999_999_999.0, 2), "1.00B");
+        assert_eq!(format_number_compact(999_999_999_999.0, 2), "1.00T");
+        assert_eq!(format_number_compact(999_999.0, 0), "1M");
+        // Just below each boundary the smaller suffix still fits.
+        assert_eq!(format_number_compact(999_994.0, 2), "999.99K");
+        assert_eq!(format_number_compact(999_994_000.0, 2), "999.99M");
+        // Nothing is larger than T, so it keeps growing.
+        assert_eq!(format_number_compact(1_500e12, 1), "1500.0T");
+    }
+
+    #[test]
+    fn median_ignores_non_finite_values() {
+        assert_eq!(median(vec![3.0, f64::NAN, 1.0, 2.0]), 2.0);
+        assert_eq!(median(vec![4.0, 1.0, f64::NAN, 3.0, 2.0]), 2.5);
+        assert_eq!(median(vec![f64::INFINITY, 5.0, f64::NEG_INFINITY]), 5.0);
+        assert_eq!(median(vec![f64::NAN]), 0.0);
+    }
+
+    #[test]
+    fn samples_are_evenly_spaced_from_first_to_last() {
+        assert_eq!(sample_indices(10, 4), [0, 3, 6, 9]);
+        assert_eq!(sample_indices(101, 5), [0, 25, 50, 75, 100]);
+        assert_eq!(sample_indices(3, 5), [0, 1, 2]);
+        assert_eq!(sample_indices(7, 1), [0]);
+        assert!(sample_indices(7, 0).is_empty());
+    }
+
+    #[test]
+    fn safe_ratio_zero_non_finite_and_normal() {
+        assert_eq!(safe_ratio(5.0, 0.0), 0.0);
+        assert_eq!(safe_ratio(0.0, 0.0), 0.0);
+        assert_eq!(safe_ratio(5.0, -0.0), 0.0);
+        assert_eq!(safe_ratio(f64::NAN, 2.0), 0.0);
+        assert_eq!(safe_ratio(f64::INFINITY, 2.0), 0.0);
+        assert_eq!(safe_ratio(1.0, f64::NAN), 0.0);
+        assert_eq!(safe_ratio(f64::MAX, 0.5), 0.0);
+        assert_eq!(safe_ratio(3.0, 4.0), 0.75);
+        assert_eq!(safe_ratio(-9.0, 3.0), -3.0);
+        assert_eq!(safe_ratio(1.0, f64::INFINITY), 0.0);
+    }
+
+    #[test]
+    fn gini_of_equal_and_concentrated_spending() {
+        assert!(gini(&[250.0; 8]).abs() < 1e-12);
+        // One of n takes everything: (n - 1) / n, approaching 1.
+        let mut one_takes_all = vec![0.0; 999];
+        one_takes_all.push(1e9);
+        assert!((gini(&one_takes_all) - 0.999).abs() < 1e-9);
+        assert_eq!(gini(&[]), 0.0);
+        assert_eq!(gini(&[0.0, 0.0]), 0.0);
+        // Negative and non-finite values are ignored.
+        assert!(gini(&[-5.0, f64::NAN, 10.0, 10.0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn working_days_skip_the_weekend() {
+        let date = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+        // Friday 7 June to Monday 10 June.
+        assert_eq!(days_diff(date(7), date(10)), 3.0);
+        assert_eq!(working_days_diff(date(7), date(10)), 1.0);
+        // Two full weeks.
+        assert_eq!(days_diff(date(3), date(17)), 14.0);
+        assert_eq!(working_days_diff(date(3), date(17)), 10.0);
+        // Saturday to Sunday has no working day; reversed spans are negative.
+        assert_eq!(working_days_diff(date(8), date(9)), 0.0);
+        assert_eq!(working_days_diff(date(10), date(7)), -1.0);
+    }
+
+    #[test]
+    fn currency_rounding_half_even_and_half_up() {
+        // 2.345 is stored as 2.34500000000000019..., so both modes round up.
+        assert_eq!(round_currency(2.345, 2, RoundingMode::HalfEven), 2.35);
+        assert_eq!(round_currency(2.345, 2, RoundingMode::HalfUp), 2.35);
+        // 2.675 is stored as 2.67499...; only half-up reads it as a tie.
+        assert_eq!(round_currency(2.675, 2, RoundingMode::HalfEven), 2.67);
+        assert_eq!(round_currency(2.675, 2, RoundingMode::HalfUp), 2.68);
+        assert_eq!(round_currency(-2.675, 2, RoundingMode::HalfUp), -2.68);
+        // Exact ties: half-even goes to the even digit, half-up away from zero.
+        assert_eq!(round_currency(0.125, 2, RoundingMode::HalfEven), 0.12);
+        assert_eq!(round_currency(0.125, 2, RoundingMode::HalfUp), 0.13);
+        assert_eq!(round_currency(2.5, 0, RoundingMode::HalfEven), 2.0);
+        assert_eq!(round_currency(2.5, 0, RoundingMode::HalfUp), 3.0);
+        // Values already at the precision are unchanged in both modes.
+        for mode in [RoundingMode::HalfEven, RoundingMode::HalfUp] {
+            assert_eq!(round_currency(2.34, 2, mode), 2.34);
+            assert!(round_currency(f64::NAN, 2, mode).is_nan());
+        }
+    }
+
+    #[test]
+    fn currency_prefixes_are_stripped_before_parsing() {
+        let prefixes = strings(&["\u{20b1}", "PHP"]);
+        assert_eq!(
+            parse_f64_with_units(Some("\u{20b1}1,234.56"), &prefixes, &[]),
+            (Some(1234.56), true)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some("PHP 1234"), &prefixes, &[]),
+            (Some(1234.0), true)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some(" php 1234 "), &prefixes, &[]),
+            (Some(1234.0), true)
+        );
+        // Junk after a recognized prefix, or without one, is still rejected.
+        assert_eq!(
+            parse_f64_with_units(Some("PHP abc"), &prefixes, &[]),
+            (None, false)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some("USD 1234"), &prefixes, &[]),
+            (None, false)
+        );
+        assert_eq!(
+            parse_f64_with_units(Some("\u{20b1}1,234.56"), &[], &[]),
+            (None, false)
+        );
+    }
+
+    #[test]
+    fn sha256_matches_the_fips_test_vectors() {
+        let digest = |bytes: &[u8]| {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            std::io::Write::write_all(&mut file, bytes).unwrap();
+            sha256_file(file.path().to_str().unwrap()).unwrap()
+        };
+        assert_eq!(
+            digest(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            digest(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // 56 bytes: the length no longer fits in the last block.
+        assert_eq!(
+            digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        // Spans several 64 KiB reads.
+        assert_eq!(
+            digest(&[b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn pages_split_at_boundaries_with_a_partial_last_page() {
+        let rows: Vec<usize> = (1..=7).collect();
+        assert_eq!(page_count(rows.len(), 3), 3);
+        assert_eq!(page(&rows, 3, 0), [1, 2, 3]);
+        assert_eq!(page(&rows, 3, 1), [4, 5, 6]);
+        assert_eq!(page(&rows, 3, 2), [7]);
+        assert!(page(&rows, 3, 3).is_empty());
+        assert!(page(&rows, 3, usize::MAX).is_empty());
+
+        // An exact multiple has no partial page.
+        assert_eq!(page_count(6, 3), 2);
+        assert_eq!(page(&rows[..6], 3, 1), [4, 5, 6]);
+        // One page holding everything, and no pages for no rows.
+        assert_eq!(page_count(rows.len(), 10), 1);
+        assert_eq!(page(&rows, 10, 0), rows.as_slice());
+        assert_eq!(page_count(0, 3), 0);
+        // A zero page size pages one row at a time.
+        assert_eq!(page_count(rows.len(), 0), 7);
+        assert_eq!(page(&rows, 0, 6), [7]);
+    }
+
+    #[test]
+    fn unparseable_savings_are_shown_as_is_not_as_zero() {
+        assert_eq!(parse_and_format("1234567.891"), "1,234,567.89");
+        assert_eq!(parse_and_format("-1,500.5"), "-1,500.50");
+        for raw in ["12.34.56", "N/A", "", "1e999", "1e300", "NaN"] {
+            assert_eq!(parse_and_format(raw), raw);
+        }
+    }
+
+    #[test]
+    fn float_looking_years_parse_only_with_a_zero_fraction() {
+        assert_eq!(parse_year_safe(Some("2021")), Some(2021));
+        assert_eq!(parse_year_safe(Some("2021.0")), Some(2021));
+        assert_eq!(parse_year_safe(Some(" 2022.00 ")), Some(2022));
+        // Not rounded to 2022: the row is dropped as a bad year.
+        assert_eq!(parse_year_safe(Some("2021.7")), None);
+        assert_eq!(parse_year_safe(Some("2021.")), None);
+        assert_eq!(parse_year_safe(Some(".0")), None);
+        assert_eq!(parse_year_safe(None), None);
+    }
+
+    #[test]
+    fn rate_guards_against_a_zero_duration() {
+        assert_eq!(rate_per_sec(9855, 0.5), Some(19710.0));
+        assert_eq!(rate_per_sec(0, 2.0), Some(0.0));
+        for secs in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(rate_per_sec(9855, secs), None);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Reproducibility check: run the full pipeline over the checked-in
+// `golden/fixture.csv` and byte-compare the report CSVs and
+// `summary.json` with the golden files next to it.
+//
+// After an intended output change, regenerate the golden files with
+// `UPDATE_GOLDEN=1 cargo test --test golden` and review their diff.
+use chrono::NaiveDate;
+use rust_report::config::Options;
+use rust_report::{loader, run_analysis, write_reports, REPORT_FILES};
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[test]
+fn outputs_match_golden_files() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden");
+    let out_dir = tempfile::tempdir().unwrap();
+    let mut options = Options::default();
+    // summary.json counts days since the latest completion from this date,
+    // so it must be fixed for the output to be reproducible.
+    options.output.as_of = NaiveDate::from_ymd_opt(2025, 6, 30);
+    options.output.prefix = out_dir.path().join("run").to_string_lossy().into_owned();
+
+    let fixture = golden.join("fixture.csv");
+    let (data, load_report) = loader::load(fixture.to_str().unwrap(), &options.load).unwrap();
+    let result = run_analysis(&data, Some(load_report), &options);
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+
+    let mut differences = Vec::new();
+    for ((file, written), base) in write_reports(&result, &options)
+        .into_iter()
+        .zip(REPORT_FILES)
+    {
+        written.unwrap();
+        let golden_file = golden.join(base);
+        if update {
+            std::fs::copy(&file, &golden_file).unwrap();
+        } else if let Some(diff) = golden_diff(&file, &golden_file).unwrap() {
+            differences.push(format!("{}: {}", base, diff));
+        }
+    }
+    assert!(
+        differences.is_empty(),
+        "outputs differ from the golden files (rerun with UPDATE_GOLDEN=1 if intended):\n{}",
+        differences.join("\n")
+    );
+}
+
+/// Compare the file at `produced` with the golden file at `golden` byte
+/// for byte. Returns `None` when they are identical, otherwise what
+/// differs: a missing golden file, the first differing line, or a length
+/// difference when one file is a prefix of the other.
+fn golden_diff(produced: &str, golden: &Path) -> std::io::Result<Option<String>> {
+    let actual = std::fs::read(produced)?;
+    let expected = match std::fs::read(golden) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Ok(Some(format!("golden file {} is missing", golden.display())))
+        }
+        Err(e) => return Err(e),
+    };
+    if actual == expected {
+        return Ok(None);
+    }
+    let differing = actual
+        .split(|&b| b == b'\n')
+        .zip(expected.split(|&b| b == b'\n'))
+        .position(|(a, e)| a != e);
+    Ok(Some(match differing {
+        Some(i) => format!("first difference on line {}", i + 1),
+        None => format!("{} bytes, golden file has {}", actual.len(), expected.len()),
+    }))
+}